@@ -0,0 +1,91 @@
+//! Rendering benchmark: times the rendering of rustdoc json files by the rd binary and
+//! prints the `--stats` report of each one
+//!
+//! `cargo bench --bench render -- [FILE...]`, the demo fixture being rendered when no
+//! file is given and `RD_BENCH_RUNS` setting the number of timed runs (10 by default)
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const DEFAULT_RUNS: usize = 10;
+
+fn rd(input: &Path, output: &Path, stats: bool) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rd"));
+    command
+        .arg(input)
+        .arg("--output")
+        .arg(output)
+        .arg("--deterministic")
+        .arg("--clean")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+    if stats {
+        command.arg("--stats");
+    } else {
+        command.stdout(Stdio::null());
+    }
+    command
+}
+
+fn bench(input: &Path, runs: usize) -> Result<(), String> {
+    let output = std::env::temp_dir().join(format!("rd-bench-{}", std::process::id()));
+
+    // A first run, untimed, warming the caches and reporting the statistics
+    let status = rd(input, &output, true)
+        .status()
+        .map_err(|err| err.to_string())?;
+    if !status.success() {
+        return Err(format!("rd failed to render {:?}: {}", input, status));
+    }
+
+    let mut times = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        let status = rd(input, &output, false)
+            .status()
+            .map_err(|err| err.to_string())?;
+        times.push(start.elapsed());
+        if !status.success() {
+            return Err(format!("rd failed to render {:?}: {}", input, status));
+        }
+    }
+    let _ = std::fs::remove_dir_all(&output);
+
+    times.sort();
+    let total: Duration = times.iter().sum();
+    println!(
+        "{}: {} runs, min {:.1?}, median {:.1?}, max {:.1?}, mean {:.1?}",
+        input.display(),
+        runs,
+        times[0],
+        times[runs / 2],
+        times[runs - 1],
+        total / runs as u32,
+    );
+    Ok(())
+}
+
+fn main() {
+    // Skip the `--bench` flag (and the others) passed by cargo
+    let mut inputs: Vec<PathBuf> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .collect();
+    if inputs.is_empty() {
+        inputs.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/demo.json"));
+    }
+    let runs = match std::env::var("RD_BENCH_RUNS") {
+        Ok(runs) => runs.parse().expect("RD_BENCH_RUNS isn't a number"),
+        Err(_) => DEFAULT_RUNS,
+    };
+    assert!(runs > 0, "RD_BENCH_RUNS must be at least 1");
+
+    for input in &inputs {
+        if let Err(err) = bench(input, runs) {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Configuration file (`rd.toml`) giving the defaults of some of the options, the ones
+//! given on the command line taking precedence, and the settings only set there
+
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use std::path::Path;
+use tracing::info;
+
+use crate::{Opt, Section};
+
+/// Configuration file looked up in the current directory when `--config` isn't given
+const DEFAULT_CONFIG: &str = "rd.toml";
+
+/// Sections starting expanded when neither the command line nor the configuration say
+const DEFAULT_OPEN_SECTIONS: [Section; 2] = [Section::Methods, Section::TraitItems];
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    open_sections: Option<Vec<Section>>,
+    collapse_threshold: Option<usize>,
+    #[serde(default)]
+    syntax_classes: SyntaxClasses,
+}
+
+/// Classes of the tokens of the signatures, remappable to match the css of a portal:
+///
+/// ```toml
+/// [syntax-classes]
+/// kw = "hl-keyword"
+/// ponct = "hl-punctuation"
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub(crate) struct SyntaxClasses {
+    pub(crate) ident: String,
+    pub(crate) kw: String,
+    pub(crate) ponct: String,
+    pub(crate) primitive: String,
+    pub(crate) attr: String,
+    pub(crate) where_clause: String,
+}
+
+impl Default for SyntaxClasses {
+    fn default() -> Self {
+        SyntaxClasses {
+            ident: "ident".to_string(),
+            kw: "kw".to_string(),
+            ponct: "ponct".to_string(),
+            primitive: "primitive".to_string(),
+            attr: "attr".to_string(),
+            where_clause: "where-clause".to_string(),
+        }
+    }
+}
+
+impl SyntaxClasses {
+    /// Check that the classes can be written as is in the `class` attributes
+    fn validate(&self) -> Result<()> {
+        for class in [
+            &self.ident,
+            &self.kw,
+            &self.ponct,
+            &self.primitive,
+            &self.attr,
+            &self.where_clause,
+        ] {
+            if class.trim().is_empty() || class.contains(['"', '\'', '<', '>', '&']) {
+                anyhow::bail!("invalid syntax class {:?}", class);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fill the options not given on the command line from the configuration file, or else
+/// with their defaults
+pub(crate) fn apply(opt: &mut Opt) -> Result<()> {
+    let config = match &opt.config {
+        Some(path) => load(path)?,
+        None if Path::new(DEFAULT_CONFIG).is_file() => load(Path::new(DEFAULT_CONFIG))?,
+        None => Config::default(),
+    };
+    apply_config(opt, config)
+}
+
+/// Fill the options not given on the command line with their defaults, ignoring the
+/// configuration files, for the renders that must not depend on the current directory
+#[cfg(test)]
+pub(crate) fn apply_defaults(opt: &mut Opt) -> Result<()> {
+    apply_config(opt, Config::default())
+}
+
+fn apply_config(opt: &mut Opt, config: Config) -> Result<()> {
+    if opt.open_sections.is_empty() {
+        opt.open_sections = config
+            .open_sections
+            .unwrap_or_else(|| DEFAULT_OPEN_SECTIONS.to_vec());
+    }
+    if opt.collapse_threshold.is_none() {
+        opt.collapse_threshold = config.collapse_threshold;
+    }
+    config.syntax_classes.validate()?;
+    opt.syntax_classes = config.syntax_classes;
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Config> {
+    info!("reading the configuration file {:?}", path);
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read the configuration file {:?}", path))?;
+    toml::from_str(&content).with_context(|| format!("invalid configuration file {:?}", path))
+}
@@ -0,0 +1,164 @@
+//! Machine-readable extraction of the public API (`rd extract`): one json object per item
+//! with its path, kind, signature and documentation, to feed the docs to other tools
+//! (search, embeddings, ...) without parsing the html
+
+use anyhow::{Context as _, Result};
+use rustdoc_types::*;
+use serde::Serialize;
+use std::io::Write;
+
+use crate::pp;
+
+/// A line of the output
+#[derive(Serialize)]
+struct Entry<'krate> {
+    path: &'krate str,
+    kind: &'static str,
+    /// Plain-text signature, on a single line
+    signature: Option<String>,
+    /// Raw markdown of the documentation
+    docs: Option<&'krate str>,
+}
+
+/// Write one json object per line for each item of the crate, module by module
+pub(crate) fn write_jsonl(krate: &Crate, writer: &mut impl Write) -> Result<()> {
+    let root = get(krate, &krate.root)?;
+    let name = root.name.as_deref().context("expect a crate name")?;
+
+    write_item(krate, writer, name, root, false)
+}
+
+fn get<'krate>(krate: &'krate Crate, id: &Id) -> Result<&'krate Item> {
+    krate
+        .index
+        .get(id)
+        .with_context(|| format!("unable to find the item {:?}", id))
+}
+
+/// Write the item and then its children (items of modules, fields, variants, associated
+/// items of traits and inherent impls)
+fn write_item(
+    krate: &Crate,
+    writer: &mut impl Write,
+    path: &str,
+    item: &Item,
+    associated: bool,
+) -> Result<()> {
+    let signature = match &item.inner {
+        ItemEnum::Module(_) => None,
+        _ => pp::Tokens::from_item(item, &krate.index)
+            .ok()
+            .map(|tokens| {
+                tokens
+                    .to_string()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }),
+    };
+    let entry = Entry {
+        path,
+        kind: kind(item, associated),
+        signature,
+        docs: item.docs.as_deref(),
+    };
+    serde_json::to_writer(&mut *writer, &entry)?;
+    writeln!(writer)?;
+
+    let (children, associated): (Vec<&Id>, bool) = match &item.inner {
+        ItemEnum::Module(module) => (
+            module
+                .items
+                .iter()
+                .filter(|id| {
+                    !matches!(
+                        krate.index.get(id).map(|item| &item.inner),
+                        Some(ItemEnum::Import(_) | ItemEnum::ExternCrate { .. })
+                    )
+                })
+                .collect(),
+            false,
+        ),
+        ItemEnum::Struct(Struct {
+            kind: StructKind::Plain { fields, .. },
+            ..
+        })
+        | ItemEnum::Union(Union { fields, .. }) => (fields.iter().collect(), false),
+        ItemEnum::Struct(Struct {
+            kind: StructKind::Tuple(fields),
+            ..
+        }) => (fields.iter().flatten().collect(), false),
+        ItemEnum::Enum(enum_) => (enum_.variants.iter().collect(), false),
+        ItemEnum::Trait(trait_) => (trait_.items.iter().collect(), true),
+        _ => (Vec::new(), false),
+    };
+    let mut children = children
+        .into_iter()
+        .map(|id| get(krate, id))
+        .collect::<Result<Vec<_>>>()?;
+    if matches!(item.inner, ItemEnum::Module(_)) {
+        children.sort_by(|x, y| x.name.cmp(&y.name));
+    }
+    for child in children {
+        let name = child.name.as_deref().unwrap_or_default();
+        write_item(
+            krate,
+            writer,
+            &format!("{}::{}", path, name),
+            child,
+            associated,
+        )?;
+    }
+
+    let impls = match &item.inner {
+        ItemEnum::Struct(Struct { impls, .. })
+        | ItemEnum::Enum(Enum { impls, .. })
+        | ItemEnum::Union(Union { impls, .. }) => impls.as_slice(),
+        _ => &[],
+    };
+    for id in impls {
+        if let ItemEnum::Impl(Impl {
+            trait_: None,
+            items,
+            ..
+        }) = &get(krate, id)?.inner
+        {
+            for id in items {
+                let child = get(krate, id)?;
+                let name = child.name.as_deref().unwrap_or_default();
+                write_item(krate, writer, &format!("{}::{}", path, name), child, true)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Kind of the item, functions of traits and impls being methods
+pub(crate) fn kind(item: &Item, associated: bool) -> &'static str {
+    match &item.inner {
+        ItemEnum::Module(_) => "module",
+        ItemEnum::ExternCrate { .. } => "extern_crate",
+        ItemEnum::Import(_) => "import",
+        ItemEnum::Union(_) => "union",
+        ItemEnum::Struct(_) => "struct",
+        ItemEnum::StructField(_) => "field",
+        ItemEnum::Enum(_) => "enum",
+        ItemEnum::Variant(_) => "variant",
+        ItemEnum::Function(_) if associated => "method",
+        ItemEnum::Function(_) => "function",
+        ItemEnum::Trait(_) => "trait",
+        ItemEnum::TraitAlias(_) => "trait_alias",
+        ItemEnum::Impl(_) => "impl",
+        ItemEnum::TypeAlias(_) => "type_alias",
+        ItemEnum::OpaqueTy(_) => "opaque_type",
+        ItemEnum::Constant { .. } => "constant",
+        ItemEnum::Static(_) => "static",
+        ItemEnum::ForeignType => "foreign_type",
+        ItemEnum::Macro(_) => "macro",
+        ItemEnum::ProcMacro(_) => "proc_macro",
+        ItemEnum::Primitive(_) => "primitive",
+        ItemEnum::AssocConst { .. } => "assoc_const",
+        ItemEnum::AssocType { .. } => "assoc_type",
+    }
+}
@@ -0,0 +1,299 @@
+//! Golden tests of the html output: every `tests/fixtures/<name>.json` rustdoc json file
+//! is rendered and compared with the expected (normalized) pages under `tests/fixtures/<name>/`.
+//!
+//! Run with `RD_BLESS=1 cargo test` to (re)generate the expected pages after an intended change.
+
+use anyhow::{Context as _, Result};
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{config, render_file, Cli};
+
+/// Env-var that, when set, overwrites the expected pages with the actual ones
+const BLESS_ENV: &str = "RD_BLESS";
+
+/// Put every tag on its own line so that the differences are readable
+fn normalize(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len() * 11 / 10);
+    for line in content.replace("\r\n", "\n").lines() {
+        let line = line.trim_end();
+        for (index, part) in line.split('<').enumerate() {
+            if index != 0 {
+                normalized.push('\n');
+                normalized.push('<');
+            }
+            normalized.push_str(part);
+        }
+        normalized.push('\n');
+    }
+    normalized
+}
+
+/// All the files under `dir`, relative to it and sorted
+fn files_under(dir: &Path) -> Result<Vec<PathBuf>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir).with_context(|| format!("unable to read {:?}", dir))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else {
+                files.push(path.strip_prefix(root)?.to_path_buf());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    if dir.exists() {
+        walk(dir, dir, &mut files)?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Render a fixture and compare (or bless) its output, returning the mismatches
+fn check_fixture(fixture: &Path, bless: bool) -> Result<Vec<String>> {
+    let name = fixture.file_stem().context("fixture without a name")?;
+    let expected_dir = fixture.with_extension("");
+    let output = std::env::temp_dir().join(format!(
+        "rd-golden-{}-{}",
+        std::process::id(),
+        name.to_string_lossy()
+    ));
+    let _ = fs::remove_dir_all(&output);
+    fs::create_dir_all(&output)?;
+
+    let cli = Cli::try_parse_from([
+        "rd".as_ref(),
+        "--deterministic".as_ref(),
+        "--output".as_ref(),
+        output.as_os_str(),
+        fixture.as_os_str(),
+    ])?;
+    let mut opt = cli.opt.context("missing options")?;
+    // Not depending on a `rd.toml` of the current directory
+    config::apply_defaults(&mut opt)?;
+    render_file(&opt, fixture).with_context(|| format!("unable to render {:?}", fixture))?;
+
+    let actual_files = files_under(&output)?;
+    let mut mismatches = Vec::new();
+
+    if bless {
+        let _ = fs::remove_dir_all(&expected_dir);
+        for file in &actual_files {
+            let expected = expected_dir.join(file);
+            fs::create_dir_all(expected.parent().unwrap())?;
+            fs::write(expected, normalize(&fs::read_to_string(output.join(file))?))?;
+        }
+    } else {
+        let expected_files = files_under(&expected_dir)?;
+        for file in expected_files.iter().filter(|f| !actual_files.contains(f)) {
+            mismatches.push(format!("{}: missing from the output", file.display()));
+        }
+        for file in &actual_files {
+            if !expected_files.contains(file) {
+                mismatches.push(format!("{}: unexpected in the output", file.display()));
+                continue;
+            }
+
+            let actual = normalize(&fs::read_to_string(output.join(file))?);
+            let expected = fs::read_to_string(expected_dir.join(file))?;
+            if let Some((line, (actual, expected))) = actual
+                .lines()
+                .zip(expected.lines())
+                .enumerate()
+                .find(|(_, (actual, expected))| actual != expected)
+            {
+                mismatches.push(format!(
+                    "{}:{}: expected `{}`, got `{}`",
+                    file.display(),
+                    line + 1,
+                    expected,
+                    actual
+                ));
+            } else if actual.lines().count() != expected.lines().count() {
+                mismatches.push(format!("{}: different number of lines", file.display()));
+            }
+        }
+    }
+
+    fs::remove_dir_all(&output)?;
+    Ok(mismatches)
+}
+
+#[test]
+fn golden() -> Result<()> {
+    let bless = std::env::var_os(BLESS_ENV).is_some();
+    let fixtures_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    let mut fixtures = fs::read_dir(&fixtures_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter(|path| !matches!(path, Ok(path) if path.extension() != Some("json".as_ref())))
+        .collect::<Result<Vec<_>, _>>()?;
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "no fixtures in {:?}", fixtures_dir);
+
+    let mut failures = String::new();
+    for fixture in &fixtures {
+        let name = fixture.strip_prefix(&fixtures_dir)?.display();
+        for mismatch in check_fixture(fixture, bless)? {
+            failures.push_str(&format!("{}: {}\n", name, mismatch));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "the html output changed (run with {}=1 to bless):\n{}",
+        BLESS_ENV,
+        failures
+    );
+    Ok(())
+}
+
+#[test]
+fn path_template_renders_the_fixture() -> Result<()> {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/demo.json");
+    let output =
+        std::env::temp_dir().join(format!("rd-golden-{}-path-template", std::process::id()));
+    let _ = fs::remove_dir_all(&output);
+
+    let cli = Cli::try_parse_from([
+        "rd".as_ref(),
+        "--path-template".as_ref(),
+        "{crate}/{path}/{kind}/{name}.html".as_ref(),
+        "--output".as_ref(),
+        output.as_os_str(),
+        input.as_os_str(),
+    ])?;
+    let mut opt = cli.opt.context("missing options")?;
+    config::apply_defaults(&mut opt)?;
+    render_file(&opt, &input)?;
+
+    // `demo::Foo` and `demo::inner::Foo` have their own pages
+    assert!(output.join("demo/index.html").exists());
+    assert!(output.join("demo/struct/Foo.html").exists());
+    assert!(output.join("demo/inner/struct/Foo.html").exists());
+    assert!(output.join("demo/mod/inner.html").exists());
+
+    // Without `{path}`, they would clash
+    assert!(Cli::try_parse_from([
+        "rd",
+        "--path-template",
+        "{crate}/{kind}/{name}.html",
+        "--output",
+        "doc",
+        "demo.json",
+    ])
+    .is_err());
+
+    fs::remove_dir_all(&output)?;
+    Ok(())
+}
+
+#[test]
+fn keep_going_lists_the_failed_items() -> Result<()> {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/demo.json");
+    let mut krate: serde_json::Value = serde_json::from_str(&fs::read_to_string(fixture)?)?;
+    // An impl (never an item of a module) and a dangling id among the items of the crate
+    let root = krate["root"].as_str().context("no root")?.to_string();
+    let impl_id = krate["index"]
+        .as_object()
+        .and_then(|index| {
+            index
+                .iter()
+                .find(|(_, item)| item["inner"].get("impl").is_some())
+        })
+        .map(|(id, _)| id.clone())
+        .context("no impl")?;
+    let items = krate["index"][&root]["inner"]["module"]["items"]
+        .as_array_mut()
+        .context("no module items")?;
+    items.push(impl_id.clone().into());
+    items.push("0:9999".into());
+
+    let dir = std::env::temp_dir().join(format!("rd-golden-{}-keep-going", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    let input = dir.join("demo.json");
+    fs::write(&input, serde_json::to_vec(&krate)?)?;
+    let output = dir.join("doc");
+
+    let cli = Cli::try_parse_from([
+        "rd".as_ref(),
+        "--keep-going".as_ref(),
+        "--output".as_ref(),
+        output.as_os_str(),
+        input.as_os_str(),
+    ])?;
+    let mut opt = cli.opt.context("missing options")?;
+    config::apply_defaults(&mut opt)?;
+    let err = render_file(&opt, &input).expect_err("the impl can't be rendered");
+
+    let message = format!("{:#}", err);
+    assert!(
+        message.contains("1 item of demo failed to render"),
+        "{}",
+        message
+    );
+    assert!(message.contains(&impl_id), "{}", message);
+    // The rest of the crate is rendered
+    assert!(output.join("demo/index.html").exists());
+    assert!(output.join("demo/fn.add.html").exists());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn keep_going_replaces_a_failed_page() -> Result<()> {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/demo.json");
+    let mut krate: serde_json::Value = serde_json::from_str(&fs::read_to_string(fixture)?)?;
+    // A dangling field of `Pair`, failing after its page was created
+    let pair = krate["index"]
+        .as_object_mut()
+        .and_then(|index| {
+            index
+                .values_mut()
+                .find(|item| item["name"].as_str() == Some("Pair"))
+        })
+        .context("no Pair")?;
+    pair["inner"]["struct"]["kind"]["tuple"]
+        .as_array_mut()
+        .context("Pair isn't a tuple struct")?
+        .push("0:99998".into());
+
+    let dir =
+        std::env::temp_dir().join(format!("rd-golden-{}-keep-going-page", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    let input = dir.join("demo.json");
+    fs::write(&input, serde_json::to_vec(&krate)?)?;
+    let output = dir.join("doc");
+
+    let cli = Cli::try_parse_from([
+        "rd".as_ref(),
+        "--keep-going".as_ref(),
+        "--output".as_ref(),
+        output.as_os_str(),
+        input.as_os_str(),
+    ])?;
+    let mut opt = cli.opt.context("missing options")?;
+    config::apply_defaults(&mut opt)?;
+    let err = render_file(&opt, &input).expect_err("Pair can't be rendered");
+
+    let message = format!("{:#}", err);
+    assert!(
+        message.contains("1 item of demo failed to render"),
+        "{}",
+        message
+    );
+    assert!(message.contains("demo::Pair"), "{}", message);
+    // The page of Pair is the placeholder, and the rest of the crate is rendered
+    let page = fs::read_to_string(output.join("demo/struct.Pair.html"))?;
+    assert!(page.contains("0:99998"), "{}", page);
+    assert!(output.join("demo/fn.add.html").exists());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
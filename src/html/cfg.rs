@@ -0,0 +1,253 @@
+//! Evaluation of the `#[cfg(..)]` of the items against the selected features and cfgs
+
+use anyhow::{Context as _, Result};
+use std::collections::HashSet;
+
+/// A parsed cfg predicate, like `all(unix, feature = "serde")`
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Cfg {
+    Name(String),
+    NameValue(String, String),
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    /// Parse a cfg predicate (the inside of `#[cfg(..)]`)
+    pub(super) fn parse(input: &str) -> Result<Self> {
+        let (cfg, rest) = Self::parse_predicate(input)
+            .with_context(|| format!("invalid cfg predicate: {:?}", input))?;
+        if !rest.trim().is_empty() {
+            anyhow::bail!("invalid cfg predicate: {:?}, unexpected {:?}", input, rest);
+        }
+        Ok(cfg)
+    }
+
+    /// Features the predicate can require, i.e. outside of a `not(..)`
+    pub(super) fn features(&self) -> Vec<&str> {
+        match self {
+            Cfg::NameValue(name, value) if name == "feature" => vec![value.as_str()],
+            Cfg::Name(_) | Cfg::NameValue(..) | Cfg::Not(_) => vec![],
+            Cfg::All(cfgs) | Cfg::Any(cfgs) => cfgs.iter().flat_map(Cfg::features).collect(),
+        }
+    }
+
+    fn parse_predicate(input: &str) -> Option<(Self, &str)> {
+        let input = input.trim_start();
+        let end = input
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(input.len());
+        let (name, rest) = input.split_at(end);
+        if name.is_empty() {
+            return None;
+        }
+        let rest = rest.trim_start();
+
+        if let Some(rest) = rest.strip_prefix('(') {
+            let mut predicates = Vec::new();
+            let mut rest = rest.trim_start();
+            while !rest.starts_with(')') {
+                let (predicate, after) = Self::parse_predicate(rest)?;
+                predicates.push(predicate);
+                rest = after.trim_start();
+                rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+            }
+            let rest = &rest[1..];
+
+            let cfg = match name {
+                "all" => Cfg::All(predicates),
+                "any" => Cfg::Any(predicates),
+                "not" if predicates.len() == 1 => Cfg::Not(Box::new(predicates.pop()?)),
+                _ => return None,
+            };
+            Some((cfg, rest))
+        } else if let Some(rest) = rest.strip_prefix('=') {
+            let rest = rest.trim_start().strip_prefix('"')?;
+            let end = rest.find('"')?;
+            Some((
+                Cfg::NameValue(name.to_string(), rest[..end].to_string()),
+                &rest[end + 1..],
+            ))
+        } else {
+            Some((Cfg::Name(name.to_string()), rest))
+        }
+    }
+}
+
+/// The features and cfgs selected with `--features`, `--all-features` and `--cfg`
+#[derive(Default)]
+pub(super) struct CfgSet {
+    /// Enabled features, `None` if all of them are
+    features: Option<HashSet<String>>,
+    /// Whether the features are known, i.e. some were selected
+    known_features: bool,
+    /// Enabled cfgs, like `unix` or `target_os = "linux"`
+    cfgs: HashSet<(String, Option<String>)>,
+}
+
+impl CfgSet {
+    pub(super) fn new(features: &[String], all_features: bool, cfgs: &[String]) -> Result<Self> {
+        let cfgs = cfgs
+            .iter()
+            .map(|cfg| match Cfg::parse(cfg)? {
+                Cfg::Name(name) => Ok((name, None)),
+                Cfg::NameValue(name, value) => Ok((name, Some(value))),
+                _ => anyhow::bail!(
+                    "invalid --cfg {:?}: expected `name` or `name=\"value\"`",
+                    cfg
+                ),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(CfgSet {
+            features: (!all_features).then(|| features.iter().cloned().collect()),
+            known_features: all_features || !features.is_empty(),
+            cfgs,
+        })
+    }
+
+    /// Whether features or cfgs were selected at all
+    pub(super) fn is_empty(&self) -> bool {
+        !self.known_features && self.cfgs.is_empty()
+    }
+
+    /// Evaluate a predicate; `None` when it depends on something that wasn't selected (like the
+    /// features when only cfgs were given), so that it is neither true nor false
+    pub(super) fn eval(&self, cfg: &Cfg) -> Option<bool> {
+        match cfg {
+            Cfg::NameValue(name, value) if name == "feature" => match &self.features {
+                _ if !self.known_features => None,
+                Some(features) => Some(features.contains(value)),
+                None => Some(true),
+            },
+            Cfg::Name(_) | Cfg::NameValue(..) if self.cfgs.is_empty() => None,
+            Cfg::Name(name) => Some(self.cfgs.contains(&(name.clone(), None))),
+            Cfg::NameValue(name, value) => {
+                Some(self.cfgs.contains(&(name.clone(), Some(value.clone()))))
+            }
+            Cfg::All(cfgs) => {
+                let mut all = Some(true);
+                for cfg in cfgs {
+                    match self.eval(cfg) {
+                        Some(false) => return Some(false),
+                        Some(true) => {}
+                        None => all = None,
+                    }
+                }
+                all
+            }
+            Cfg::Any(cfgs) => {
+                let mut any = Some(false);
+                for cfg in cfgs {
+                    match self.eval(cfg) {
+                        Some(true) => return Some(true),
+                        Some(false) => {}
+                        None => any = None,
+                    }
+                }
+                any
+            }
+            Cfg::Not(cfg) => self.eval(cfg).map(|enabled| !enabled),
+        }
+    }
+
+    /// Whether the `#[cfg(..)]` attributes of an item exclude it from the selected features
+    /// and cfgs
+    pub(super) fn is_disabled<T: AsRef<str>>(&self, attrs: &[T]) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        attrs
+            .iter()
+            .filter_map(|attr| {
+                attr.as_ref()
+                    .strip_prefix("#[cfg(")
+                    .and_then(|cfg| cfg.strip_suffix(")]"))
+            })
+            .any(|cfg| match Cfg::parse(cfg) {
+                Ok(cfg) => self.eval(&cfg) == Some(false),
+                Err(err) => {
+                    tracing::warn!("{:#}", err);
+                    false
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> Cfg {
+        Cfg::Name(name.to_string())
+    }
+
+    fn feature(value: &str) -> Cfg {
+        Cfg::NameValue("feature".to_string(), value.to_string())
+    }
+
+    #[test]
+    fn parse_predicates() {
+        assert_eq!(Cfg::parse("unix").unwrap(), name("unix"));
+        assert_eq!(
+            Cfg::parse(r#" feature = "serde" "#).unwrap(),
+            feature("serde")
+        );
+        assert_eq!(
+            Cfg::parse(r#"all(unix, not(feature="std"), any(test, doc,),)"#).unwrap(),
+            Cfg::All(vec![
+                name("unix"),
+                Cfg::Not(Box::new(feature("std"))),
+                Cfg::Any(vec![name("test"), name("doc")]),
+            ])
+        );
+        assert_eq!(Cfg::parse("all()").unwrap(), Cfg::All(vec![]));
+    }
+
+    #[test]
+    fn parse_invalid_predicates() {
+        for input in [
+            "",
+            "unix windows",
+            "all(unix",
+            "not(unix, windows)",
+            "not()",
+            "one_of(unix)",
+            r#"feature = serde"#,
+            r#"feature = "serde"#,
+            "(unix)",
+        ] {
+            assert!(Cfg::parse(input).is_err(), "{:?} was parsed", input);
+        }
+    }
+
+    #[test]
+    fn features_outside_of_not() {
+        let cfg = Cfg::parse(r#"any(feature = "a", all(feature = "b", not(feature = "c")))"#);
+        assert_eq!(cfg.unwrap().features(), ["a", "b"]);
+    }
+
+    #[test]
+    fn eval() {
+        let set = CfgSet::new(&["std".to_string()], false, &["unix".to_string()]).unwrap();
+        let eval = |input| set.eval(&Cfg::parse(input).unwrap());
+        assert_eq!(eval(r#"feature = "std""#), Some(true));
+        assert_eq!(eval(r#"feature = "alloc""#), Some(false));
+        assert_eq!(eval(r#"all(unix, not(feature = "std"))"#), Some(false));
+        assert_eq!(eval(r#"any(windows, feature = "std")"#), Some(true));
+        assert_eq!(eval(r#"target_os = "linux""#), Some(false));
+
+        // Only cfgs given: the features are unknown
+        let set = CfgSet::new(&[], false, &[r#"target_os="linux""#.to_string()]).unwrap();
+        let eval = |input| set.eval(&Cfg::parse(input).unwrap());
+        assert_eq!(eval(r#"target_os = "linux""#), Some(true));
+        assert_eq!(eval(r#"feature = "std""#), None);
+        assert_eq!(eval(r#"all(target_os = "linux", feature = "std")"#), None);
+        assert_eq!(eval(r#"all(windows, feature = "std")"#), Some(false));
+
+        assert!(CfgSet::new(&[], false, &["all(unix)".to_string()]).is_err());
+        assert!(!CfgSet::default().is_disabled(&["#[cfg(windows)]"]));
+    }
+}
@@ -1,10 +1,131 @@
 //! Collections constants used for the html output generation
 
-pub const STYLE_CSS: &str = "style.css";
-pub const RUST_SVG: &str = "rust.svg";
-pub const SEARCH_JS: &str = "search.js";
+/// A static file of rd, written at the top of the output under a name fingerprinted with its
+/// content (`style.1a2b3c4d.css`) so that browsers don't keep a stale version after an upgrade
+pub struct StaticFile {
+    pub name: &'static str,
+    pub content: &'static [u8],
+}
+
+pub const STYLE_CSS: StaticFile = StaticFile {
+    name: "style.css",
+    content: include_bytes!("static/css/style.css"),
+};
+pub const RUST_SVG: StaticFile = StaticFile {
+    name: "rust.svg",
+    content: include_bytes!("static/imgs/rust.svg"),
+};
+pub const SEARCH_JS: StaticFile = StaticFile {
+    name: "search.js",
+    content: include_bytes!("static/js/search.js"),
+};
+pub const SETTINGS_JS: StaticFile = StaticFile {
+    name: "settings.js",
+    content: include_bytes!("static/js/settings.js"),
+};
+pub const TOC_JS: StaticFile = StaticFile {
+    name: "toc.js",
+    content: include_bytes!("static/js/toc.js"),
+};
+pub const REDIRECT_JS: StaticFile = StaticFile {
+    name: "redirect.js",
+    content: include_bytes!("static/js/redirect.js"),
+};
+pub const IMPLS_JS: StaticFile = StaticFile {
+    name: "impls.js",
+    content: include_bytes!("static/js/impls.js"),
+};
+pub const COPY_JS: StaticFile = StaticFile {
+    name: "copy.js",
+    content: include_bytes!("static/js/copy.js"),
+};
+pub const STATIC_FILES: &[StaticFile] = &[
+    STYLE_CSS,
+    RUST_SVG,
+    SEARCH_JS,
+    SETTINGS_JS,
+    TOC_JS,
+    REDIRECT_JS,
+    IMPLS_JS,
+    COPY_JS,
+];
+
 pub const SEARCH_INDEX_JS: &str = "search-index.js";
+pub const SEARCH_INDEX_JSON: &str = "search-index.json";
+pub const SEARCH_INDEX_BIN: &str = "search-index.bin";
+pub const SEARCH_DOCS_INDEX_JS: &str = "search-docs-index.js";
+pub const IMPL_REDIRECTS_JSON: &str = "impl-redirects.json";
+pub const ANCHORS_JSON: &str = "anchors.json";
+pub const ANCHOR_ALIASES_JS: &str = "anchor-aliases.js";
+pub const DEPENDENCIES_HTML: &str = "dependencies.html";
+pub const UNSAFE_HTML: &str = "unsafe.html";
+pub const STATS_HTML: &str = "stats.html";
+pub const BLANKET_IMPLS_HTML: &str = "blanket-impls.html";
+pub const SEARCH_HTML: &str = "search.html";
+pub const OPENSEARCH_XML: &str = "opensearch.xml";
+pub const SITEMAP_XML: &str = "sitemap.xml";
+/// Directory of the chapters of the guide (`--guide`), in the one of the crate
+pub const GUIDE_DIR: &str = "guide";
+/// Extension of the side scripts with the deferred impl bodies of a page
+pub const LAZY_IMPLS_EXTENSION: &str = "impls.js";
+/// Number of trait impls on a page above which the collapsed ones are deferred
+pub const LAZY_IMPLS_THRESHOLD: usize = 50;
+
+/// Crates of the standard distribution, documented at [`STD_DOCS_URL`]
+pub const STD_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+pub const STD_DOCS_URL: &str = "https://doc.rust-lang.org/nightly/";
+
+/// Directory of the vendored copies of the third-party assets (`--vendored-assets`)
+pub const VENDOR_DIR: &str = "vendor";
 
+/// A third-party asset, loaded from its CDN or from its vendored copy
+pub struct Asset {
+    pub cdn: &'static str,
+    pub integrity: Option<&'static str>,
+    /// Path of the vendored copy, relative to the vendored assets directory
+    pub vendored: &'static str,
+}
+
+// bootstrap-dark-5 1.1.3 is built on Bootstrap 5.1.3, keep the Bootstrap js in sync with it
+pub const BOOTSTRAP_CSS: Asset = Asset {
+    cdn: "https://cdn.jsdelivr.net/npm/bootstrap-dark-5@1.1.3/dist/css/bootstrap-blackbox.min.css",
+    integrity: Some("sha384-nXtYGwAUBOgb4M8Eo9xOK3Er3bVPQo1HguUNWf/RheIagsbCaP3ZaYqVeUqHEr20"),
+    vendored: "bootstrap-blackbox.min.css",
+};
+pub const BOOTSTRAP_ICONS_CSS: Asset = Asset {
+    cdn: "https://cdn.jsdelivr.net/npm/bootstrap-icons@1.5.0/font/bootstrap-icons.css",
+    integrity: Some("sha384-tKLJeE1ALTUwtXlaGjJYM3sejfssWdAaWR2s97axw4xkiAdMzQjtOjgcyw0Y50KU"),
+    vendored: "bootstrap-icons.css",
+};
+pub const BOOTSTRAP_JS: Asset = Asset {
+    cdn: "https://cdn.jsdelivr.net/npm/bootstrap@5.1.3/dist/js/bootstrap.min.js",
+    integrity: Some("sha384-QJHtvGhmr9XOIpI6YVutG+2QOK9T+ZnN4kzFN1RtK3zEFEIsxhlmWl5/YESvpZ13"),
+    vendored: "bootstrap.min.js",
+};
+pub const DARKMODE_JS: Asset = Asset {
+    cdn: "https://cdn.jsdelivr.net/npm/bootstrap-dark-5@1.1.3/dist/js/darkmode.min.js",
+    integrity: Some("sha384-A4SLs39X/aUfwRclRaXvNeXNBTLZdnZdHhhteqbYFS2jZTRD79tKeFeBn7SGXNpi"),
+    vendored: "darkmode.min.js",
+};
+/// Single-file build of mermaid, bundled in the output as a fingerprinted static file from
+/// the vendored assets directory (`--mermaid`), or loaded from its CDN (`--mermaid=cdn`)
+pub const MERMAID_JS: &str = "mermaid.min.js";
+pub const MERMAID_CDN: &str = "https://cdn.jsdelivr.net/npm/mermaid@10.9.1/dist/mermaid.min.js";
+/// Assets that must be in the vendored assets directory
+pub const VENDORED_ASSETS: &[Asset] = &[
+    BOOTSTRAP_CSS,
+    BOOTSTRAP_ICONS_CSS,
+    BOOTSTRAP_JS,
+    DARKMODE_JS,
+];
+
+/// Traits whose impls are listed in a popup next to the functions returning them
+pub const NOTABLE_TRAITS: &[&str] = &["Iterator", "Future", "Read", "Write"];
+
+pub const README: &str = "README";
+pub const README_ID: &str = "readme";
+pub const VALUE: &str = "Value";
+pub const VALUE_ID: &str = "value";
 pub const VARIANTS: &str = "Variants";
 pub const VARIANTS_ID: &str = "variants";
 pub const ASSOCIATED_TYPES: &str = "Associated Types";
@@ -21,17 +142,48 @@ pub const IMPLEMENTORS: &str = "Implementors";
 pub const IMPLEMENTORS_ID: &str = "implementors";
 pub const AUTO_IMPLEMENTORS: &str = "Auto Implementors";
 pub const AUTO_IMPLEMENTORS_ID: &str = "auto-implementors";
+pub const REQUIRED_BY: &str = "Required By";
+pub const REQUIRED_BY_ID: &str = "required-by";
 pub const IMPLEMENTATIONS: &str = "Implementations";
 pub const IMPLEMENTATIONS_ID: &str = "implementations";
 pub const TRAIT_IMPLEMENTATIONS: &str = "Trait Implementations";
 pub const TRAIT_IMPLEMENTATIONS_ID: &str = "trait-implementations";
+pub const DERIVED_TRAIT_IMPLEMENTATIONS: &str = "Derived Trait Implementations";
+pub const DERIVED_TRAIT_IMPLEMENTATIONS_ID: &str = "derived-trait-implementations";
 pub const AUTO_TRAIT_IMPLEMENTATIONS: &str = "Auto Trait Implementations";
 pub const AUTO_TRAIT_IMPLEMENTATIONS_ID: &str = "auto-trait-implementations";
 pub const BLANKET_IMPLEMENTATIONS: &str = "Blanket Implementations";
 pub const BLANKET_IMPLEMENTATIONS_ID: &str = "blanket-implementations";
 
+pub const DEPENDENCIES: &str = "Dependencies";
+pub const DEPENDENCIES_ID: &str = "dependencies";
+pub const GUIDE: &str = "Guide";
+pub const GUIDE_ID: &str = "guide";
+pub const SEARCH_RESULTS: &str = "Search Results";
+pub const SEARCH_RESULTS_ID: &str = "search-results";
+pub const UNSAFE: &str = "Unsafe API";
+pub const UNSAFE_ID: &str = "unsafe";
+pub const UNSAFE_FUNCTIONS: &str = "Unsafe Functions";
+pub const UNSAFE_FUNCTIONS_ID: &str = "unsafe-functions";
+pub const UNSAFE_TRAITS: &str = "Unsafe Traits";
+pub const UNSAFE_TRAITS_ID: &str = "unsafe-traits";
+pub const UNSAFE_BY_MODULE: &str = "By Module";
+pub const UNSAFE_BY_MODULE_ID: &str = "unsafe-by-module";
+pub const STATISTICS: &str = "Statistics";
+pub const STATISTICS_ID: &str = "statistics";
+pub const STATISTICS_OVERVIEW: &str = "Overview";
+pub const STATISTICS_OVERVIEW_ID: &str = "overview";
+pub const STATISTICS_KINDS: &str = "Items by Kind";
+pub const STATISTICS_KINDS_ID: &str = "items-by-kind";
+pub const FEATURES: &str = "Features";
+pub const FEATURES_ID: &str = "features";
+pub const CONTRACTS_ID: &str = "contracts";
+pub const FAILED_ITEMS: &str = "Failed Items";
+pub const FAILED_ITEMS_ID: &str = "failed-items";
 pub const IMPORTS: &str = "Re-exports";
 pub const IMPORTS_ID: &str = "imports";
+pub const PRIMITIVES: &str = "Primitive Types";
+pub const PRIMITIVES_ID: &str = "primitives";
 pub const MODULES: &str = "Modules";
 pub const MODULES_ID: &str = "modules";
 pub const METHODS: &str = "Methods";
@@ -50,9 +202,75 @@ pub const TRAIT_ALIAS: &str = "Trait Alias";
 pub const TRAIT_ALIAS_ID: &str = "trait_alias";
 pub const TYPEDEFS: &str = "Type Definitions";
 pub const TYPEDEFS_ID: &str = "typedefs";
+pub const OPAQUE_TYPES: &str = "Opaque Types";
+pub const OPAQUE_TYPES_ID: &str = "opaque-types";
+pub const KEYWORDS: &str = "Keywords";
+pub const KEYWORDS_ID: &str = "keywords";
+pub const FOREIGN_TYPES: &str = "Foreign Types";
+pub const FOREIGN_TYPES_ID: &str = "foreign-types";
 pub const CONSTANTS: &str = "Constants";
 pub const CONSTANTS_ID: &str = "constants";
 pub const MACROS: &str = "Macros";
 pub const MACROS_ID: &str = "macros";
 pub const PROC_MACROS: &str = "Proc Macros";
 pub const PROC_MACROS_ID: &str = "proc_macros";
+
+/// Ids of the sections and of the elements of the pages with docs, never taken by their
+/// headings (the sections of the statistics, unsafe and search pages have no docs around)
+pub const RESERVED_IDS: &[&str] = &[
+    README_ID,
+    VALUE_ID,
+    VARIANTS_ID,
+    ASSOCIATED_TYPES_ID,
+    ASSOCIATED_CONSTS_ID,
+    REQUIRED_METHODS_ID,
+    PROVIDED_METHODS_ID,
+    IMPLEMENTATION_FOREIGN_TYPES_ID,
+    IMPLEMENTORS_ID,
+    AUTO_IMPLEMENTORS_ID,
+    REQUIRED_BY_ID,
+    IMPLEMENTATIONS_ID,
+    TRAIT_IMPLEMENTATIONS_ID,
+    DERIVED_TRAIT_IMPLEMENTATIONS_ID,
+    AUTO_TRAIT_IMPLEMENTATIONS_ID,
+    BLANKET_IMPLEMENTATIONS_ID,
+    DEPENDENCIES_ID,
+    GUIDE_ID,
+    UNSAFE_ID,
+    STATISTICS_ID,
+    FEATURES_ID,
+    CONTRACTS_ID,
+    FAILED_ITEMS_ID,
+    IMPORTS_ID,
+    PRIMITIVES_ID,
+    MODULES_ID,
+    METHODS_ID,
+    UNIONS_ID,
+    STRUCTS_ID,
+    ENUMS_ID,
+    FUNCTIONS_ID,
+    TRAITS_ID,
+    TRAIT_ALIAS_ID,
+    TYPEDEFS_ID,
+    OPAQUE_TYPES_ID,
+    KEYWORDS_ID,
+    FOREIGN_TYPES_ID,
+    CONSTANTS_ID,
+    MACROS_ID,
+    PROC_MACROS_ID,
+    "item-title",
+    "item-definition",
+    "item-documentation",
+    "toc-documentation",
+    "rd-docs-nav",
+    "rd-guide-chapters",
+    "rd-options",
+    "rd-options-form",
+    "rd-options-title",
+    "rd-search-form",
+    "rd-search-input",
+    "rd-search-items",
+    "rd-search-kinds",
+    "rd-search-menu",
+    "rd-search-pages",
+];
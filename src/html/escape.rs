@@ -0,0 +1,38 @@
+//! Escaping of the text written by hand in the HTML, in the content of the elements as in
+//! their (quoted) attributes
+
+use std::fmt::{self, Display, Write};
+
+/// Write `text` with the characters special to HTML escaped
+pub(super) fn escape_html(writer: &mut impl Write, text: &str) -> fmt::Result {
+    let mut last = 0;
+    for (index, byte) in text.bytes().enumerate() {
+        let escaped = match byte {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            b'\'' => "&#39;",
+            _ => continue,
+        };
+        writer.write_str(&text[last..index])?;
+        writer.write_str(escaped)?;
+        last = index + 1;
+    }
+    writer.write_str(&text[last..])
+}
+
+/// Text displayed and rendered escaped, for the HTML built with `format!`
+pub(super) struct Escaped<'a>(pub(super) &'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        escape_html(f, self.0)
+    }
+}
+
+impl markup::Render for Escaped<'_> {
+    fn render(&self, writer: &mut impl Write) -> fmt::Result {
+        escape_html(writer, self.0)
+    }
+}
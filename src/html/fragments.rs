@@ -0,0 +1,127 @@
+//! Per-item JSON fragments for live-preview tooling
+
+use anyhow::{Context as _, Result};
+use rustdoc_types::*;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use tracing::debug;
+
+use super::markdown::MarkdownWithToc;
+use super::render::{GlobalContext, PageContext, TocSection};
+use super::validate::validate_html;
+
+/// Directory (relative to the output directory) where the fragments are written
+pub(super) const FRAGMENTS_DIR: &str = "fragments";
+
+/// A self-contained rendering of a single item page
+#[derive(Serialize)]
+struct Fragment<'a> {
+    name: &'a str,
+    kind: &'a str,
+    page: String,
+    signature: Option<String>,
+    docs: Option<String>,
+    toc: Vec<FragmentTocEntry>,
+}
+
+#[derive(Serialize)]
+struct FragmentTocEntry {
+    level: u32,
+    section: Option<&'static str>,
+    name: String,
+    href: String,
+}
+
+/// Render a `markup`able value into a owned string
+fn render_to_string(renderable: &impl markup::Render) -> String {
+    let mut buf = String::new();
+    // Writing to a `String` can't fail
+    let _ = renderable.render(&mut buf);
+    buf
+}
+
+/// Write the fragment of the item of the given page under [`FRAGMENTS_DIR`]
+pub(super) fn write_fragment<'context>(
+    global_context: &'context GlobalContext<'context>,
+    page_context: &'context PageContext<'context>,
+    item: &'context Item,
+    toc: &[TocSection<'context>],
+) -> Result<()> {
+    let component = page_context
+        .item_path
+        .0
+        .last()
+        .context("empty item path for fragment")?;
+
+    let signature = match &item.inner {
+        ItemEnum::Module(_) | ItemEnum::Primitive(_) => None,
+        _ => Some(render_to_string(&super::render::TokensToHtml(
+            global_context,
+            page_context,
+            global_context.tokens(item)?,
+        ))),
+    };
+
+    let mut entries = Vec::new();
+    let docs = MarkdownWithToc::from_docs(global_context, page_context, &item.docs, &item.links)
+        .map(|markdown| {
+            let docs = render_to_string(&markdown);
+            for (level, name, id) in markdown.4.borrow().iter() {
+                entries.push(FragmentTocEntry {
+                    level: *level,
+                    section: None,
+                    name: name.clone(),
+                    href: id.with_pound().to_string(),
+                });
+            }
+            docs
+        });
+
+    for section in toc {
+        for (name, destination, _) in &section.items {
+            entries.push(FragmentTocEntry {
+                level: 1,
+                section: Some(section.id),
+                name: name.to_string(),
+                href: render_to_string(destination),
+            });
+        }
+    }
+
+    let page = format!("{}", component.filepath.display());
+    for html in signature.iter().chain(&docs) {
+        validate_html(html, &page);
+    }
+
+    let fragment = Fragment {
+        name: &component.name,
+        kind: component.kind,
+        page,
+        signature,
+        docs,
+        toc: entries,
+    };
+
+    let mut path = global_context.opt.output.join(FRAGMENTS_DIR);
+    path.push(match &page_context.inline_id {
+        // The items on the page of their module are named like their own page would be
+        Some(inline_id) => page_context
+            .filepath
+            .with_file_name(format!("{}.json", inline_id)),
+        None => page_context.filepath.with_extension("json"),
+    });
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("unable to create the fragments dir {:?}", parent))?;
+    }
+
+    debug!("writing the fragment {:?}", &path);
+    let file =
+        File::create(&path).with_context(|| format!("unable to create the {:?} file", path))?;
+    serde_json::to_writer(BufWriter::new(file), &fragment)
+        .with_context(|| format!("unable to write the fragment {:?}", path))?;
+
+    Ok(())
+}
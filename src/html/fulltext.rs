@@ -0,0 +1,109 @@
+//! Full-text index of the documentation prose, used by the "In docs" search tab
+
+use anyhow::{Context as _, Result};
+use pulldown_cmark::{Event, Parser};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Words too common to be worth indexing (must be kept in sync with `search.js`)
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "its", "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+/// Inverted index: stemmed term to the (sorted) indexes of the pages in the search index
+#[derive(Default)]
+pub(super) struct FullTextIndex(RefCell<BTreeMap<String, Vec<usize>>>);
+
+impl FullTextIndex {
+    /// Index the documentation of the page at `page` in the search index
+    pub(super) fn add(&self, page: usize, docs: &str) {
+        let mut terms = self.0.borrow_mut();
+
+        for event in Parser::new(docs) {
+            if let Event::Text(text) | Event::Code(text) = event {
+                for term in terms_of(&text) {
+                    let pages = terms.entry(term).or_default();
+                    if pages.last() != Some(&page) {
+                        pages.push(page);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renumber the pages after the reordering of the search index, `order` giving the
+    /// previous index of each page
+    pub(super) fn reorder(&self, order: &[usize]) {
+        let mut new_indexes = vec![0; order.len()];
+        for (new_index, previous_index) in order.iter().enumerate() {
+            new_indexes[*previous_index] = new_index;
+        }
+
+        for pages in self.0.borrow_mut().values_mut() {
+            for page in pages.iter_mut() {
+                *page = new_indexes[*page];
+            }
+            pages.sort_unstable();
+        }
+    }
+
+    /// Serialize the index as javascript, declaring the `DOCS_INDEX` constant
+    pub(super) fn to_js(&self) -> Result<String> {
+        let json = serde_json::to_string(&*self.0.borrow())
+            .context("unable to serialize the full-text index")?;
+        Ok(format!("\n\nconst DOCS_INDEX = {};\n", json))
+    }
+}
+
+/// Split a text into lower-cased, stemmed terms without the stop words
+fn terms_of(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() > 1)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+}
+
+/// Very light suffix stripping (must be kept in sync with `search.js`)
+fn stem(word: &str) -> String {
+    if word.len() > 4 && word.ends_with("ies") {
+        format!("{}y", &word[..word.len() - 3])
+    } else if word.len() > 5 && word.ends_with("ing") {
+        word[..word.len() - 3].to_string()
+    } else if word.len() > 4 && word.ends_with("ed") {
+        word[..word.len() - 2].to_string()
+    } else if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stems() {
+        assert_eq!(stem("entries"), "entry");
+        assert_eq!(stem("parsing"), "pars");
+        assert_eq!(stem("parsed"), "pars");
+        assert_eq!(stem("items"), "item");
+        // Too short to be stripped
+        assert_eq!(stem("ties"), "tie");
+        assert_eq!(stem("ring"), "ring");
+        assert_eq!(stem("red"), "red");
+        assert_eq!(stem("is"), "is");
+        assert_eq!(stem("class"), "class");
+    }
+
+    #[test]
+    fn terms() {
+        assert_eq!(
+            terms_of("Parses the given Entries, a-b").collect::<Vec<_>>(),
+            ["parse", "given", "entry"]
+        );
+    }
+}
@@ -2,6 +2,8 @@ use std::{fmt::Display, ops::Add};
 
 use markup::Render;
 
+use super::escape::escape_html;
+
 #[derive(Debug)]
 pub struct Id(String);
 
@@ -23,7 +25,7 @@ impl Id {
         impl<'a> Render for Pound<'a> {
             fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
                 writer.write_str("#")?;
-                writer.write_str(&self.0 .0)
+                escape_html(writer, &self.0 .0)
             }
         }
 
@@ -49,7 +51,7 @@ impl Add<Id> for &Id {
 
 impl Render for Id {
     fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
-        writer.write_str(&self.0)
+        escape_html(writer, &self.0)
     }
 }
 
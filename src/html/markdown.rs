@@ -1,37 +1,22 @@
 //! Markdown handling for HTML output
 
-use log::warn;
-use pulldown_cmark::{escape, html, BrokenLink, CowStr, Event, HeadingLevel, Options, Parser, Tag};
-use rustdoc_types::Id;
+use pulldown_cmark::{
+    html, BrokenLink, CowStr, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd,
+};
+use rustdoc_types::{Crate, Id};
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
-use std::{fmt, io, str};
+use tracing::warn;
 
+use super::escape::{escape_html, Escaped};
 use super::id::Id as HtmlId;
 use super::render::{GlobalContext, PageContext};
+use super::sanitize::Sanitize;
+use super::templates::CopyButton;
 use super::utils::*;
 
-/// Convert an std::fmt::Write to std::io::Write
-struct Adapter<'a> {
-    f: &'a mut dyn fmt::Write,
-}
-
-impl<'a> io::Write for Adapter<'a> {
-    fn write(&mut self, b: &[u8]) -> Result<usize, io::Error> {
-        let s = str::from_utf8(b).map_err(|_| io::Error::from(io::ErrorKind::Other))?;
-        self.f
-            .write_str(s)
-            .map_err(|_| io::Error::from(io::ErrorKind::Other))?;
-        Ok(b.len())
-    }
-
-    fn flush(&mut self) -> Result<(), io::Error> {
-        Ok(())
-    }
-}
-
 /// Options for rendering Markdown in the main body of documentation.
 fn opts() -> Options {
     Options::ENABLE_TABLES
@@ -39,6 +24,7 @@ fn opts() -> Options {
         | Options::ENABLE_STRIKETHROUGH
         | Options::ENABLE_TASKLISTS
         | Options::ENABLE_SMART_PUNCTUATION
+        | Options::ENABLE_DEFINITION_LIST
 }
 
 /// A subset of [`opts()`] used for rendering summaries.
@@ -51,7 +37,7 @@ pub(super) struct Markdown<'context, 'krate, 'content>(
     &'context GlobalContext<'krate>,
     &'context PageContext<'context>,
     Option<&'context HtmlId>,
-    &'content String,
+    &'content str,
     &'krate HashMap<String, Id>,
 );
 
@@ -73,54 +59,121 @@ impl<'context, 'krate, 'content> Markdown<'context, 'krate, 'content> {
 impl<'context, 'krate, 'content> markup::Render for Markdown<'context, 'krate, 'content> {
     fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
         if !self.3.is_empty() {
-            let adapter = Adapter { f: writer };
-
             let mut replacer = |broken_link: BrokenLink<'_>| {
-                if let Some(id) = self.4.get(broken_link.reference.as_ref()) {
-                    if let Some((external_crate_url, relative, fragment, _type_of)) =
-                        href(self.0, self.1, id)
-                    {
-                        Some((
-                            {
-                                let mut href = String::new();
-
-                                if let Some(external_crate_url) = external_crate_url {
-                                    href.push_str(external_crate_url);
-                                }
-                                href.push_str(
-                                    relative.to_str().expect("cannot convert PathBuf to str"),
-                                );
-                                if let Some(fragment) = fragment {
-                                    href.push_str(&fragment);
-                                }
-
-                                CowStr::Boxed(href.into_boxed_str())
-                            },
-                            CowStr::Boxed(broken_link.reference.to_string().into_boxed_str()),
-                        ))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
+                let id = self.4.get(broken_link.reference.as_ref())?;
+                Some((
+                    link_href(self.0, self.1, id)?.into(),
+                    broken_link.reference.to_string().into(),
+                ))
             };
 
             let parser = Parser::new_with_broken_link_callback(self.3, opts(), Some(&mut replacer));
-            let parser = CodeBlocks::new(parser);
-            let parser = Headings::new(parser, self.2, self.1, None);
+            let parser = CodeLinks::new(parser, self.0, self.1, self.4);
+            let parser = Sanitize::new(parser, self.0.opt.allow_raw_html);
+            let parser = CodeBlocks::new(parser, self.0.opt.mermaid.is_some());
+            let parser = Headings::new(parser, self.2, self.1, None, !self.0.opt.no_heading_shift);
+            let parser = Footnotes::new(parser, self.1.footnotes_prefix(self.2));
 
-            html::write_html(adapter, parser).unwrap();
+            html::write_html_fmt(&mut *writer, parser)?;
         }
         Ok(())
     }
 }
 
+/// Kind of an attention banner
+#[derive(Clone, Copy)]
+pub(super) enum BannerKind {
+    Warning,
+    Info,
+}
+
+/// Markers (at the start of the leading blockquote of the docs) of attention banners
+const BANNER_MARKERS: &[(&str, BannerKind)] = &[
+    ("⚠️", BannerKind::Warning),
+    ("⚠", BannerKind::Warning),
+    ("[!WARNING]", BannerKind::Warning),
+    ("[!CAUTION]", BannerKind::Warning),
+    ("[!IMPORTANT]", BannerKind::Warning),
+    ("ℹ️", BannerKind::Info),
+    ("ℹ", BannerKind::Info),
+    ("[!NOTE]", BannerKind::Info),
+    ("[!TIP]", BannerKind::Info),
+];
+
+/// Split the attention banner of the docs: a leading blockquote starting with a marker,
+/// like `> ⚠️ Prefer ...` or `> [!NOTE]`, returning its kind, its text and the rest of the docs
+fn split_banner(docs: &str) -> Option<(BannerKind, String, &str)> {
+    let docs = docs.trim_start_matches('\n');
+    let mut quoted = Vec::new();
+    let mut rest = docs;
+
+    while let Some(line) = rest.split_inclusive('\n').next() {
+        let text = match line.trim_start().strip_prefix('>') {
+            Some(text) => text.strip_prefix(' ').unwrap_or(text).trim_end(),
+            None => break,
+        };
+        quoted.push(text);
+        rest = &rest[line.len()..];
+    }
+
+    let first = quoted.first()?.trim_start();
+    let (marker, kind) = BANNER_MARKERS
+        .iter()
+        .find(|(marker, _)| first.starts_with(marker))?;
+    quoted[0] = first[marker.len()..].trim_start();
+
+    Some((*kind, quoted.join("\n").trim().to_string(), rest))
+}
+
+/// The docs without their attention banner (see [`split_banner`])
+fn without_banner(docs: &str) -> &str {
+    split_banner(docs).map_or(docs, |(_, _, rest)| rest)
+}
+
+/// Render the attention banner of the docs (if any)
+pub(super) struct MarkdownBanner<'context, 'krate>(
+    &'context GlobalContext<'krate>,
+    &'context PageContext<'context>,
+    BannerKind,
+    String,
+    &'krate HashMap<String, Id>,
+);
+
+impl<'context, 'krate> MarkdownBanner<'context, 'krate> {
+    /// Create a [`MarkdownBanner`] struct from some context and a content
+    pub(super) fn from_docs(
+        global_context: &'context GlobalContext<'krate>,
+        page_context: &'context PageContext<'context>,
+        content: &Option<String>,
+        links: &'krate HashMap<String, Id>,
+    ) -> Option<Self> {
+        let (kind, text, _) = split_banner(content.as_ref()?)?;
+        Some(Self(global_context, page_context, kind, text, links))
+    }
+}
+
+impl<'context, 'krate> markup::Render for MarkdownBanner<'context, 'krate> {
+    fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
+        let (class, icon) = match self.2 {
+            BannerKind::Warning => ("alert-warning", "bi-exclamation-triangle-fill"),
+            BannerKind::Info => ("alert-info", "bi-info-circle-fill"),
+        };
+
+        write!(
+            writer,
+            "<div class=\"alert {} rd-banner\" role=\"note\"><i class=\"bi {} me-2\"></i>",
+            class, icon
+        )?;
+        markup::Render::render(&Markdown(self.0, self.1, None, &self.3, self.4), writer)?;
+        writer.write_str("</div>")
+    }
+}
+
 /// Render the all Markdown in html
 pub struct MarkdownWithToc<'context, 'krate, 'content>(
     &'context GlobalContext<'krate>,
     &'context PageContext<'context>,
-    &'content String,
+    &'content str,
     &'krate HashMap<String, Id>,
     // RefCell required here because of the immutable `&self` on `render`
     pub(crate) RefCell<Vec<(u32, String, &'context HtmlId)>>,
@@ -138,7 +191,7 @@ impl<'context, 'krate, 'content> MarkdownWithToc<'context, 'krate, 'content> {
             Self(
                 global_context,
                 page_context,
-                content,
+                without_banner(content),
                 links,
                 RefCell::new(Default::default()),
             )
@@ -149,49 +202,33 @@ impl<'context, 'krate, 'content> MarkdownWithToc<'context, 'krate, 'content> {
 impl<'context, 'krate, 'content> markup::Render for MarkdownWithToc<'context, 'krate, 'content> {
     fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
         if !self.2.is_empty() {
-            let adapter = Adapter { f: writer };
-
             let gloabl_context = self.0;
             let page_context = self.1;
             let ids = self.3;
             let mut replacer = |broken_link: BrokenLink<'_>| {
-                if let Some(id) = ids.get(broken_link.reference.as_ref()) {
-                    if let Some((external_crate_url, relative, fragment, _type_of)) =
-                        href(gloabl_context, page_context, id)
-                    {
-                        Some((
-                            {
-                                let mut href = String::new();
-
-                                if let Some(external_crate_url) = external_crate_url {
-                                    href.push_str(external_crate_url);
-                                }
-                                href.push_str(
-                                    relative.to_str().expect("cannot convert PathBuf to str"),
-                                );
-                                if let Some(fragment) = fragment {
-                                    href.push_str(&fragment);
-                                }
-
-                                CowStr::Boxed(href.into_boxed_str())
-                            },
-                            CowStr::Boxed(broken_link.reference.to_string().into_boxed_str()),
-                        ))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
+                let id = ids.get(broken_link.reference.as_ref())?;
+                Some((
+                    link_href(gloabl_context, page_context, id)?.into(),
+                    broken_link.reference.to_string().into(),
+                ))
             };
 
             let parser = Parser::new_with_broken_link_callback(self.2, opts(), Some(&mut replacer));
-            let parser = CodeBlocks::new(parser);
+            let parser = CodeLinks::new(parser, gloabl_context, page_context, ids);
+            let parser = Sanitize::new(parser, gloabl_context.opt.allow_raw_html);
+            let parser = CodeBlocks::new(parser, gloabl_context.opt.mermaid.is_some());
 
             let mut toc_borrow = self.4.borrow_mut();
-            let parser = Headings::new(parser, None, page_context, Some(&mut toc_borrow));
+            let parser = Headings::new(
+                parser,
+                None,
+                page_context,
+                Some(&mut toc_borrow),
+                !gloabl_context.opt.no_heading_shift,
+            );
+            let parser = Footnotes::new(parser, page_context.footnotes_prefix(None));
 
-            html::write_html(adapter, parser).unwrap();
+            html::write_html_fmt(&mut *writer, parser)?;
         }
         Ok(())
     }
@@ -201,7 +238,7 @@ impl<'context, 'krate, 'content> markup::Render for MarkdownWithToc<'context, 'k
 pub(super) struct MarkdownSummaryLine<'context, 'krate, 'content>(
     &'context GlobalContext<'krate>,
     &'context PageContext<'context>,
-    &'content String,
+    &'content str,
     &'krate HashMap<String, Id>,
 );
 
@@ -215,7 +252,7 @@ impl<'context, 'krate, 'content> MarkdownSummaryLine<'context, 'krate, 'content>
     ) -> Option<Self> {
         content
             .as_ref()
-            .map(|content| Self(global_context, page_context, content, links))
+            .map(|content| Self(global_context, page_context, without_banner(content), links))
     }
 }
 
@@ -224,49 +261,353 @@ impl<'context, 'krate, 'content> markup::Render
 {
     fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
         if !self.2.is_empty() {
-            let adapter = Adapter { f: writer };
-
             let mut replacer = |broken_link: BrokenLink<'_>| {
-                if let Some(id) = self.3.get(broken_link.reference.as_ref()) {
-                    if let Some((external_crate_url, relative, fragment, _type_of)) =
-                        href(self.0, self.1, id)
-                    {
-                        Some((
-                            {
-                                let mut href = String::new();
-
-                                if let Some(external_crate_url) = external_crate_url {
-                                    href.push_str(external_crate_url);
-                                }
-                                href.push_str(
-                                    relative.to_str().expect("cannot convert PathBuf to str"),
-                                );
-                                if let Some(fragment) = fragment {
-                                    href.push_str(&fragment);
-                                }
-
-                                CowStr::Boxed(href.into_boxed_str())
-                            },
-                            CowStr::Boxed(broken_link.reference.to_string().into_boxed_str()),
-                        ))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
+                let id = self.3.get(broken_link.reference.as_ref())?;
+                Some((
+                    link_href(self.0, self.1, id)?.into(),
+                    broken_link.reference.to_string().into(),
+                ))
             };
 
             let parser =
                 Parser::new_with_broken_link_callback(self.2, summary_opts(), Some(&mut replacer));
-            let parser = SummaryLine::new(parser);
+            let parser = CodeLinks::new(parser, self.0, self.1, self.3);
+            let parser = Sanitize::new(parser, self.0.opt.allow_raw_html);
+            let events: Vec<_> = SummaryLine::new(parser).collect();
+
+            match first_sentence(&events) {
+                Some(mut sentence) => {
+                    let mut title = String::new();
+                    for event in &events {
+                        if let Event::Text(text) | Event::Code(text) = event {
+                            title.push_str(text);
+                        }
+                    }
 
-            html::write_html(adapter, parser).unwrap();
+                    // Put the full paragraph in the title of the (now shortened) paragraph
+                    if let Some(first @ Event::Start(Tag::Paragraph)) = sentence.first_mut() {
+                        let mut html = String::from("<p title=\"");
+                        escape_html(&mut html, title.trim()).unwrap();
+                        html.push_str("\">");
+                        *first = Event::Html(html.into());
+                    }
+
+                    html::write_html_fmt(&mut *writer, sentence.into_iter())?;
+                }
+                None => html::write_html_fmt(&mut *writer, events.into_iter())?,
+            }
         }
         Ok(())
     }
 }
 
+/// Render a chapter of the guide (`--guide`), its links to the other chapters (like
+/// `other.md#usage`) leading to their pages
+pub(super) struct MarkdownChapter<'context, 'krate, 'content>(
+    &'context GlobalContext<'krate>,
+    &'context PageContext<'context>,
+    &'content str,
+    &'krate HashMap<String, Id>,
+);
+
+impl<'context, 'krate, 'content> MarkdownChapter<'context, 'krate, 'content> {
+    /// Create a [`MarkdownChapter`] struct from some context, a chapter and its links
+    pub(super) fn new(
+        global_context: &'context GlobalContext<'krate>,
+        page_context: &'context PageContext<'context>,
+        content: &'content str,
+        links: &'krate HashMap<String, Id>,
+    ) -> Self {
+        Self(global_context, page_context, content, links)
+    }
+}
+
+impl<'context, 'krate, 'content> markup::Render for MarkdownChapter<'context, 'krate, 'content> {
+    fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
+        let mut replacer = |broken_link: BrokenLink<'_>| {
+            let id = self.3.get(broken_link.reference.as_ref())?;
+            Some((
+                link_href(self.0, self.1, id)?.into(),
+                broken_link.reference.to_string().into(),
+            ))
+        };
+
+        let parser = Parser::new_with_broken_link_callback(self.2, opts(), Some(&mut replacer));
+        let parser = parser.map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: chapter_url(dest_url),
+                title,
+                id,
+            }),
+            event => event,
+        });
+        let parser = CodeLinks::new(parser, self.0, self.1, self.3);
+        let parser = Sanitize::new(parser, self.0.opt.allow_raw_html);
+        let parser = CodeBlocks::new(parser, self.0.opt.mermaid.is_some());
+        // The title of the chapter (`#`) being the title of the page
+        let parser = Headings::new(parser, None, self.1, None, false);
+        let parser = Footnotes::new(parser, self.1.footnotes_prefix(None));
+
+        html::write_html_fmt(&mut *writer, parser)
+    }
+}
+
+/// Page of a chapter of the guide, from the name of its file (without `.md`)
+pub(super) fn chapter_page(name: &str) -> String {
+    match name {
+        "index" | "README" => "index.html".to_string(),
+        name => format!("{}.html", name),
+    }
+}
+
+/// Page of the chapter of a link to a markdown file, the other links being kept as is
+fn chapter_url(url: CowStr<'_>) -> CowStr<'_> {
+    if url.contains(':') || url.starts_with('/') {
+        return url;
+    }
+
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (&*url, None),
+    };
+    let Some(name) = path.strip_suffix(".md") else {
+        return url;
+    };
+    let (directory, name) = match name.rsplit_once('/') {
+        Some((directory, name)) => (Some(directory), name),
+        None => (None, name),
+    };
+
+    let mut chapter_url = String::new();
+    if let Some(directory) = directory {
+        chapter_url.push_str(directory);
+        chapter_url.push('/');
+    }
+    chapter_url.push_str(&chapter_page(name));
+    if let Some(fragment) = fragment {
+        chapter_url.push('#');
+        chapter_url.push_str(fragment);
+    }
+    chapter_url.into()
+}
+
+/// Text of the first heading of the docs, like the title of a chapter
+pub(super) fn first_heading(docs: &str) -> Option<String> {
+    let mut title: Option<String> = None;
+    for event in Parser::new_ext(docs, opts()) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => title = Some(String::new()),
+            Event::End(TagEnd::Heading(_)) => return title.filter(|title| !title.is_empty()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(title) = &mut title {
+                    title.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Url of the page (and anchor) of a linked item, from the page being rendered
+fn link_href(
+    global_context: &GlobalContext<'_>,
+    page_context: &PageContext<'_>,
+    id: &Id,
+) -> Option<String> {
+    let (external_crate_url, relative, fragment, _type_of) =
+        href(global_context, page_context, id)?;
+
+    let mut href = String::new();
+    if let Some(external_crate_url) = external_crate_url {
+        href.push_str(external_crate_url);
+    }
+    href.push_str(relative.to_str().expect("cannot convert PathBuf to str"));
+    if let Some(fragment) = fragment {
+        href.push('#');
+        href.push_str(&fragment);
+    }
+    Some(href)
+}
+
+/// Link the inline code spans (outside of the links) whose text is one of the links of the
+/// item, like `` `Foo` `` when ``[`Foo`]`` is linked elsewhere in the docs (`--link-code-spans`)
+struct CodeLinks<'context, 'krate, 'a, I: Iterator<Item = Event<'a>>> {
+    inner: I,
+    global_context: &'context GlobalContext<'krate>,
+    page_context: &'context PageContext<'context>,
+    links: &'krate HashMap<String, Id>,
+    enabled: bool,
+    link_depth: usize,
+    pending: VecDeque<Event<'a>>,
+}
+
+impl<'context, 'krate, 'a, I: Iterator<Item = Event<'a>>> CodeLinks<'context, 'krate, 'a, I> {
+    fn new(
+        iter: I,
+        global_context: &'context GlobalContext<'krate>,
+        page_context: &'context PageContext<'context>,
+        links: &'krate HashMap<String, Id>,
+    ) -> Self {
+        CodeLinks {
+            inner: iter,
+            global_context,
+            page_context,
+            links,
+            enabled: global_context.opt.link_code_spans,
+            link_depth: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn href(&self, code: &str) -> Option<String> {
+        let id = self
+            .links
+            .get(&format!("`{}`", code))
+            .or_else(|| self.links.get(code))?;
+        link_href(self.global_context, self.page_context, id)
+    }
+}
+
+impl<'context, 'krate, 'a, I: Iterator<Item = Event<'a>>> Iterator
+    for CodeLinks<'context, 'krate, 'a, I>
+{
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
+
+        let event = self.inner.next()?;
+        if !self.enabled {
+            return Some(event);
+        }
+        match event {
+            Event::Start(Tag::Link { .. }) => self.link_depth += 1,
+            Event::End(TagEnd::Link) => self.link_depth -= 1,
+            Event::Code(ref code) if self.link_depth == 0 => {
+                if let Some(href) = self.href(code) {
+                    self.pending.push_back(event);
+                    self.pending.push_back(Event::End(TagEnd::Link));
+                    return Some(Event::Start(Tag::Link {
+                        link_type: LinkType::Inline,
+                        dest_url: href.into(),
+                        title: CowStr::Borrowed(""),
+                        id: CowStr::Borrowed(""),
+                    }));
+                }
+            }
+            _ => {}
+        }
+        Some(event)
+    }
+}
+
+/// Resolve the intra-doc-like links of a README (`[Foo]`, ``[`module::Bar`]``,
+/// `[crate::Baz]`) to the local items with these paths
+pub(super) fn readme_links(krate: &Crate, readme: &str) -> HashMap<String, Id> {
+    let mut references = Vec::new();
+    let mut callback = |broken_link: BrokenLink<'_>| {
+        references.push(broken_link.reference.to_string());
+        None
+    };
+    Parser::new_with_broken_link_callback(readme, opts(), Some(&mut callback)).for_each(drop);
+
+    let krate_name = krate
+        .index
+        .get(&krate.root)
+        .and_then(|item| item.name.as_deref());
+    let local_paths: HashMap<String, &Id> = krate
+        .paths
+        .iter()
+        .filter(|(_, summary)| summary.crate_id == 0)
+        .map(|(id, summary)| (summary.path[1..].join("::"), id))
+        .collect();
+
+    references
+        .into_iter()
+        .filter_map(|reference| {
+            let path = reference.trim_matches('`');
+            let path = match path.split_once("::") {
+                Some(("crate", rest)) => rest,
+                Some((first, rest)) if Some(first) == krate_name => rest,
+                _ => path,
+            };
+            let id = local_paths.get(path)?;
+            Some((reference, (*id).clone()))
+        })
+        .collect()
+}
+
+/// Plain-text version of the summary line (first paragraph) of the docs
+pub(super) fn summary_text(docs: &str) -> String {
+    let parser = Parser::new_ext(without_banner(docs), summary_opts());
+
+    let mut text = String::new();
+    for event in SummaryLine::new(parser) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+/// Cut the events of a summary at the end of the first sentence, closing the
+/// still opened tags and adding an ellipsis; `None` if there is nothing to cut
+fn first_sentence<'a>(events: &[Event<'a>]) -> Option<Vec<Event<'a>>> {
+    fn sentence_end(text: &str) -> Option<usize> {
+        text.char_indices().find_map(|(index, c)| {
+            let end = index + c.len_utf8();
+            let is_end = matches!(c, '.' | '!' | '?')
+                && text[end..].starts_with(char::is_whitespace)
+                && !["e.g.", "i.e.", "etc.", "vs."]
+                    .iter()
+                    .any(|abbr| text[..end].ends_with(abbr));
+            is_end.then_some(end)
+        })
+    }
+
+    let mut opened = Vec::new();
+    let mut sentence = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::Start(tag) => opened.push(tag.to_end()),
+            Event::End(_) => {
+                opened.pop();
+            }
+            Event::Text(text) => {
+                if let Some(end) = sentence_end(text) {
+                    let rest_is_empty = text[end..].trim().is_empty()
+                        && events[index + 1..]
+                            .iter()
+                            .all(|event| matches!(event, Event::End(_)));
+                    if rest_is_empty {
+                        return None;
+                    }
+
+                    sentence.push(Event::Text(text[..end].to_string().into()));
+                    sentence.push(Event::Text(" …".into()));
+                    sentence.extend(opened.into_iter().rev().map(Event::End));
+                    return Some(sentence);
+                }
+            }
+            _ => {}
+        }
+        sentence.push(event.clone());
+    }
+
+    None
+}
+
 /// Extracts just the first paragraph.
 struct SummaryLine<'a, I: Iterator<Item = Event<'a>>> {
     inner: I,
@@ -288,22 +629,26 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for SummaryLine<'a, I> {
     type Item = Event<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        fn check_if_allowed_tag(t: &Tag<'_>) -> bool {
+        fn check_if_allowed_tag(t: &TagEnd) -> bool {
             matches!(
                 t,
-                Tag::Paragraph
-                    | Tag::Item
-                    | Tag::Emphasis
-                    | Tag::Strong
-                    | Tag::Link(..)
-                    | Tag::BlockQuote
+                TagEnd::Paragraph
+                    | TagEnd::Item
+                    | TagEnd::Emphasis
+                    | TagEnd::Strong
+                    | TagEnd::Link
+                    | TagEnd::BlockQuote(_)
             )
         }
 
-        fn is_forbidden_tag(t: &Tag<'_>) -> bool {
+        fn is_forbidden_tag(t: &TagEnd) -> bool {
             matches!(
                 t,
-                Tag::CodeBlock(_) | Tag::Table(_) | Tag::TableHead | Tag::TableRow | Tag::TableCell
+                TagEnd::CodeBlock
+                    | TagEnd::Table
+                    | TagEnd::TableHead
+                    | TagEnd::TableRow
+                    | TagEnd::TableCell
             )
         }
 
@@ -317,11 +662,11 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for SummaryLine<'a, I> {
             let mut is_start = true;
             let is_allowed_tag = match event {
                 Event::Start(ref c) => {
-                    if is_forbidden_tag(c) {
+                    if is_forbidden_tag(&c.to_end()) {
                         return None;
                     }
                     self.depth += 1;
-                    check_if_allowed_tag(c)
+                    check_if_allowed_tag(&c.to_end())
                 }
                 Event::End(ref c) => {
                     if is_forbidden_tag(c) {
@@ -337,7 +682,7 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for SummaryLine<'a, I> {
                 if is_start {
                     Some(Event::Start(Tag::Paragraph))
                 } else {
-                    Some(Event::End(Tag::Paragraph))
+                    Some(Event::End(TagEnd::Paragraph))
                 }
             } else {
                 Some(event)
@@ -347,6 +692,123 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for SummaryLine<'a, I> {
     }
 }
 
+/// Id of a heading of the docs from its text, before its parent prefix and de-duplication
+fn heading_slug(text: &str) -> String {
+    let mut id = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if c.is_whitespace() {
+            id.push('-');
+        }
+    }
+    id
+}
+
+/// Sections of the docs documenting the contract of a function
+pub(crate) const CONTRACT_SECTIONS: [&str; 3] = ["Panics", "Errors", "Safety"];
+
+/// The [`CONTRACT_SECTIONS`] of the docs (by their heading, at any level), with the id of
+/// their heading before its parent prefix
+pub(crate) fn contract_sections(docs: &str) -> Vec<(&'static str, String)> {
+    let mut sections = Vec::new();
+    let mut heading: Option<String> = None;
+    for event in Parser::new_ext(without_banner(docs), opts()) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => heading = Some(String::new()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = &mut heading {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(..)) => {
+                let Some(text) = heading.take() else {
+                    continue;
+                };
+                if let Some(name) = CONTRACT_SECTIONS
+                    .iter()
+                    .find(|name| name.eq_ignore_ascii_case(text.trim()))
+                {
+                    if sections.iter().all(|(other, _)| other != name) {
+                        sections.push((*name, heading_slug(&text)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    sections.sort_by_key(|(name, _)| CONTRACT_SECTIONS.iter().position(|other| other == name));
+    sections
+}
+
+/// Features described by the `Features` section of the docs (by its heading, at any level),
+/// listed as `` - `name`: description `` (or `**name**`), in order
+pub(crate) fn feature_descriptions(docs: &str) -> Vec<(String, String)> {
+    let mut features = Vec::new();
+    let mut heading: Option<String> = None;
+    // Level of the heading of the section, while in it
+    let mut section: Option<HeadingLevel> = None;
+    let mut depth = 0;
+    let mut strong = false;
+    let mut feature: Option<(String, String)> = None;
+    for event in Parser::new_ext(without_banner(docs), opts()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                if section.is_some_and(|section| level <= section) {
+                    section = None;
+                }
+                heading = Some(String::new());
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let text = heading.take().unwrap_or_default();
+                if text.trim().eq_ignore_ascii_case("Features") {
+                    section = Some(level);
+                }
+            }
+            Event::Start(Tag::Item) if section.is_some() => {
+                depth += 1;
+                if depth == 1 {
+                    feature = Some(Default::default());
+                }
+            }
+            Event::End(TagEnd::Item) if section.is_some() => {
+                depth -= 1;
+                if depth > 0 {
+                    continue;
+                }
+                if let Some((name, description)) = feature.take() {
+                    if !name.is_empty() {
+                        let description = description.trim_start_matches([':', '-', '—', ' ']);
+                        features.push((name, description.trim().to_string()));
+                    }
+                }
+            }
+            Event::Start(Tag::Strong) => strong = true,
+            Event::End(TagEnd::Strong) => strong = false,
+            Event::Text(ref text) | Event::Code(ref text) => {
+                if let Some(heading) = &mut heading {
+                    heading.push_str(text);
+                } else if let Some((name, description)) = feature.as_mut().filter(|_| depth == 1) {
+                    // The name is the first code span or strong text of the item
+                    let is_name = strong || matches!(event, Event::Code(_));
+                    if is_name && name.is_empty() && description.trim().is_empty() {
+                        name.push_str(text);
+                    } else {
+                        description.push_str(text);
+                    }
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((_, description)) = feature.as_mut().filter(|_| depth == 1) {
+                    description.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    features
+}
+
 /// Format a litle bit diffrently the Codeblocks
 struct Headings<'a, 'toc, 'context, I: Iterator<Item = Event<'a>>> {
     inner: I,
@@ -354,6 +816,8 @@ struct Headings<'a, 'toc, 'context, I: Iterator<Item = Event<'a>>> {
     parent_id: Option<&'context HtmlId>,
     page_context: &'context PageContext<'context>,
     toc: Option<&'toc mut Vec<(u32, String, &'context HtmlId)>>,
+    /// Whether the headings are moved one level down, below the title of the page
+    shift: bool,
 }
 
 impl<'a, 'toc, 'context, I: Iterator<Item = Event<'a>>> Headings<'a, 'toc, 'context, I> {
@@ -362,6 +826,7 @@ impl<'a, 'toc, 'context, I: Iterator<Item = Event<'a>>> Headings<'a, 'toc, 'cont
         parent_id: Option<&'context HtmlId>,
         page_context: &'context PageContext<'context>,
         toc: Option<&'toc mut Vec<(u32, String, &'context HtmlId)>>,
+        shift: bool,
     ) -> Self {
         Self {
             inner: iter,
@@ -369,6 +834,7 @@ impl<'a, 'toc, 'context, I: Iterator<Item = Event<'a>>> Headings<'a, 'toc, 'cont
             parent_id,
             page_context,
             toc,
+            shift,
         }
     }
 }
@@ -382,7 +848,7 @@ impl<'a, 'toc, 'vec, I: Iterator<Item = Event<'a>>> Iterator for Headings<'a, 't
         }
 
         let event = self.inner.next();
-        let level = if let Some(Event::Start(Tag::Heading(level, ..))) = event {
+        let level = if let Some(Event::Start(Tag::Heading { level, .. })) = event {
             level
         } else {
             return event;
@@ -391,8 +857,8 @@ impl<'a, 'toc, 'vec, I: Iterator<Item = Event<'a>>> Iterator for Headings<'a, 't
         let mut original_text = String::new();
         for event in &mut self.inner {
             match event {
-                Event::End(Tag::Heading(..)) => break,
-                Event::Start(Tag::Link(_, _, _)) | Event::End(Tag::Link(..)) => {}
+                Event::End(TagEnd::Heading(..)) => break,
+                Event::Start(Tag::Link { .. }) | Event::End(TagEnd::Link) => {}
                 Event::Text(ref s) | Event::Code(ref s) => {
                     original_text.push_str(s);
                     self.buf.push_back(event);
@@ -401,29 +867,30 @@ impl<'a, 'toc, 'vec, I: Iterator<Item = Event<'a>>> Iterator for Headings<'a, 't
             }
         }
 
-        let mut id = String::new();
-        for c in original_text.trim().chars() {
-            if c.is_alphanumeric() {
-                id.push(c.to_ascii_lowercase());
-            } else if c.is_whitespace() {
-                id.push('-');
-            }
-        }
-
-        let mut id = HtmlId::new(id);
-        if let Some(parent_id) = self.parent_id {
-            id = parent_id + id;
-        }
+        let id = HtmlId::new(heading_slug(&original_text));
+        let id = match self.parent_id {
+            Some(parent_id) => parent_id + id,
+            None => self.page_context.inline_prefixed(id),
+        };
+        let id = self.page_context.unique_heading_id(id);
 
-        let inner_level = HeadingLevel::try_from(level as usize + 1)
-            .expect("unable to increase the heading level");
+        // There is no level below h6, where the deepest headings stay
+        let inner_level = match self.shift {
+            true => HeadingLevel::try_from(level as usize + 1).unwrap_or(HeadingLevel::H6),
+            false => level,
+        };
 
-        let start_html = format!("<{} class=\"rd-anchor\" id=\"{}\">", inner_level, id);
+        let id_text = id.to_string();
+        let start_html = format!(
+            "<{} class=\"rd-anchor\" id=\"{}\">",
+            inner_level,
+            Escaped(&id_text)
+        );
 
         let end_html = format!(
             "<a aria-label=\"anchor\" href=\"{}\">\
                                <i class=\"bi bi-hash\"></i></a></{}>",
-            id.with_pound(),
+            Escaped(&id.with_pound().to_string()),
             inner_level
         );
 
@@ -437,14 +904,115 @@ impl<'a, 'toc, 'vec, I: Iterator<Item = Event<'a>>> Iterator for Headings<'a, 't
     }
 }
 
+/// Move the footnotes definitions at the end of the block, with backlinks to their references
+struct Footnotes<'a, I: Iterator<Item = Event<'a>>> {
+    inner: I,
+    /// Prefix of the ids, unique to the block on the page
+    prefix: String,
+    footnotes: Vec<(CowStr<'a>, Vec<Event<'a>>)>,
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Footnotes<'a, I> {
+    fn new(iter: I, prefix: String) -> Self {
+        Self {
+            inner: iter,
+            prefix,
+            footnotes: Vec::new(),
+        }
+    }
+
+    /// Number (starting at 1) of the footnote with this name, in order of appearance
+    fn number_of(&mut self, name: CowStr<'a>) -> usize {
+        match self.footnotes.iter().position(|(n, _)| *n == name) {
+            Some(index) => index + 1,
+            None => {
+                self.footnotes.push((name, Vec::new()));
+                self.footnotes.len()
+            }
+        }
+    }
+
+    fn id(&self, kind: &str, number: usize) -> String {
+        format!("{}{}{}", self.prefix, kind, number)
+    }
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Iterator for Footnotes<'a, I> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next() {
+                Some(Event::FootnoteReference(name)) => {
+                    let number = self.number_of(name);
+                    return Some(Event::Html(
+                        format!(
+                            "<sup id=\"{}\" class=\"footnote-reference\"><a href=\"#{}\">{}</a></sup>",
+                            Escaped(&self.id("fnref", number)),
+                            Escaped(&self.id("fn", number)),
+                            number
+                        )
+                        .into(),
+                    ));
+                }
+                Some(Event::Start(Tag::FootnoteDefinition(name))) => {
+                    let number = self.number_of(name);
+                    let content = &mut self.footnotes[number - 1].1;
+                    for event in &mut self.inner {
+                        if let Event::End(TagEnd::FootnoteDefinition) = event {
+                            break;
+                        }
+                        content.push(event);
+                    }
+                }
+                Some(event) => return Some(event),
+                None if self.footnotes.is_empty() => return None,
+                None => {
+                    let footnotes = std::mem::take(&mut self.footnotes);
+
+                    let mut html = String::from("<div class=\"footnotes\"><hr><ol>");
+                    for (index, (_, content)) in footnotes.into_iter().enumerate() {
+                        let number = index + 1;
+                        let mut content_html = String::new();
+                        html::push_html(&mut content_html, content.into_iter());
+
+                        let backref = format!(
+                            "&nbsp;<a href=\"#{}\" class=\"footnote-backref\">↩</a>",
+                            Escaped(&self.id("fnref", number))
+                        );
+                        if content_html.ends_with("</p>\n") {
+                            content_html.insert_str(content_html.len() - "</p>\n".len(), &backref);
+                        } else {
+                            content_html.push_str(&backref);
+                        }
+
+                        html.push_str(&format!(
+                            "<li id=\"{}\" class=\"rd-anchor\">{}</li>",
+                            Escaped(&self.id("fn", number)),
+                            content_html
+                        ));
+                    }
+                    html.push_str("</ol></div>");
+
+                    return Some(Event::Html(html.into()));
+                }
+            }
+        }
+    }
+}
+
 /// Format a litle bit diffrently the Codeblocks
 struct CodeBlocks<'a, I: Iterator<Item = Event<'a>>> {
     inner: I,
+    mermaid: bool,
 }
 
 impl<'a, I: Iterator<Item = Event<'a>>> CodeBlocks<'a, I> {
-    fn new(iter: I) -> Self {
-        Self { inner: iter }
+    fn new(iter: I, mermaid: bool) -> Self {
+        Self {
+            inner: iter,
+            mermaid,
+        }
     }
 }
 
@@ -462,11 +1030,45 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for CodeBlocks<'a, I> {
 
         let _lang = match kind {
             pulldown_cmark::CodeBlockKind::Indented => Default::default(),
+            pulldown_cmark::CodeBlockKind::Fenced(ref lang_string)
+                if self.mermaid && lang_string.trim() == "mermaid" =>
+            {
+                let mut html = String::from("<div class=\"mermaid\">");
+                for event in &mut self.inner {
+                    match event {
+                        Event::End(TagEnd::CodeBlock) => break,
+                        Event::Text(ref s) => escape_html(&mut html, s).unwrap(),
+                        _ => {}
+                    }
+                }
+                html.push_str("</div>");
+
+                return Some(Event::Html(html.into()));
+            }
             pulldown_cmark::CodeBlockKind::Fenced(ref lang_string) => {
                 let lang = LangString::parse(lang_string);
 
                 if !lang.rust {
-                    return Some(Event::Start(Tag::CodeBlock(kind)));
+                    let class = lang_string.split([',', ' ', '\t']).next().unwrap_or("");
+                    let mut html = String::from("<pre>");
+                    html.push_str(&CopyButton {}.to_string());
+                    if class.is_empty() {
+                        html.push_str("<code>");
+                    } else {
+                        html.push_str("<code class=\"language-");
+                        escape_html(&mut html, class).unwrap();
+                        html.push_str("\">");
+                    }
+                    for event in &mut self.inner {
+                        match event {
+                            Event::End(TagEnd::CodeBlock) => break,
+                            Event::Text(ref s) => escape_html(&mut html, s).unwrap(),
+                            _ => {}
+                        }
+                    }
+                    html.push_str("</code></pre>");
+
+                    return Some(Event::Html(html.into()));
                 }
 
                 lang
@@ -476,7 +1078,7 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for CodeBlocks<'a, I> {
         let mut original_code = String::new();
         for event in &mut self.inner {
             match event {
-                Event::End(Tag::CodeBlock(..)) => break,
+                Event::End(TagEnd::CodeBlock) => break,
                 Event::Text(ref s) => {
                     original_code.push_str(s);
                 }
@@ -498,8 +1100,10 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for CodeBlocks<'a, I> {
         let stripped_code = lines.collect::<Vec<Cow<'_, str>>>().join("\n");
 
         let mut html = String::with_capacity(50 + stripped_code.len());
-        html.push_str("<pre><code class=\"language-rust\">");
-        escape::escape_html(&mut html, &stripped_code).unwrap();
+        html.push_str("<pre>");
+        html.push_str(&CopyButton {}.to_string());
+        html.push_str("<code class=\"language-rust\">");
+        escape_html(&mut html, &stripped_code).unwrap();
         html.push_str("</code></pre>");
 
         Some(Event::Html(html.into()))
@@ -566,7 +1170,7 @@ impl LangString {
         };
 
         string
-            .split(|c| c == ',' || c == ' ' || c == '\t')
+            .split([',', ' ', '\t'])
             .map(str::trim)
             .map(|token| token.strip_prefix('.').unwrap_or(token))
             .filter(|token| !token.is_empty())
@@ -682,3 +1286,125 @@ impl LangString {
         data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Html of the first sentence of the markdown, `None` when it's the whole text
+    fn first_sentence_html(markdown: &str) -> Option<String> {
+        let events: Vec<_> = Parser::new_ext(markdown, summary_opts()).collect();
+        let sentence = first_sentence(&events)?;
+        let mut html = String::new();
+        html::push_html(&mut html, sentence.into_iter());
+        Some(html)
+    }
+
+    #[test]
+    fn first_sentence_cut() {
+        assert_eq!(
+            first_sentence_html("Does a thing. And an other one.").as_deref(),
+            Some("<p>Does a thing. …</p>\n")
+        );
+        assert_eq!(
+            first_sentence_html("Really? Yes.").as_deref(),
+            Some("<p>Really? …</p>\n")
+        );
+    }
+
+    #[test]
+    fn first_sentence_whole_text() {
+        assert_eq!(first_sentence_html("Does a thing."), None);
+        assert_eq!(first_sentence_html("Does a thing.  "), None);
+        assert_eq!(first_sentence_html("Does a *thing.*"), None);
+        assert_eq!(first_sentence_html("No end of sentence"), None);
+    }
+
+    #[test]
+    fn first_sentence_not_ends() {
+        assert_eq!(
+            first_sentence_html("Uses e.g. a map, i.e. a table. Then more.").as_deref(),
+            Some("<p>Uses e.g. a map, i.e. a table. …</p>\n")
+        );
+        assert_eq!(first_sentence_html("Added in 1.0.2 and v2.0"), None);
+    }
+
+    #[test]
+    fn first_sentence_closes_the_tags() {
+        assert_eq!(
+            first_sentence_html("Does *a **thing**. And* more.").as_deref(),
+            Some("<p>Does <em>a <strong>thing</strong>. …</em></p>\n")
+        );
+        assert_eq!(
+            first_sentence_html("Calls `run`. Then [stops](https://example.com).").as_deref(),
+            Some("<p>Calls <code>run</code>. …</p>\n")
+        );
+    }
+
+    #[test]
+    fn chapter_urls() {
+        let chapter_url = |url: &'static str| chapter_url(url.into()).to_string();
+        assert_eq!(chapter_url("usage.md"), "usage.html");
+        assert_eq!(chapter_url("usage.md#options"), "usage.html#options");
+        assert_eq!(chapter_url("guide/README.md"), "guide/index.html");
+        assert_eq!(chapter_url("../index.md#top"), "../index.html#top");
+        assert_eq!(chapter_url("#options"), "#options");
+        assert_eq!(chapter_url("notes.txt"), "notes.txt");
+        assert_eq!(chapter_url("/usage.md"), "/usage.md");
+        assert_eq!(
+            chapter_url("https://example.com/usage.md"),
+            "https://example.com/usage.md"
+        );
+    }
+
+    #[test]
+    fn feature_descriptions_of_the_section() {
+        let docs = "\
+Some crate.
+
+## Features
+
+- `std`: Use the standard library.
+- **serde** - Serialize the types
+  over the wire.
+  - `nested`: not a feature.
+- Without a name.
+
+## Usage
+
+- `unrelated`: not a feature.
+";
+        assert_eq!(
+            feature_descriptions(docs),
+            [
+                ("std".to_string(), "Use the standard library.".to_string()),
+                (
+                    "serde".to_string(),
+                    "Serialize the types over the wire.".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn feature_descriptions_subsections() {
+        let docs = "\
+# Crate
+
+## Features
+
+### Optional
+
+- `alloc`
+
+# Other
+
+- `unrelated`
+";
+        assert_eq!(
+            feature_descriptions(docs),
+            [("alloc".to_string(), String::new())]
+        );
+        assert!(feature_descriptions("- `std`: no section").is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Streaming HTML minification (`--minify`)
+
+use std::io::{self, Write};
+
+/// Elements whose whitespace around them is not rendered
+const BLOCK_ELEMENTS: &[&str] = &[
+    "html",
+    "head",
+    "body",
+    "title",
+    "meta",
+    "link",
+    "script",
+    "style",
+    "div",
+    "p",
+    "section",
+    "nav",
+    "main",
+    "header",
+    "footer",
+    "aside",
+    "article",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "dl",
+    "dt",
+    "dd",
+    "table",
+    "thead",
+    "tbody",
+    "tfoot",
+    "tr",
+    "td",
+    "th",
+    "pre",
+    "blockquote",
+    "hr",
+    "br",
+    "details",
+    "summary",
+];
+
+/// Elements whose content is written as is
+const RAW_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// Writer collapsing the runs of whitespace of the HTML written through it, dropping the
+/// ones between block elements and the comments, leaving the raw elements untouched
+pub(super) struct Minify<W: Write> {
+    inner: W,
+    enabled: bool,
+    /// Tag being written, from its `<`
+    tag: Vec<u8>,
+    quote: Option<u8>,
+    /// Whitespace to write before the next text or tag
+    pending: Option<u8>,
+    after_block: bool,
+    /// Raw element we are in
+    raw: Option<&'static str>,
+}
+
+impl<W: Write> Minify<W> {
+    pub(super) fn new(inner: W, enabled: bool) -> Self {
+        Minify {
+            inner,
+            enabled,
+            tag: Vec::new(),
+            quote: None,
+            pending: None,
+            after_block: false,
+            raw: None,
+        }
+    }
+
+    fn end_tag(&mut self, out: &mut Vec<u8>) {
+        let (closing, rest) = match self.tag[1..].strip_prefix(b"/") {
+            Some(rest) => (true, rest),
+            None => (false, &self.tag[1..]),
+        };
+        let name = rest
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric())
+            .map(|b| b.to_ascii_lowercase() as char)
+            .collect::<String>();
+
+        if let Some(raw) = self.raw {
+            if closing && name == raw {
+                self.raw = None;
+                self.after_block = BLOCK_ELEMENTS.contains(&raw);
+            }
+        } else {
+            let block = name.is_empty() || BLOCK_ELEMENTS.contains(&name.as_str());
+            if let Some(whitespace) = self.pending.take() {
+                if !(self.after_block && block) {
+                    out.push(whitespace);
+                }
+            }
+            self.after_block = block;
+            if !closing {
+                self.raw = RAW_ELEMENTS.iter().copied().find(|raw| *raw == name);
+            }
+        }
+
+        out.append(&mut self.tag);
+    }
+}
+
+impl<W: Write> Write for Minify<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.enabled {
+            return self.inner.write(buf);
+        }
+
+        let mut out = Vec::with_capacity(buf.len());
+        for &b in buf {
+            // In a raw element, a `<` restarts the tag: the previous one was some text
+            if b == b'<' && (self.tag.is_empty() || self.raw.is_some()) {
+                out.append(&mut self.tag);
+                self.tag.push(b);
+            } else if !self.tag.is_empty() {
+                self.tag.push(b);
+                if self.raw.is_none() && self.tag.starts_with(b"<!--") {
+                    // Comments are dropped, the whitespace around them is kept pending
+                    if self.tag.len() >= 7 && self.tag.ends_with(b"-->") {
+                        self.tag.clear();
+                    }
+                    continue;
+                }
+                match (self.quote, b) {
+                    (Some(quote), _) if quote == b => self.quote = None,
+                    (None, b'"' | b'\'') if self.raw.is_none() => self.quote = Some(b),
+                    (None, b'>') => self.end_tag(&mut out),
+                    _ => {}
+                }
+            } else if self.raw.is_some() {
+                out.push(b);
+            } else if b.is_ascii_whitespace() {
+                self.pending = Some(if b == b'\n' || self.pending == Some(b'\n') {
+                    b'\n'
+                } else {
+                    b' '
+                });
+            } else {
+                out.extend(self.pending.take());
+                out.push(b);
+                self.after_block = false;
+            }
+        }
+
+        self.inner.write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minify(html: &str) -> String {
+        let mut minify = Minify::new(Vec::new(), true);
+        // Byte by byte, as the tags can be split across the writes
+        for byte in html.as_bytes() {
+            minify.write_all(std::slice::from_ref(byte)).unwrap();
+        }
+        String::from_utf8(minify.inner).unwrap()
+    }
+
+    #[test]
+    fn whitespace_between_blocks() {
+        assert_eq!(
+            minify("<div>\n    <p>  Some   text  </p>\n</div>\n"),
+            "<div><p> Some text </p></div>"
+        );
+    }
+
+    #[test]
+    fn whitespace_between_inlines() {
+        assert_eq!(minify("<a>x</a>   \t <b>y</b>"), "<a>x</a> <b>y</b>");
+        assert_eq!(minify("<a>x</a>\n    <b>y</b>"), "<a>x</a>\n<b>y</b>");
+        assert_eq!(
+            minify("<code>a</code> <code>b</code>"),
+            "<code>a</code> <code>b</code>"
+        );
+    }
+
+    #[test]
+    fn raw_elements_preserved() {
+        assert_eq!(
+            minify("<pre>  fn  main() {\n    <span>x</span>\n}</pre>  <p> a </p>"),
+            "<pre>  fn  main() {\n    <span>x</span>\n}</pre><p> a </p>"
+        );
+        assert_eq!(
+            minify("<script>\n  if (a  <  b && c > d) {}\n  // <!-- x -->\n</script>\n<p>a</p>"),
+            "<script>\n  if (a  <  b && c > d) {}\n  // <!-- x -->\n</script><p>a</p>"
+        );
+        assert_eq!(
+            minify("<script>let s = \"</p>  \";</script>"),
+            "<script>let s = \"</p>  \";</script>"
+        );
+    }
+
+    #[test]
+    fn comments_removed() {
+        assert_eq!(
+            minify("<p>a <!-- <b> > c --> d</p><!---->\n<div></div>"),
+            "<p>a d</p><div></div>"
+        );
+    }
+
+    #[test]
+    fn quoted_attributes() {
+        assert_eq!(
+            minify("<a title=\"a  >  b\"   href='#x'>y</a>"),
+            "<a title=\"a  >  b\"   href='#x'>y</a>"
+        );
+    }
+}
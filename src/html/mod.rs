@@ -1,8 +1,18 @@
 //! HTML output generation
 
+mod cfg;
 mod constants;
+mod escape;
+mod fragments;
+mod fulltext;
 mod id;
 mod markdown;
+mod minify;
+pub(crate) mod plugins;
 pub(crate) mod render;
+mod sanitize;
+mod search_index;
+mod stats;
 mod templates;
 mod utils;
+mod validate;
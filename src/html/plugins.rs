@@ -0,0 +1,72 @@
+//! Compiled-in item processors adding sections to the pages of the items (`--processor`)
+//!
+//! A fork adds its own sections (benchmark results, coverage badges, ...) by implementing
+//! [`ItemProcessor`] and listing it in [`PROCESSORS`]; the renderer doesn't need patching.
+
+use anyhow::Result;
+use rustdoc_types::{Crate, Item};
+
+/// Processors known to rd, enabled by name with `--processor`
+pub(crate) static PROCESSORS: &[&dyn ItemProcessor] = &[&SourceLocation];
+
+/// What the processors know of the page of an item
+pub(crate) struct ItemContext<'a> {
+    pub(crate) krate: &'a Crate,
+    pub(crate) krate_name: &'a str,
+    /// Rust path of the item, like `krate::module::Item`
+    pub(crate) path: String,
+    /// Kind of the item, like `struct` or `function`
+    pub(crate) kind: &'static str,
+}
+
+/// Section added to the page of an item, after its documentation
+pub(crate) struct PluginSection {
+    pub(crate) title: String,
+    /// Html of the section, rendered as is
+    pub(crate) html: String,
+}
+
+/// A processor of the items, given each item having its own page
+pub(crate) trait ItemProcessor: Sync {
+    /// Name of the processor, for `--processor` and the id of its sections
+    fn name(&self) -> &'static str;
+
+    /// Section to add to the page of the item, if any
+    fn process(&self, item: &Item, context: &ItemContext<'_>) -> Result<Option<PluginSection>>;
+}
+
+/// Processors enabled with `--processor`, in the order of [`PROCESSORS`]
+pub(crate) fn enabled(names: &[String]) -> impl Iterator<Item = &'static dyn ItemProcessor> + '_ {
+    PROCESSORS
+        .iter()
+        .copied()
+        .filter(move |processor| names.iter().any(|name| name == processor.name()))
+}
+
+/// File and line where the item is defined
+struct SourceLocation;
+
+impl ItemProcessor for SourceLocation {
+    fn name(&self) -> &'static str {
+        "source-location"
+    }
+
+    fn process(&self, item: &Item, context: &ItemContext<'_>) -> Result<Option<PluginSection>> {
+        let Some(span) = &item.span else {
+            return Ok(None);
+        };
+
+        Ok(Some(PluginSection {
+            title: "Source".to_string(),
+            html: markup::new! {
+                p {
+                    "The " @context.kind " " code { @context.path } " of " @context.krate_name
+                    @if let Some(version) = &context.krate.crate_version { " " @version }
+                    " is defined in " code { @span.filename.display().to_string() }
+                    ", line " @span.begin.0 "."
+                }
+            }
+            .to_string(),
+        }))
+    }
+}
@@ -1,21 +1,43 @@
 //! HTML renderer
 
 use anyhow::{Context as _, Result};
-use log::{debug, info, trace, warn};
+use markup::DynRender;
 use rustdoc_types::*;
+use serde::Serialize;
 use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{DirBuilder, File};
 use std::io::{BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use tracing::{debug, error, info, info_span, trace, warn};
 use typed_arena::Arena;
 
+use super::cfg::{Cfg, CfgSet};
 use super::constants::*;
+use super::escape::{escape_html, Escaped};
+use super::fragments::{write_fragment, FRAGMENTS_DIR};
+use super::fulltext::FullTextIndex;
 use super::id::Id as HtmlId;
-use super::markdown::{Markdown, MarkdownSummaryLine, MarkdownWithToc};
+use super::markdown::{
+    chapter_page, contract_sections, feature_descriptions, first_heading, readme_links,
+    summary_text, Markdown, MarkdownBanner, MarkdownChapter, MarkdownSummaryLine, MarkdownWithToc,
+};
+use super::minify::Minify;
+use super::plugins::{self, ItemContext, PluginSection};
+use super::search_index::{emitter, SearchEntry};
+use super::stats::RenderStats;
 use super::templates::*;
 use super::utils::*;
+use super::validate::{validate_html, Validate};
+use crate::metadata::{self, CrateMetadata};
+use crate::output;
 use crate::pp;
+use crate::{CfgMode, MermaidSource, Section};
 
 /// A context that is global for all the pages
 pub(super) struct GlobalContext<'krate> {
@@ -24,16 +46,391 @@ pub(super) struct GlobalContext<'krate> {
     pub(super) krate_name: &'krate str,
     pub(super) files: Arena<PathBuf>,
     pub(super) paths: Arena<ItemPath>,
+    /// Rust paths and ids of the items rendered, by page
+    pages: RefCell<HashMap<PathBuf, (String, Option<Id>)>>,
+    /// Html of the items rendered on the page of their module (`--module-single-page`),
+    /// by module page
+    inlined: RefCell<HashMap<PathBuf, String>>,
+    /// Metadata recorded by the pages, merged into the indexes of the crate at the end
+    records: RefCell<Vec<PageRecords>>,
+    /// Full-text index of the docs, when enabled
+    full_text: Option<FullTextIndex>,
+    /// Blanket impls rendered once on a shared page, when enabled
+    shared_blanket_impls: Option<SharedBlanketImpls<'krate>>,
+    /// README of the crate and the items its links resolve to, when provided
+    readme: Option<String>,
+    readme_links: HashMap<String, Id>,
+    /// Documentation of the modules with the files of `--include-doc` appended, and the items
+    /// the links of both resolve to
+    included_docs: IncludedDocs<'krate>,
+    /// Rendering statistics, when enabled
+    stats: Option<RenderStats>,
+    /// Preview of each entry of the search index, in the same order as [`GlobalContext::paths`]
+    previews: RefCell<Vec<SearchPreview>>,
+    /// Plain-text summary line of the items, by item
+    summaries: RefCell<HashMap<Id, Option<Arc<str>>>>,
+    /// Contents of the source files read for `--include-source`, `None` when unreadable
+    sources: RefCell<HashMap<PathBuf, Option<Rc<str>>>>,
+    /// Paths of the items that failed to render (with `--keep-going`) and their error
+    pub(super) failures: RefCell<Vec<(String, String)>>,
+    /// Metadata of the crate, when provided
+    pub(super) metadata: Option<CrateMetadata>,
+    /// Classification of the impls, by impl
+    impl_classes: HashMap<&'krate Id, ImplClass>,
+    /// Impls of notable traits, by implementing type
+    notable_impls: HashMap<&'krate Id, Vec<&'krate Item>>,
+    /// Traits declaring the local associated items (and the items of the impls of foreign
+    /// traits), by item
+    pub(super) trait_items: HashMap<&'krate Id, &'krate Id>,
+    /// Methods of the impls of foreign traits overriding a method provided by the trait
+    pub(super) provided_methods: HashSet<&'krate Id>,
+    /// Guessed documentation roots of the external crates without `html_root_url`
+    pub(super) external_urls: HashMap<u32, String>,
+    /// Number of links dropped because of an external crate without documentation root
+    pub(super) unresolved: RefCell<BTreeMap<u32, usize>>,
+    /// Local items (and their associated items) whose generics require a trait, by trait
+    required_by: HashMap<&'krate Id, Vec<ListedItem<'krate>>>,
+    /// Spans of the local modules, by file and start
+    module_spans: Vec<(&'krate Span, &'krate Id)>,
+    /// Features and cfgs selected to annotate or hide the items
+    pub(super) cfg_set: CfgSet,
+    /// Year of the copyright notice of the pages
+    pub(super) year: u32,
+    /// Names of the static files in the output
+    pub(super) static_files: StaticFileNames,
+    /// Name of the mermaid bundled in the output, with `--mermaid`
+    pub(super) bundled_mermaid: Option<String>,
+    /// Buffers of the pretty-printed tokens already rendered, reused by the next items
+    token_buffers: RefCell<Vec<Vec<pp::Token<'static>>>>,
+    /// Page of the module of `--only`, when rendered
+    only_page: RefCell<Option<PathBuf>>,
+    /// Pages of the parent modules of the module of `--only`, only listing it
+    partial_pages: RefCell<Vec<PathBuf>>,
+}
+
+/// Number of token buffers kept for reuse, more being rarely alive at the same time
+const TOKEN_BUFFERS: usize = 256;
+
+impl<'krate> GlobalContext<'krate> {
+    /// Pretty-print an item, in a reused buffer when there is one
+    pub(super) fn tokens(
+        &self,
+        item: &'krate Item,
+    ) -> Result<pp::Tokens<'krate>, pp::FromItemErrorKind> {
+        let buffer = self.token_buffers.borrow_mut().pop().unwrap_or_default();
+        pp::Tokens::from_item_in(item, &self.krate.index, buffer)
+    }
+
+    /// Plain-text summary line of an item, computed once and shared by its search preview and
+    /// the tooltips of the links to its page
+    fn summary_line(&self, item: &Item) -> Option<Arc<str>> {
+        self.summaries
+            .borrow_mut()
+            .entry(item.id.clone())
+            .or_insert_with(|| {
+                item.docs
+                    .as_deref()
+                    .map(summary_text)
+                    .filter(|summary| !summary.is_empty())
+                    .map(Arc::from)
+            })
+            .clone()
+    }
+
+    /// Classification of an impl of the crate
+    fn impl_class(&self, id: &Id) -> Option<ImplClass> {
+        self.impl_classes.get(id).copied()
+    }
+
+    /// Give back the buffer of tokens no longer needed, for the next items
+    fn recycle(&self, tokens: pp::Tokens<'_>) {
+        let mut token_buffers = self.token_buffers.borrow_mut();
+        if token_buffers.len() < TOKEN_BUFFERS {
+            token_buffers.push(tokens.into_buffer());
+        }
+    }
+}
+
+impl GlobalContext<'_> {
+    /// Keep the metadata recorded by a page, once it's rendered
+    fn add_records(&self, page_context: &PageContext<'_>) {
+        let mut records = page_context.records.take();
+        records.page = page_context.filepath.to_path_buf();
+        self.records.borrow_mut().push(records);
+    }
+
+    /// Take a page for an item (or for the crate, without an id), two items rendering to the
+    /// same page would silently overwrite each other
+    fn take_page(&self, page: &std::path::Path, rust_path: &str, id: Option<&Id>) -> Result<()> {
+        if let Some((other, _)) = self
+            .pages
+            .borrow_mut()
+            .insert(page.to_path_buf(), (rust_path.to_string(), id.cloned()))
+        {
+            anyhow::bail!(
+                "path clash: {} and {} both render to {:?}",
+                other,
+                rust_path,
+                page
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the item of this path is rendered, with `--only` when it's in the subtree of
+    /// the module or one of its parent modules
+    fn is_rendered(&self, item: &Item, path: &str) -> bool {
+        let Some(only) = &self.opt.only else {
+            return true;
+        };
+        let within = |parent: &str, path: &str| {
+            path.strip_prefix(parent)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        };
+        within(only, path) || (matches!(item.inner, ItemEnum::Module(_)) && within(path, only))
+    }
+
+    /// Whether an item is hidden because its cfgs don't match the selected ones
+    pub(super) fn cfg_hidden<T: AsRef<str>>(&self, attrs: &[T]) -> bool {
+        self.opt.cfg_mode == CfgMode::Filter && self.cfg_set.is_disabled(attrs)
+    }
+
+    /// Whether the impls and items of a section with `len` of them start expanded
+    pub(super) fn section_open(&self, section: Section, len: usize) -> bool {
+        self.opt.open_sections.contains(&section)
+            && self
+                .opt
+                .collapse_threshold
+                .is_none_or(|threshold| len <= threshold)
+    }
 }
 
 /// A context that is unique from each page
 pub(super) struct PageContext<'context> {
-    #[allow(dead_code)]
     item: &'context Item,
     pub(super) filepath: &'context PathBuf,
     pub(super) filename: PathBuf,
     pub(super) item_path: &'context ItemPath,
     pub(super) ids: Arena<HtmlId>,
+    impl_ids: RefCell<HashMap<String, usize>>,
+    heading_ids: RefCell<HashSet<String>>,
+    /// Ids of the associated items rendered on the page, for the links to them
+    pub(super) assoc_ids: RefCell<HashMap<Id, String>>,
+    /// Anchor of the item on the page of its module, when rendered there
+    /// (`--module-single-page`)
+    pub(super) inline_id: Option<String>,
+    /// Metadata recorded while rendering the page
+    records: RefCell<PageRecords>,
+    /// Number of the blocks of documentation without an item rendered on the page
+    footnote_blocks: Cell<usize>,
+}
+
+/// Metadata of a page for the indexes of the crate, recorded while rendering it
+#[derive(Default)]
+struct PageRecords {
+    /// File of the page
+    page: PathBuf,
+    /// Rust paths of the items (and associated items) of the page to their page and fragment
+    anchors: Vec<(String, String)>,
+    /// Ids of the associated items in the other formats (by older rd or rustdoc) to their
+    /// current one, the current ids being their own alias
+    anchor_aliases: Vec<(String, String)>,
+    /// Legacy impl ids to their stable ones
+    impl_redirects: Vec<(String, String)>,
+}
+
+impl<'context> PageContext<'context> {
+    /// Id prefixed by the anchor of the item when it's rendered on the page of its module,
+    /// the other items of the page having the same ones
+    pub(super) fn inline_prefixed(&self, id: HtmlId) -> HtmlId {
+        match &self.inline_id {
+            Some(inline_id) => HtmlId::new(format!("{}.{}", inline_id, id)),
+            None => id,
+        }
+    }
+
+    /// Prefix of the footnotes ids of a block of documentation, unique on the page: the id
+    /// of its item, otherwise the rank of the block after the first one
+    pub(super) fn footnotes_prefix(&self, parent_id: Option<&HtmlId>) -> String {
+        if let Some(parent_id) = parent_id {
+            return format!("{}.", parent_id);
+        }
+
+        let block = self.footnote_blocks.get();
+        self.footnote_blocks.set(block + 1);
+        match (block, &self.inline_id) {
+            (0, None) => String::new(),
+            (0, Some(inline_id)) => format!("{}.", inline_id),
+            (block, _) => format!(
+                "{}.",
+                self.inline_prefixed(HtmlId::new(format!("block{}", block)))
+            ),
+        }
+    }
+
+    /// Disambiguate the impls whose stable ids are the same on this page
+    fn unique_impl_id(&self, id: HtmlId) -> HtmlId {
+        let id = self.inline_prefixed(id);
+        let mut impl_ids = self.impl_ids.borrow_mut();
+        let count = impl_ids.entry(id.to_string()).or_default();
+        *count += 1;
+
+        if *count == 1 {
+            id
+        } else {
+            HtmlId::new(format!("{}-{}", id, count))
+        }
+    }
+
+    /// Disambiguate the ids of the headings of the docs like rustdoc and GitHub (`examples-1`),
+    /// between them and with the [`RESERVED_IDS`] of the page (like `implementations`)
+    pub(super) fn unique_heading_id(&self, id: HtmlId) -> HtmlId {
+        let mut heading_ids = self.heading_ids.borrow_mut();
+        let is_reserved = |id: &str| {
+            let id = match &self.inline_id {
+                Some(inline_id) => id
+                    .strip_prefix(inline_id.as_str())
+                    .and_then(|id| id.strip_prefix('.')),
+                None => Some(id),
+            };
+            id.is_some_and(|id| RESERVED_IDS.contains(&id))
+        };
+        let mut unique = id.to_string();
+        let mut count = 0;
+        while heading_ids.contains(&unique) || is_reserved(&unique) {
+            count += 1;
+            unique = format!("{}-{}", id, count);
+        }
+
+        heading_ids.insert(unique.clone());
+        HtmlId::new(unique)
+    }
+
+    /// Disambiguate the ids of the associated items like rustdoc (`method.foo-1`)
+    fn rustdoc_unique_id(&self, id: HtmlId) -> HtmlId {
+        let id = self.inline_prefixed(id);
+        let mut impl_ids = self.impl_ids.borrow_mut();
+        let count = impl_ids.entry(id.to_string()).or_default();
+        *count += 1;
+
+        if *count == 1 {
+            id
+        } else {
+            HtmlId::new(format!("{}-{}", id, *count - 1))
+        }
+    }
+}
+
+/// Distinct blanket impls of a crate, rendered once on the [`BLANKET_IMPLS_HTML`] page
+struct SharedBlanketImpls<'krate> {
+    /// Generic version (`for T`) of the impls and their id, by their signature
+    impls: BTreeMap<String, (&'krate Item, HtmlId)>,
+    /// Signature of the generic version of each blanket impl, by the id of the impl
+    signatures: HashMap<&'krate Id, String>,
+}
+
+impl<'krate> SharedBlanketImpls<'krate> {
+    /// Generic version of all the blanket impls of the crate, to be given to [`Self::new`]
+    fn generic_impls(krate: &Crate) -> Vec<Item> {
+        let mut generic_impls: Vec<Item> = krate
+            .index
+            .values()
+            .filter_map(|item| match &item.inner {
+                ItemEnum::Impl(Impl {
+                    blanket_impl: Some(blanket_impl),
+                    ..
+                }) => {
+                    let mut item = item.clone();
+                    if let ItemEnum::Impl(impl_) = &mut item.inner {
+                        impl_.for_ = blanket_impl.clone();
+                    }
+                    Some(item)
+                }
+                _ => None,
+            })
+            .collect();
+        generic_impls.sort_by(|x, y| x.id.0.cmp(&y.id.0));
+        generic_impls
+    }
+
+    fn new(krate: &'krate Crate, generic_impls: &'krate [Item]) -> Self {
+        let mut impls = BTreeMap::new();
+        let mut signatures = HashMap::new();
+        for item in generic_impls {
+            if let Ok(tokens) = pp::Tokens::from_item(item, &krate.index) {
+                let signature = tokens.to_string();
+                impls.entry(signature.clone()).or_insert(item);
+                signatures.insert(&item.id, signature);
+            }
+        }
+
+        // Same disambiguation as `PageContext::unique_impl_id`, in the order of the page
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let impls = impls
+            .into_iter()
+            .filter_map(|(signature, item)| {
+                let (_, id) = id(item)?;
+                let count = counts.entry(id.to_string()).or_default();
+                *count += 1;
+                let id = match *count {
+                    1 => id,
+                    count => HtmlId::new(format!("{}-{}", id, count)),
+                };
+                Some((signature, (item, id)))
+            })
+            .collect();
+
+        SharedBlanketImpls { impls, signatures }
+    }
+
+    /// The shared generic version of a blanket impl and its id on the shared page
+    fn get(&self, item: &Item) -> Option<&(&'krate Item, HtmlId)> {
+        self.impls.get(self.signatures.get(&item.id)?)
+    }
+}
+
+/// Summary line and signature of an item, shown next to the search results
+#[derive(Serialize)]
+pub(super) struct SearchPreview {
+    summary: Option<Arc<str>>,
+    signature: Option<String>,
+    since: Option<String>,
+    /// Other names of the item, from `#[doc(alias)]`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    /// Visibility of the item when it isn't public
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visibility: Option<&'static str>,
+}
+
+impl SearchPreview {
+    /// Maximum number of lines of the signature, the rest being elided
+    const MAX_SIGNATURE_LINES: usize = 12;
+
+    fn of(global_context: &GlobalContext<'_>, item: &Item) -> Self {
+        let signature = pp::Tokens::from_item(item, &global_context.krate.index)
+            .ok()
+            .map(|tokens| {
+                let signature = tokens.to_string();
+                let mut lines: Vec<&str> = signature.lines().map(str::trim_end).collect();
+                if lines.len() > Self::MAX_SIGNATURE_LINES {
+                    lines.truncate(Self::MAX_SIGNATURE_LINES);
+                    lines.push("    // ...");
+                }
+                lines.join("\n")
+            });
+
+        SearchPreview {
+            summary: global_context.summary_line(item),
+            signature,
+            since: since_version(&item.attrs).map(str::to_string),
+            aliases: doc_aliases(&item.attrs)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            visibility: private_visibility(item),
+        }
+    }
 }
 
 /// Path to an item; slice of [`ItemPathComponent`]
@@ -47,6 +444,12 @@ pub(crate) struct ItemPathComponent {
 }
 
 impl<'context> ItemPath {
+    /// Rust path (`krate::module::Item`) of the item
+    fn to_rust_path(&self) -> String {
+        let names: Vec<&str> = self.0.iter().map(|c| c.name.as_str()).collect();
+        names.join("::")
+    }
+
     /// Create a `markup`able version of an [`ItemPath`]
     fn display(
         &'context self,
@@ -69,10 +472,10 @@ impl<'context> markup::Render for ItemPathDisplay<'context> {
 
             writer.write_str("\" href=\"")?;
             let href = relative(self.1.filepath, &item_path_component.filepath);
-            writer.write_fmt(format_args!("{}", href.display()))?;
+            escape_html(writer, &href.to_string_lossy())?;
 
             writer.write_str("\">")?;
-            writer.write_str(&item_path_component.name)?;
+            escape_html(writer, &item_path_component.name)?;
 
             writer.write_str("</a>")?;
         }
@@ -83,7 +486,8 @@ impl<'context> markup::Render for ItemPathDisplay<'context> {
 pub struct TocSection<'toc> {
     pub(super) name: &'static str,
     pub(super) id: &'static str,
-    pub(super) items: Vec<(Cow<'toc, str>, TocDestination<'toc>)>,
+    /// Name, destination and tooltip (summary line of the item) of the entries
+    pub(super) items: Vec<(Cow<'toc, str>, TocDestination<'toc>, Option<Arc<str>>)>,
 }
 
 enum TocSupplier<Supply> {
@@ -99,12 +503,8 @@ pub enum TocDestination<'a> {
 impl<'a> markup::Render for TocDestination<'a> {
     fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
         match self {
-            TocDestination::Id(id) => {
-                write!(writer, "{}", id.with_pound())
-            }
-            TocDestination::File(path) => {
-                write!(writer, "{}", path.display())
-            }
+            TocDestination::Id(id) => id.with_pound().render(writer),
+            TocDestination::File(path) => escape_html(writer, &path.to_string_lossy()),
         }
     }
 }
@@ -119,38 +519,573 @@ impl<'deprecation> DeprecationNotice<'deprecation> {
 }
 
 impl<'portability> PortabilityNotice<'portability> {
-    fn from<T: AsRef<str>>(attrs: &'portability [T]) -> Result<Option<Self>> {
+    fn from<T: AsRef<str>>(
+        global_context: &GlobalContext<'_>,
+        attrs: &'portability [T],
+    ) -> Result<Option<Self>> {
+        let disabled = global_context.cfg_set.is_disabled(attrs);
         Ok(Portability::from_attrs(attrs)?
             .as_ref()
             .map(Portability::render_long)
             .map(|(message, portability)| Self {
-                message,
+                message: match disabled {
+                    true => "Not available with the selected features and cfgs, the portability is defined by: ",
+                    false => message,
+                },
                 portability,
+                disabled,
             }))
     }
 }
 
+impl<'must_use> MustUseNotice<'must_use> {
+    fn from(item: &'must_use Item) -> Option<Self> {
+        MustUse::from_attrs(&item.attrs)
+            .map(|must_use| must_use.render_long(item))
+            .map(|(message, reason)| Self { message, reason })
+    }
+}
+
+impl ContractBadges {
+    /// Panics, errors and safety sections of the docs of a function, linking to their
+    /// headings (prefixed by the id of the function on the page of its parent)
+    fn from(
+        page_context: &PageContext<'_>,
+        parent_id: Option<&HtmlId>,
+        item: &Item,
+    ) -> Option<Self> {
+        let sections = contract_sections(item.docs.as_deref()?)
+            .into_iter()
+            .map(|(name, slug)| {
+                let id = HtmlId::new(slug);
+                (
+                    name,
+                    match parent_id {
+                        Some(parent_id) => parent_id + id,
+                        None => page_context.inline_prefixed(id),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        (!sections.is_empty()).then_some(Self { sections })
+    }
+}
+
+impl<'context, 'krate> NotableTraits<TokensToHtml<'context, 'krate>> {
+    /// Notable traits implemented by the return type of a function
+    fn from(
+        global_context: &'context GlobalContext<'krate>,
+        page_context: &'context PageContext<'context>,
+        item: &'krate Item,
+    ) -> Option<Self> {
+        let ItemEnum::Function(function_) = &item.inner else {
+            return None;
+        };
+        let id = type_id(function_.decl.output.as_ref()?).ok()?;
+        let impls = global_context.notable_impls.get(id)?;
+
+        Some(Self {
+            impls: impls
+                .iter()
+                .filter_map(|impl_| {
+                    pp::Tokens::from_item(impl_, &global_context.krate.index)
+                        .map(|tokens| TokensToHtml(global_context, page_context, tokens))
+                        .ok()
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Documentation roots of the external crates without `html_root_url`: the Rust
+/// documentation for the standard crates and else docs.rs, at the version found in the
+/// cargo metadata or the latest one
+fn docs_rs_urls(krate: &Crate, packages: &[metadata::CrateMetadata]) -> HashMap<u32, String> {
+    krate
+        .external_crates
+        .iter()
+        .filter(|(_, external_crate)| external_crate.html_root_url.is_none())
+        .map(|(crate_id, external_crate)| {
+            let name = &external_crate.name;
+            let url = if STD_CRATES.contains(&name.as_str()) {
+                STD_DOCS_URL.to_string()
+            } else {
+                let package = packages
+                    .iter()
+                    .find(|package| package.name.replace('-', "_") == *name);
+                format!(
+                    "https://docs.rs/{}/{}/",
+                    package.map_or(name, |package| &package.name),
+                    package
+                        .and_then(|package| package.version.as_deref())
+                        .unwrap_or("latest")
+                )
+            };
+            debug!("guessed the documentation of {} at {}", name, url);
+            (*crate_id, url)
+        })
+        .collect()
+}
+
+fn module_spans(krate: &Crate) -> Vec<(&Span, &Id)> {
+    let mut module_spans = krate
+        .index
+        .iter()
+        .filter(|(id, item)| {
+            matches!(item.inner, ItemEnum::Module(_))
+                && krate
+                    .paths
+                    .get(id)
+                    .is_some_and(|summary| summary.crate_id == 0)
+        })
+        .filter_map(|(id, item)| Some((item.span.as_ref()?, id)))
+        .collect::<Vec<_>>();
+    // The nested modules sharing a span with their parent come after it, the innermost last
+    module_spans.sort_by_key(|(span, id)| {
+        (
+            &span.filename,
+            span.begin,
+            std::cmp::Reverse(span.end),
+            krate.paths.get(id).map(|summary| summary.path.len()),
+        )
+    });
+    module_spans
+}
+
+/// Innermost local module whose source contains the item
+fn defining_module<'krate>(
+    global_context: &GlobalContext<'krate>,
+    item: &Item,
+) -> Option<&'krate Id> {
+    let span = item.span.as_ref()?;
+    global_context
+        .module_spans
+        .iter()
+        .filter(|(module, _)| {
+            module.filename == span.filename && module.begin <= span.begin && span.end <= module.end
+        })
+        .max_by_key(|(module, _)| module.begin)
+        .map(|(_, id)| *id)
+}
+
+/// Source of a provided trait method, read from its span under `--include-source`
+fn default_body(global_context: &GlobalContext<'_>, item: &Item) -> Option<String> {
+    let root = global_context.opt.include_source.as_ref()?;
+    let span = item.span.as_ref()?;
+    let path = root.join(&span.filename);
+
+    // The provided methods of a trait are in the same file, read it once
+    let source = global_context
+        .sources
+        .borrow_mut()
+        .entry(path.clone())
+        .or_insert_with(|| match std::fs::read_to_string(&path) {
+            Ok(source) => Some(source.into()),
+            Err(err) => {
+                warn!("unable to read the source {:?}: {}", path, err);
+                None
+            }
+        })
+        .clone()?;
+    // The source may have changed since rustdoc
+    let name = item.name.as_deref()?;
+    let body = span_source(&source, span).filter(|body| body.contains(&format!("fn {}", name)));
+    if body.is_none() {
+        warn!("the span of {} doesn't match its source {:?}", name, path);
+    }
+    body
+}
+
+/// Sections of the processors enabled with `--processor` for the page of an item
+fn plugin_sections(
+    global_context: &GlobalContext<'_>,
+    page_context: &PageContext<'_>,
+    item: &Item,
+) -> Result<Vec<(HtmlId, PluginSection)>> {
+    let context = ItemContext {
+        krate: global_context.krate,
+        krate_name: global_context.krate_name,
+        path: page_context.item_path.to_rust_path(),
+        kind: crate::extract::kind(item, false),
+    };
+
+    let mut sections = Vec::new();
+    for processor in plugins::enabled(&global_context.opt.processors) {
+        let section = processor
+            .process(item, &context)
+            .with_context(|| format!("the processor {} failed", processor.name()))?;
+        if let Some(section) = section {
+            let id =
+                page_context.inline_prefixed(HtmlId::new(format!("plugin-{}", processor.name())));
+            sections.push((page_context.unique_heading_id(id), section));
+        }
+    }
+    Ok(sections)
+}
+
+fn trait_items(krate: &Crate) -> HashMap<&Id, &Id> {
+    krate
+        .index
+        .iter()
+        .filter_map(|(id, item)| match &item.inner {
+            ItemEnum::Trait(trait_) => Some((&trait_.items, id)),
+            // The items of the impls of foreign traits are declared on the (foreign) trait page
+            ItemEnum::Impl(Impl {
+                trait_: Some(trait_),
+                items,
+                ..
+            }) if !krate.index.contains_key(&trait_.id) => Some((items, &trait_.id)),
+            _ => None,
+        })
+        .flat_map(|(items, trait_id)| items.iter().map(move |item| (item, trait_id)))
+        .collect()
+}
+
+fn provided_methods(krate: &Crate) -> HashSet<&Id> {
+    krate
+        .index
+        .values()
+        .filter_map(|item| match &item.inner {
+            ItemEnum::Impl(Impl {
+                trait_: Some(trait_),
+                items,
+                provided_trait_methods,
+                ..
+            }) if !krate.index.contains_key(&trait_.id) => Some((items, provided_trait_methods)),
+            _ => None,
+        })
+        .flat_map(|(items, provided_trait_methods)| {
+            items.iter().filter(move |id| {
+                krate
+                    .index
+                    .get(id)
+                    .and_then(|item| item.name.as_ref())
+                    .is_some_and(|name| provided_trait_methods.contains(name))
+            })
+        })
+        .collect()
+}
+
+/// A local item with a page, or one of the associated items (of an impl or a trait) listed
+/// on it
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ListedItem<'krate> {
+    /// Item with a page
+    item: &'krate Id,
+    /// Associated item, when not the item itself
+    assoc_item: Option<&'krate Id>,
+    /// Trait impl of the associated item, scoping its anchor
+    trait_impl: Option<&'krate Id>,
+}
+
+impl<'krate> ListedItem<'krate> {
+    /// The local items with a page and their associated items, in the index order
+    fn all(krate: &'krate Crate) -> impl Iterator<Item = (Self, &'krate Item)> {
+        krate
+            .index
+            .iter()
+            .filter(move |(id, _)| {
+                krate
+                    .paths
+                    .get(*id)
+                    .is_some_and(|summary| summary.crate_id == 0)
+            })
+            .flat_map(move |(id, item)| {
+                let page_item = ListedItem {
+                    item: id,
+                    assoc_item: None,
+                    trait_impl: None,
+                };
+                // The associated items are listed on the page of the trait or the
+                // implementing type
+                let assoc_ids = match &item.inner {
+                    ItemEnum::Trait(trait_) => trait_.items.as_slice(),
+                    ItemEnum::Struct(Struct { impls, .. })
+                    | ItemEnum::Enum(Enum { impls, .. })
+                    | ItemEnum::Union(Union { impls, .. }) => impls.as_slice(),
+                    _ => &[],
+                };
+                let assoc_items = assoc_ids.iter().flat_map(move |assoc_id| {
+                    match krate.index.get(assoc_id).map(|item| &item.inner) {
+                        // The blanket impls are not written for this type
+                        Some(ItemEnum::Impl(Impl {
+                            items,
+                            trait_,
+                            blanket_impl: None,
+                            ..
+                        })) => items
+                            .iter()
+                            .map(|item| (item, trait_.as_ref().map(|_| assoc_id)))
+                            .collect(),
+                        Some(ItemEnum::Impl(_)) => vec![],
+                        _ => vec![(assoc_id, None)],
+                    }
+                });
+                std::iter::once((page_item, item)).chain(assoc_items.filter_map(
+                    move |(assoc_id, trait_impl)| {
+                        let assoc_item = ListedItem {
+                            item: id,
+                            assoc_item: Some(assoc_id),
+                            trait_impl,
+                        };
+                        Some((assoc_item, krate.index.get(assoc_id)?))
+                    },
+                ))
+            })
+    }
+
+    /// Rust path (without the crate), link and kind of the item from the given page
+    fn link(
+        self,
+        global_context: &GlobalContext<'krate>,
+        page_context: &PageContext<'_>,
+    ) -> Option<(String, PathBuf, &'static str)> {
+        let (_, path, _, kind) = href(global_context, page_context, self.item)?;
+        let name = global_context.krate.paths[self.item].path[1..].join("::");
+        let Some(assoc_id) = self.assoc_item else {
+            return Some((name, path, kind));
+        };
+
+        let assoc_item = &global_context.krate.index[assoc_id];
+        let fragment = match (global_context.opt.rustdoc_compat_urls, self.trait_impl) {
+            (true, _) => rustdoc_id(assoc_item)?,
+            // The items of the trait impls are scoped by their impl
+            (false, Some(trait_impl)) => {
+                id(&global_context.krate.index[trait_impl])?.1 + id(assoc_item)?.1
+            }
+            (false, None) => id(assoc_item)?.1,
+        };
+        let kind = match &assoc_item.inner {
+            ItemEnum::Function(_) => "method",
+            ItemEnum::AssocConst { .. } => "associatedconst",
+            _ => "associatedtype",
+        };
+        // The item rendered on the page of its module prefixes the ids by its anchor
+        let link = match path.to_str()?.split_once('#') {
+            Some((page, anchor)) => format!("{}#{}.{}", page, anchor, fragment),
+            None => format!("{}#{}", path.display(), fragment),
+        };
+        Some((
+            format!("{}::{}", name, assoc_item.name.as_deref()?),
+            PathBuf::from(link),
+            kind,
+        ))
+    }
+
+    /// Id of the listed item itself
+    fn id(self) -> &'krate Id {
+        self.assoc_item.unwrap_or(self.item)
+    }
+
+    /// Sort key keeping a stable order independent of the index iteration order
+    fn sort_key(self, krate: &'krate Crate) -> (&'krate [String], Option<&'krate str>) {
+        let name = self
+            .assoc_item
+            .and_then(|assoc_id| krate.index[assoc_id].name.as_deref());
+        (&krate.paths[self.item].path, name)
+    }
+}
+
+/// Local items (and associated items) whose generics require a trait, by trait
+fn required_by(krate: &Crate) -> HashMap<&Id, Vec<ListedItem<'_>>> {
+    let mut required_by: HashMap<_, Vec<_>> = HashMap::new();
+    for (listed_item, item) in ListedItem::all(krate) {
+        let generics = match &item.inner {
+            ItemEnum::Function(function) => &function.generics,
+            ItemEnum::Struct(struct_) => &struct_.generics,
+            ItemEnum::Enum(enum_) => &enum_.generics,
+            ItemEnum::Union(union_) => &union_.generics,
+            ItemEnum::Trait(trait_) => &trait_.generics,
+            ItemEnum::TypeAlias(type_alias) => &type_alias.generics,
+            ItemEnum::AssocType { generics, .. } => generics,
+            _ => continue,
+        };
+
+        let param_bounds = generics
+            .params
+            .iter()
+            .filter_map(|param| match &param.kind {
+                GenericParamDefKind::Type { bounds, .. } => Some(bounds),
+                _ => None,
+            });
+        let predicate_bounds =
+            generics
+                .where_predicates
+                .iter()
+                .filter_map(|predicate| match predicate {
+                    WherePredicate::BoundPredicate { bounds, .. } => Some(bounds),
+                    _ => None,
+                });
+        for bound in param_bounds.chain(predicate_bounds).flatten() {
+            if let GenericBound::TraitBound { trait_, .. } = bound {
+                let listed_items = required_by.entry(&trait_.id).or_default();
+                if !listed_items.contains(&listed_item) {
+                    listed_items.push(listed_item);
+                }
+            }
+        }
+    }
+
+    for listed_items in required_by.values_mut() {
+        listed_items.sort_by_key(|listed_item| listed_item.sort_key(krate));
+    }
+    required_by
+}
+
+/// Kind of the implementing type of an impl
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ImplFor {
+    /// A type of the crate
+    Local,
+    /// A type of an other crate
+    Foreign,
+    Primitive,
+    /// A generic, a tuple, a function pointer, ...
+    Other,
+}
+
+/// Classification of an impl, shared by the trait pages and the type pages
+#[derive(Clone, Copy)]
+struct ImplClass {
+    for_: ImplFor,
+    /// Whether the implemented trait is an auto-trait and its crate id (`None` for the
+    /// inherent and blanket impls, and the unknown traits, warned about when rendered)
+    trait_: Option<(bool, u32)>,
+}
+
+/// Classify all the impls of the crate at once, by impl
+fn impl_classes(krate: &Crate) -> HashMap<&Id, ImplClass> {
+    let auto_traits = auto_traits(krate);
+
+    krate
+        .index
+        .iter()
+        .filter_map(|(id, item)| {
+            let ItemEnum::Impl(impl_) = &item.inner else {
+                return None;
+            };
+
+            let for_ = match type_id(&impl_.for_) {
+                Ok(type_id) => {
+                    let crate_id = krate
+                        .paths
+                        .get(type_id)
+                        .map(|summary| summary.crate_id)
+                        .or_else(|| krate.index.get(type_id).map(|item| item.crate_id));
+                    match crate_id {
+                        Some(0) => ImplFor::Local,
+                        _ => ImplFor::Foreign,
+                    }
+                }
+                Err(Some(ItemKind::Primitive)) => ImplFor::Primitive,
+                Err(_) => ImplFor::Other,
+            };
+            let trait_ = match (&impl_.trait_, &impl_.blanket_impl) {
+                (Some(trait_), None) => auto_traits.get(&trait_.id).copied(),
+                _ => None,
+            };
+            Some((id, ImplClass { for_, trait_ }))
+        })
+        .collect()
+}
+
+/// Build the reverse lookup of the types to their notable traits impls
+fn notable_impls(krate: &Crate) -> HashMap<&Id, Vec<&Item>> {
+    let mut notable_impls: HashMap<_, Vec<_>> = HashMap::new();
+
+    for item in krate.index.values() {
+        let ItemEnum::Impl(impl_) = &item.inner else {
+            continue;
+        };
+        let Some(trait_) = &impl_.trait_ else {
+            continue;
+        };
+        if impl_.blanket_impl.is_some()
+            || !NOTABLE_TRAITS.contains(&trait_.name.rsplit("::").next().unwrap_or_default())
+        {
+            continue;
+        }
+        if let Ok(id) = type_id(&impl_.for_) {
+            notable_impls.entry(id).or_default().push(item);
+        }
+    }
+
+    // Keep a stable order independent of the index iteration order
+    for impls in notable_impls.values_mut() {
+        impls.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+    }
+    notable_impls
+}
+
 fn dump_to<P: AsRef<std::path::Path>>(path: P, buf: &[u8]) -> std::io::Result<()> {
     let mut file = File::create(path)?;
     std::io::Write::write_all(&mut file, buf)?;
     Ok(())
 }
 
+/// Copy a directory and its content, recursively
+fn copy_dir(from: &std::path::Path, to: &std::path::Path) -> Result<()> {
+    DirBuilder::new()
+        .recursive(true)
+        .create(to)
+        .with_context(|| format!("unable to create the directory {:?}", to))?;
+    for entry in std::fs::read_dir(from)
+        .with_context(|| format!("unable to read the directory {:?}", from))?
+    {
+        let path = entry?.path();
+        let dest = to.join(path.file_name().context("entry without a name")?);
+        if path.is_dir() {
+            copy_dir(&path, &dest)?;
+        } else {
+            std::fs::copy(&path, &dest)
+                .with_context(|| format!("unable to copy {:?} to {:?}", path, dest))?;
+        }
+    }
+    Ok(())
+}
+
+/// Fingerprinted name and content of the mermaid bundled in the output, read from the
+/// vendored assets directory
+fn bundled_mermaid(opt: &super::super::Opt) -> Result<Option<(String, Vec<u8>)>> {
+    if opt.mermaid != Some(MermaidSource::Bundled) {
+        return Ok(None);
+    }
+    let vendored_assets = opt.vendored_assets.as_ref().with_context(|| {
+        format!(
+            "--mermaid bundles the {} of --vendored-assets, use --mermaid=cdn to load it from its CDN",
+            MERMAID_JS
+        )
+    })?;
+    let path = vendored_assets.join(MERMAID_JS);
+    let content = std::fs::read(&path)
+        .with_context(|| format!("unable to read the vendored asset {:?}", path))?;
+    Ok(Some((fingerprinted(MERMAID_JS, &content), content)))
+}
+
 pub(crate) fn render_global(opt: &super::super::Opt, _outputs: &[PathBuf]) -> Result<PathBuf> {
     // TODO: Do a global index with the outputs links
 
-    dump_to(
-        format!("{}/{}", &opt.output.display(), STYLE_CSS),
-        include_bytes!("static/css/style.css"),
-    )?;
-    dump_to(
-        format!("{}/{}", &opt.output.display(), RUST_SVG),
-        include_bytes!("static/imgs/rust.svg"),
-    )?;
-    dump_to(
-        format!("{}/{}", &opt.output.display(), SEARCH_JS),
-        include_bytes!("static/js/search.js"),
-    )?;
+    let static_files = StaticFileNames::new();
+    for file in STATIC_FILES {
+        dump_to(opt.output.join(static_files.get(file)), file.content)
+            .with_context(|| format!("unable to write the static file {}", file.name))?;
+    }
+
+    if let Some((name, content)) = bundled_mermaid(opt)? {
+        dump_to(opt.output.join(name), &content)
+            .with_context(|| format!("unable to write the static file {}", MERMAID_JS))?;
+    }
+
+    if let Some(vendored_assets) = &opt.vendored_assets {
+        for asset in VENDORED_ASSETS {
+            let path = vendored_assets.join(asset.vendored);
+            if !path.is_file() {
+                anyhow::bail!("the vendored asset {:?} is missing", path);
+            }
+        }
+        copy_dir(vendored_assets, &opt.output.join(VENDOR_DIR))
+            .context("unable to copy the vendored assets")?;
+    }
 
     Ok(opt.output.clone())
 }
@@ -162,95 +1097,727 @@ pub(crate) fn render<'krate>(
     krate_item: &'krate Item,
 ) -> Result<PathBuf> {
     if let ItemEnum::Module(krate_module) = &krate_item.inner {
+        let krate_name = krate_item.name.as_ref().context("expect a crate name")?;
+        if opt.fragments && krate_name == FRAGMENTS_DIR {
+            anyhow::bail!(
+                "the crate {} clashes with the {:?} directory of the fragments",
+                krate_name,
+                FRAGMENTS_DIR
+            );
+        }
+        let mut packages = match &opt.cargo_metadata {
+            Some(path) => metadata::load(path)?,
+            None => Vec::new(),
+        };
+        let external_urls = match opt.docs_rs_fallback {
+            true => docs_rs_urls(krate, &packages),
+            false => HashMap::new(),
+        };
+        let metadata = packages
+            .iter()
+            .position(|metadata| metadata.name.replace('-', "_") == *krate_name)
+            .map(|index| packages.swap_remove(index));
+        if opt.cargo_metadata.is_some() && metadata.is_none() {
+            warn!("no package named {:?} in the cargo metadata", krate_name);
+        }
+
+        let readme = opt
+            .readme
+            .as_ref()
+            .map(|path| {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("unable to read the readme {:?}", path))
+            })
+            .transpose()?;
+        let generic_blanket_impls = match opt.shared_blanket_impls {
+            true => SharedBlanketImpls::generic_impls(krate),
+            false => Vec::new(),
+        };
         let mut global_context = GlobalContext {
             opt,
             krate,
             files: Default::default(),
             paths: Default::default(),
-            krate_name: krate_item.name.as_ref().context("expect a crate name")?,
+            pages: Default::default(),
+            inlined: Default::default(),
+            records: Default::default(),
+            token_buffers: Default::default(),
+            only_page: Default::default(),
+            partial_pages: Default::default(),
+            full_text: opt.full_text_search.then(Default::default),
+            previews: Default::default(),
+            summaries: Default::default(),
+            sources: Default::default(),
+            stats: opt.stats.then(Default::default),
+            readme_links: readme
+                .as_deref()
+                .map(|readme| readme_links(krate, readme))
+                .unwrap_or_default(),
+            readme,
+            included_docs: included_docs(krate, krate_name, &opt.include_docs)?,
+            shared_blanket_impls: opt
+                .shared_blanket_impls
+                .then(|| SharedBlanketImpls::new(krate, &generic_blanket_impls)),
+            notable_impls: notable_impls(krate),
+            impl_classes: impl_classes(krate),
+            trait_items: trait_items(krate),
+            provided_methods: provided_methods(krate),
+            external_urls,
+            unresolved: Default::default(),
+            required_by: required_by(krate),
+            module_spans: module_spans(krate),
+            cfg_set: CfgSet::new(&opt.features, opt.all_features, &opt.cfg)?,
+            year: year_of(build_timestamp(opt.deterministic)?),
+            static_files: StaticFileNames::new(),
+            bundled_mermaid: bundled_mermaid(opt)?.map(|(name, _)| name),
+            metadata,
+            failures: Default::default(),
+            krate_name,
         };
 
-        let module_page_context = module_page(
-            &global_context,
-            None,
-            krate_item,
-            &global_context.krate_name,
-            krate_module,
-        )?;
-        let module_index_path = global_context.opt.output.join(module_page_context.filepath);
-        let mut search = String::new();
-
-        search.push_str("\n\nconst INDEX = JSON.parse('[");
-        for (iitem, item) in global_context.paths.iter_mut().enumerate() {
-            if iitem != 0 {
-                search.push(',');
+        let only = match &opt.only {
+            Some(only) if !only_module(krate, krate_name, only)? => {
+                info!("skipping {}, {} isn't one of its modules", krate_name, only);
+                return Ok(opt.output.join(krate_name).join("index.html"));
             }
-            search.push_str("{\"components\":[");
-            for (icomponent, component) in item.0.iter().enumerate() {
-                if icomponent != 0 {
-                    search.push(',');
-                }
-                search.push_str("{\"name\":\"");
-                search.push_str(&component.name);
-                search.push_str("\",\"lower_case_name\":\"");
-                search.push_str(&component.name.to_ascii_lowercase());
-                search.push_str("\",\"kind\":\"");
-                search.push_str(component.kind);
-                search.push_str("\"}");
+            only => only,
+        };
+
+        if opt.index_only {
+            let module_index_path = PathBuf::from(krate_name).join("index.html");
+            if !opt.output.join(&module_index_path).is_file() {
+                anyhow::bail!(
+                    "no documentation of {} in {:?} to refresh the search index of, render it first",
+                    krate_name,
+                    opt.output
+                );
             }
 
-            let last = item.0.last().unwrap();
-            search.push_str("],\"filepath\":\"");
-            search.push_str(&format!("{}", last.filepath.display()));
-            search.push_str("\"}");
+            index_module(&global_context, None, krate_item, krate_name, krate_module)?;
+            write_search_index(&mut global_context)?;
+            return Ok(opt.output.join(module_index_path));
         }
-        search.push_str("]');\n");
 
-        dump_to(
-            format!(
-                "{}/{}/{}",
-                &opt.output.display(),
-                &krate_item.name.as_ref().unwrap(),
-                SEARCH_INDEX_JS,
-            ),
-            search.as_bytes(),
-        )?;
+        let span = info_span!("page", path = krate_name);
+        let module_page_context = span.in_scope(|| {
+            module_page(
+                &global_context,
+                None,
+                krate_item,
+                global_context.krate_name,
+                krate_module,
+            )
+        })?;
+        // The pages and indexes of the whole crate, that would only know of the subtree
+        let whole_crate = only.is_none();
+        if whole_crate {
+            dependencies_page(&global_context, &module_page_context)?;
+            unsafe_page(&global_context, &module_page_context)?;
+            stats_page(&global_context, &module_page_context)?;
+            blanket_impls_page(&global_context, &module_page_context)?;
+            search_page(&global_context, &module_page_context)?;
+            guide_pages(&global_context, &module_page_context)?;
+        }
+
+        let module_index_path = match only {
+            Some(only) => {
+                // The full pages of the parent modules are kept when merging in the output
+                output::add_partial_pages(&opt.output, &global_context.partial_pages.borrow())?;
+                let only_page = global_context.only_page.borrow();
+                let only_page = only_page
+                    .as_ref()
+                    .with_context(|| format!("the module {} wasn't rendered", only))?;
+                opt.output.join(only_page)
+            }
+            None => global_context.opt.output.join(module_page_context.filepath),
+        };
+        if whole_crate {
+            write_crate_indexes(&mut global_context)?;
+        }
+
+        for (crate_id, count) in global_context.unresolved.borrow().iter() {
+            warn!(
+                "{} link{} to the items of {} dropped, the crate has no html_root_url{}",
+                count,
+                if *count > 1 { "s" } else { "" },
+                krate.external_crates[crate_id].name,
+                if opt.docs_rs_fallback {
+                    ""
+                } else {
+                    " (see --docs-rs-fallback)"
+                }
+            );
+        }
+
+        if let Some(stats) = &global_context.stats {
+            stats.report(
+                &mut std::io::stdout().lock(),
+                global_context.krate_name,
+                &opt.output,
+                global_context.files.iter_mut().map(|file| &*file),
+            )?;
+        }
 
-        Ok(module_index_path)
+        let failures = global_context.failures.take();
+        if failures.is_empty() {
+            return Ok(module_index_path);
+        }
+        anyhow::bail!(
+            "{} item{} of {} failed to render:{}",
+            failures.len(),
+            if failures.len() > 1 { "s" } else { "" },
+            global_context.krate_name,
+            failures
+                .iter()
+                .map(|(path, err)| format!("\n  {}: {}", path, err))
+                .collect::<String>()
+        )
     } else {
         anyhow::bail!("main item is not a Module")
     }
 }
 
+/// Write the indexes of the whole crate: search index, impl redirects, anchor aliases
+/// and anchors
+fn write_crate_indexes(global_context: &mut GlobalContext<'_>) -> Result<()> {
+    write_search_index(global_context)?;
+
+    // The records of the pages, in the order they were written
+    let mut anchors = BTreeMap::new();
+    let mut anchor_aliases: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut impl_redirects: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut pages = BTreeSet::new();
+    for records in global_context.records.get_mut().drain(..) {
+        let page = records.page.display().to_string();
+        // Like the fragments, the first item with an ambiguous path or id takes it
+        for (path, anchor) in records.anchors {
+            anchors.entry(path).or_insert(anchor);
+        }
+        let page_aliases = anchor_aliases.entry(page.clone()).or_default();
+        for (alias, id) in records.anchor_aliases {
+            if alias == id {
+                page_aliases.insert(alias, id);
+            } else {
+                page_aliases.entry(alias).or_insert(id);
+            }
+        }
+        if !records.impl_redirects.is_empty() {
+            impl_redirects
+                .entry(page)
+                .or_default()
+                .extend(records.impl_redirects);
+        }
+        pages.insert(records.page);
+    }
+
+    let krate_dir = global_context.opt.output.join(global_context.krate_name);
+    let impl_redirects =
+        serde_json::to_string(&impl_redirects).context("unable to serialize the impl redirects")?;
+    dump_to(
+        krate_dir.join(IMPL_REDIRECTS_JSON),
+        impl_redirects.as_bytes(),
+    )?;
+
+    for page_aliases in anchor_aliases.values_mut() {
+        page_aliases.retain(|alias, id| alias != id);
+    }
+    anchor_aliases.retain(|_, page_aliases| !page_aliases.is_empty());
+    let anchor_aliases =
+        serde_json::to_string(&anchor_aliases).context("unable to serialize the anchor aliases")?;
+    dump_to(
+        krate_dir.join(ANCHOR_ALIASES_JS),
+        format!("const RD_ANCHOR_ALIASES = {};\n", anchor_aliases).as_bytes(),
+    )?;
+
+    let anchors = serde_json::to_string(&anchors).context("unable to serialize the anchors")?;
+    dump_to(krate_dir.join(ANCHORS_JSON), anchors.as_bytes())?;
+
+    if let Some(base_url) = &global_context.opt.base_url {
+        write_sitemap(&krate_dir, base_url, &pages)?;
+    }
+    Ok(())
+}
+
+/// Write the sitemap of the pages of the crate (but its search results page), for the search
+/// engines
+fn write_sitemap(
+    krate_dir: &std::path::Path,
+    base_url: &str,
+    pages: &BTreeSet<PathBuf>,
+) -> Result<()> {
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for page in pages {
+        let page = page
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        sitemap.push_str(&format!(
+            "  <url><loc>{}/{}</loc></url>\n",
+            Escaped(base_url),
+            Escaped(&page)
+        ));
+    }
+    sitemap.push_str("</urlset>\n");
+    dump_to(krate_dir.join(SITEMAP_XML), sitemap.as_bytes()).context("unable to write the sitemap")
+}
+
+/// Whether the module of `--only` is in the crate, an error when the crate has no such module
+fn only_module(krate: &Crate, krate_name: &str, only: &str) -> Result<bool> {
+    // For an other crate of the run
+    if only != krate_name && !only.starts_with(&format!("{}::", krate_name)) {
+        return Ok(false);
+    }
+
+    let found = krate.paths.iter().any(|(id, summary)| {
+        summary.crate_id == 0
+            && summary.kind == ItemKind::Module
+            && krate.index.contains_key(id)
+            && summary.path.join("::") == only
+    });
+    anyhow::ensure!(found, "no module {} to render with --only", only);
+    Ok(true)
+}
+
+/// Documentation of the modules and the items its links resolve to, by module
+type IncludedDocs<'krate> = HashMap<&'krate Id, (Option<String>, HashMap<String, Id>)>;
+
+/// Documentation of the modules of the crate given files with `--include-doc`: their docs
+/// followed by the files, and the links of both
+fn included_docs<'krate>(
+    krate: &'krate Crate,
+    krate_name: &str,
+    includes: &[(String, PathBuf)],
+) -> Result<IncludedDocs<'krate>> {
+    let mut included_docs = HashMap::new();
+    for (path, file) in includes {
+        // For an other crate of the run
+        if path != krate_name && !path.starts_with(&format!("{}::", krate_name)) {
+            continue;
+        }
+
+        let (id, _) = krate
+            .paths
+            .iter()
+            .find(|(id, summary)| {
+                summary.crate_id == 0
+                    && summary.kind == ItemKind::Module
+                    && krate.index.contains_key(id)
+                    && summary.path.join("::") == *path
+            })
+            .with_context(|| format!("no module {} to include {:?} in", path, file))?;
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("unable to read the included doc {:?}", file))?;
+
+        let item = &krate.index[id];
+        let (docs, links) = included_docs
+            .entry(id)
+            .or_insert_with(|| (item.docs.clone(), item.links.clone()));
+        for (reference, id) in readme_links(krate, &content) {
+            links.entry(reference).or_insert(id);
+        }
+        let docs = docs.get_or_insert_with(String::new);
+        if !docs.is_empty() {
+            docs.push_str("\n\n");
+        }
+        docs.push_str(&content);
+    }
+    Ok(included_docs)
+}
+
+/// Write the search index of the collected item paths, in each of the requested formats,
+/// and the full-text index when enabled
+///
+/// The entries are sorted by path and kind rather than kept in the order of the rendering, so
+/// that the index only changes with the items. The formats are serialized and written on
+/// threads of their own, the full-text index (not `Sync`) on the current one meanwhile.
+fn write_search_index(global_context: &mut GlobalContext<'_>) -> Result<()> {
+    let krate_dir = global_context.opt.output.join(global_context.krate_name);
+    let formats = &global_context.opt.search_index_format;
+    let full_text = &global_context.full_text;
+    let previews = global_context.previews.borrow();
+    let paths = global_context
+        .paths
+        .iter_mut()
+        .map(|path| &*path)
+        .collect::<Vec<_>>();
+
+    let mut order = (0..paths.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        let (a, b) = (&paths[*a].0, &paths[*b].0);
+        let (a_last, b_last) = (a.last().unwrap(), b.last().unwrap());
+        a.iter()
+            .map(|component| &component.name)
+            .cmp(b.iter().map(|component| &component.name))
+            .then_with(|| a_last.kind.cmp(b_last.kind))
+            .then_with(|| a_last.filepath.cmp(&b_last.filepath))
+    });
+    let entries = order
+        .iter()
+        .map(|index| SearchEntry {
+            path: paths[*index],
+            preview: &previews[*index],
+        })
+        .collect::<Vec<_>>();
+    if let Some(full_text) = full_text {
+        full_text.reorder(&order);
+    }
+
+    let (entries, krate_dir) = (&entries, &krate_dir);
+    std::thread::scope(|scope| {
+        let emitters = formats
+            .iter()
+            .map(|format| {
+                scope.spawn(move || {
+                    let emitter = emitter(*format);
+                    dump_to(krate_dir.join(emitter.filename()), &emitter.emit(entries)?)
+                        .with_context(|| {
+                            format!("unable to write the {} search index", emitter.filename())
+                        })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(full_text) = full_text {
+            dump_to(
+                krate_dir.join(SEARCH_DOCS_INDEX_JS),
+                full_text.to_js()?.as_bytes(),
+            )
+            .context("unable to write the full-text search index")?;
+        }
+
+        for emitter in emitters {
+            emitter
+                .join()
+                .map_err(|_| anyhow::anyhow!("the search index thread panicked"))??;
+        }
+        Ok(())
+    })
+}
+
+/// Title, kind and Rust path of a page, written as YAML front-matter (`--front-matter`)
+struct FrontMatter<'a> {
+    title: &'a str,
+    kind: &'a str,
+    path: &'a str,
+}
+
+impl FrontMatter<'_> {
+    /// Front-matter of the pages of the crate that aren't the one of an item
+    fn krate<'a>(
+        global_context: &GlobalContext<'a>,
+        title: &'a str,
+        kind: &'a str,
+    ) -> FrontMatter<'a> {
+        FrontMatter {
+            title,
+            kind,
+            path: global_context.krate_name,
+        }
+    }
+
+    fn write(&self, writer: &mut impl Write) -> Result<()> {
+        // The json strings are valid (and escaped) YAML strings
+        writeln!(writer, "---")?;
+        writeln!(writer, "title: {}", serde_json::to_string(self.title)?)?;
+        writeln!(writer, "kind: {}", serde_json::to_string(self.kind)?)?;
+        writeln!(writer, "path: {}", serde_json::to_string(self.path)?)?;
+        writeln!(writer, "---")?;
+        Ok(())
+    }
+}
+
+/// Create the file of a page, starting with its front-matter and minifying it when requested
+///
+/// In debug builds, the tags of the page are checked to be balanced.
+fn create_page(
+    global_context: &GlobalContext<'_>,
+    filepath: &std::path::Path,
+    front_matter: FrontMatter<'_>,
+) -> Result<Validate<Minify<BufWriter<File>>>> {
+    let path = global_context.opt.output.join(filepath);
+    let file =
+        File::create(&path).with_context(|| format!("unable to create the {:?} file", path))?;
+    let mut file = BufWriter::new(file);
+    // Before the minification that would join its lines
+    if global_context.opt.front_matter {
+        front_matter.write(&mut file)?;
+    }
+    Ok(Validate::new(
+        Minify::new(file, global_context.opt.minify),
+        front_matter.path,
+        cfg!(debug_assertions),
+    ))
+}
+
+/// Kind of an item and the name of its page
+fn page_filename(
+    global_context: &GlobalContext<'_>,
+    item: &Item,
+    name: &str,
+) -> Result<(&'static str, PathBuf)> {
+    if let Some((kind, _)) = special_module(item) {
+        return Ok((kind, format!("{}.{}.html", kind, name).into()));
+    }
+
+    let (item_kind_name, _item_kind_file) =
+        prefix_item(item).context("unable to get of this item")?;
+    let filename: PathBuf = if matches!(item.inner, ItemEnum::Module(..)) {
+        format!("{}/index.html", name).into()
+    } else if let (true, Some(kind)) = (global_context.opt.rustdoc_compat_urls, item_kind(item)) {
+        format!(
+            "{}.{}.html",
+            rustdoc_page_prefix(item_kind_name, &kind),
+            name
+        )
+        .into()
+    } else {
+        format!("{}.{}.html", item_kind_name, name).into()
+    };
+    if global_context.opt.module_single_page && !matches!(item.inner, ItemEnum::Module(..)) {
+        let filename = filename.to_str().context("non utf-8 filename")?;
+        return Ok((item_kind_name, inlined_page(filename).into()));
+    }
+    Ok((item_kind_name, filename))
+}
+
+/// Page of an item following the `--path-template`, when given and the item isn't the crate,
+/// or at the root of the crate for the primitives and keywords with `--rustdoc-compat-urls`
+/// (like rustdoc)
+fn templated_page(
+    global_context: &GlobalContext<'_>,
+    parent_item_path: Option<&ItemPath>,
+    item_kind_name: &str,
+    name: &str,
+) -> Option<PathBuf> {
+    let modules = parent_item_path?
+        .0
+        .iter()
+        .map(|component| component.name.as_str())
+        .collect::<Vec<_>>();
+    if global_context.opt.rustdoc_compat_urls && matches!(item_kind_name, "primitive" | "keyword") {
+        return Some(PathBuf::from(modules[0]).join(format!("{}.{}.html", item_kind_name, name)));
+    }
+
+    let template = global_context.opt.path_template.as_deref()?;
+    Some(templated_path(template, &modules, item_kind_name, name))
+}
+
+/// Add the page of an item to the search index (path, preview and full-text), returning
+/// the path of the item
+fn index_item<'context>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: Option<&'context ItemPath>,
+    item: &Item,
+    name: &str,
+    item_kind_name: &'static str,
+    filepath: &std::path::Path,
+) -> &'context ItemPath {
+    if let (Some(full_text), Some(docs)) = (&global_context.full_text, &item.docs) {
+        full_text.add(global_context.paths.len(), docs);
+    }
+    global_context
+        .previews
+        .borrow_mut()
+        .push(SearchPreview::of(global_context, item));
+
+    global_context.paths.alloc({
+        let mut path = vec![];
+        if let Some(pip) = parent_item_path {
+            path.extend_from_slice(pip.0.as_slice());
+        }
+        path.push(ItemPathComponent {
+            name: name.to_string(),
+            kind: item_kind_name,
+            filepath: filepath.to_path_buf(),
+        });
+
+        ItemPath(path)
+    })
+}
+
+/// Items of a module (with the inlined re-exports) and their name, in the order of the page,
+/// without the ones left out by `--only` or the cfgs; shared by [`module_page`] and
+/// [`index_module`] so the index has the pages that are rendered
+fn module_items<'context>(
+    global_context: &'context GlobalContext<'context>,
+    module_path: &ItemPath,
+    module: &'context Module,
+) -> Vec<(&'context Item, Option<&'context str>)> {
+    let module_path = module_path.to_rust_path();
+    let mut items = module
+        .items
+        .iter()
+        .filter_map(|id| {
+            fn get<'context>(
+                global_context: &'context GlobalContext<'context>,
+                id: &'context Id,
+                name: Option<&'context str>,
+            ) -> Option<(&'context Item, Option<&'context str>)> {
+                let item = global_context.krate.index.get(id);
+                if item.is_none() {
+                    // A dangling id doesn't prevent rendering the rest of the module
+                    warn!(
+                        "unable to find the item {:?} of the module, skipping it",
+                        id
+                    );
+                }
+                let item = item?;
+
+                match &item.inner {
+                    ItemEnum::Import(Import {
+                        name: import_name,
+                        id: Some(target),
+                        ..
+                    }) => {
+                        // Like rustdoc: local items are inlined unless `#[doc(no_inline)]`,
+                        // external ones only with `#[doc(inline)]` (and if we have them)
+                        let inline = match doc_inlining(&item.attrs) {
+                            Some(false) => false,
+                            Some(true) => global_context.krate.index.contains_key(target),
+                            None => target.0.starts_with("0:"),
+                        };
+
+                        if inline {
+                            get(global_context, target, Some(import_name))
+                        } else {
+                            debug!(
+                                "listing `pub use` of {:?} ({:?}) as a re-export",
+                                import_name, target
+                            );
+                            Some((item, name.or(item.name.as_deref())))
+                        }
+                    }
+                    _ => Some((item, name.or(item.name.as_deref()))),
+                }
+            }
+
+            get(global_context, id, None)
+        })
+        .filter(|(item, _)| !global_context.cfg_hidden(&item.attrs))
+        .filter(|(item, name)| {
+            let path = format!("{}::{}", module_path, name.unwrap_or_default());
+            global_context.is_rendered(item, &path)
+        })
+        .collect::<Vec<_>>();
+    items.sort_by(|x_item, y_item| {
+        match (&x_item.0.inner, &y_item.0.inner) {
+            (ItemEnum::Module(_), ItemEnum::Module(_)) => x_item.0.name.cmp(&y_item.0.name),
+            (ItemEnum::Module(_), _) => Ordering::Less,
+            (_, ItemEnum::Module(_)) => Ordering::Greater,
+            _ => x_item.0.name.cmp(&y_item.0.name),
+        }
+        // The items of the same name (in different namespaces) in an order independent of the json
+        .then_with(|| match global_context.opt.deterministic {
+            true => x_item.0.id.0.cmp(&y_item.0.id.0),
+            false => Ordering::Equal,
+        })
+    });
+
+    items
+}
+
+/// Collect the paths (and search previews) of the pages of a module and its items, like
+/// [`module_page`] but without rendering them
+fn index_module<'context>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: Option<&'context ItemPath>,
+    item: &'context Item,
+    module_name: &'context str,
+    module: &'context Module,
+) -> Result<()> {
+    let mut filepath = parent_item_path
+        .and_then(|parent_item_path| parent_item_path.0.last())
+        .and_then(|parent| parent.filepath.parent())
+        .map(std::path::Path::to_path_buf)
+        .unwrap_or_default();
+    let (item_kind_name, filename) = page_filename(global_context, item, module_name)?;
+    filepath.push(filename);
+    if let Some(templated) = templated_page(
+        global_context,
+        parent_item_path,
+        item_kind_name,
+        module_name,
+    ) {
+        filepath = templated;
+    }
+    let item_path = index_item(
+        global_context,
+        parent_item_path,
+        item,
+        module_name,
+        item_kind_name,
+        &filepath,
+    );
+
+    for (item, name) in module_items(global_context, item_path, module) {
+        let special = special_module(item);
+        match (&item.inner, name) {
+            (ItemEnum::Module(module), Some(name)) if special.is_none() => {
+                index_module(global_context, Some(item_path), item, name, module)?
+            }
+            (ItemEnum::Import(_), _) => {}
+            (_, Some(name))
+                if special.is_some() || matches!(prefix_item(item), Some((_, true))) =>
+            {
+                let name = special.map_or(name, |(_, name)| name);
+                let (item_kind_name, filename) = page_filename(global_context, item, name)?;
+                let filepath =
+                    templated_page(global_context, Some(item_path), item_kind_name, name)
+                        .unwrap_or_else(|| filepath.with_file_name(filename));
+                index_item(
+                    global_context,
+                    Some(item_path),
+                    item,
+                    name,
+                    item_kind_name,
+                    &filepath,
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 /// Entry point of each page that create the file, page_context, ...
+///
+/// There is no file for the items rendered on the page of their module, see [`write_page`].
 fn base_page<'context>(
     global_context: &'context GlobalContext<'context>,
     parent_item_path: Option<&'context ItemPath>,
     item: &'context Item,
     name: &'context str,
-) -> Result<(PageContext<'context>, impl Write)> {
+) -> Result<(PageContext<'context>, Option<impl Write>)> {
     let parts = if let Some(parent_item_path) = parent_item_path {
         parent_item_path.0.iter().map(|c| &c.name).collect()
     } else {
         Vec::new()
     };
 
-    let (item_kind_name, _item_kind_file) =
-        prefix_item(item).context("unable to get of this item")?;
-    let filename: PathBuf = if matches!(item.inner, ItemEnum::Module(..)) {
-        format!("{}/index.html", name).into()
-    } else {
-        format!("{}.{}.html", item_kind_name, name).into()
-    };
+    let (item_kind_name, mut filename) = page_filename(global_context, item, name)?;
+    let templated = templated_page(global_context, parent_item_path, item_kind_name, name);
+
+    if let Some(templated) = &templated {
+        let path = global_context.opt.output.join(templated);
+        let path = path.parent().context("expect a parent directory")?;
 
-    if let ItemEnum::Module(_) = &item.inner {
+        debug!("creating the page directory {:?}", path);
+        DirBuilder::new()
+            .recursive(true)
+            .create(path)
+            .with_context(|| format!("unable to create the page dir: {}", path.display()))?;
+    } else if let ItemEnum::Module(_) = &item.inner {
         let mut path = global_context.opt.output.to_path_buf();
         path.extend(&parts);
         path.push(name);
 
         debug!("creating the module directory {:?}", &path);
         DirBuilder::new()
-            .recursive(false)
+            .recursive(true)
             .create(&path)
             .context(format!(
                 "unable to create the module dir: {}",
@@ -258,43 +1825,135 @@ fn base_page<'context>(
             ))?;
     }
 
-    let mut filepath: PathBuf = "".into();
-    filepath.extend(&parts);
-    filepath.push(&filename);
+    let mut filepath: PathBuf = match templated {
+        // The link from the page of the module is no longer its file name
+        Some(templated) => {
+            if let Some(parent) = parent_item_path.and_then(|path| path.0.last()) {
+                filename = relative(&parent.filepath, &templated);
+            }
+            templated
+        }
+        None => parts.iter().collect::<PathBuf>().join(&filename),
+    };
+
+    // The path of the page has the anchor of the item when it's on the page of its module
+    let inline_id = filename
+        .to_str()
+        .and_then(|filename| filename.split_once('#'))
+        .map(|(_, id)| id.to_string());
+    let page_path = filepath.clone();
+    if inline_id.is_some() {
+        filepath.set_file_name("index.html");
+    }
+
+    let rust_path = parts
+        .iter()
+        .map(|part| part.as_str())
+        .chain(std::iter::once(name))
+        .collect::<Vec<_>>()
+        .join("::");
+    global_context.take_page(&page_path, &rust_path, Some(&item.id))?;
 
     let filepath = global_context.files.alloc(filepath);
 
-    info!("generating {} {}", item_kind_name, name);
-    debug!("creating the {} file {:?}", item_kind_name, filepath);
+    info!(kind = item_kind_name, file = %page_path.display(), "generating {} {}", item_kind_name, name);
+    debug!("creating the {} file {:?}", item_kind_name, page_path);
     trace!("ID: {:?} -- krate_path {:?}", &item.id, &parts);
 
-    let path = global_context.opt.output.join(&filepath);
-    let file =
-        File::create(&path).with_context(|| format!("unable to create the {:?} file", path))?;
-    let file = BufWriter::new(file);
+    let file = match inline_id {
+        Some(_) => None,
+        None => Some(create_page(
+            global_context,
+            filepath,
+            FrontMatter {
+                title: name,
+                kind: item_kind_name,
+                path: &rust_path,
+            },
+        )?),
+    };
 
-    Ok((
-        PageContext {
+    if let Some(stats) = &global_context.stats {
+        stats.add_item(item_kind_name);
+    }
+
+    let page_context = PageContext {
+        item,
+        filepath,
+        filename,
+        item_path: index_item(
+            global_context,
+            parent_item_path,
             item,
-            filepath,
-            filename,
-            item_path: global_context.paths.alloc({
-                let mut path = vec![];
-                if let Some(pip) = parent_item_path {
-                    path.extend_from_slice(pip.0.as_slice());
-                }
-                path.push(ItemPathComponent {
-                    name: name.to_string(),
-                    kind: item_kind_name,
-                    filepath: filepath.clone(),
-                });
+            name,
+            item_kind_name,
+            &page_path,
+        ),
+        ids: Default::default(),
+        impl_ids: Default::default(),
+        assoc_ids: Default::default(),
+        heading_ids: Default::default(),
+        inline_id,
+        records: Default::default(),
+        footnote_blocks: Default::default(),
+    };
 
-                ItemPath(path)
-            }),
-            ids: Default::default(),
-        },
-        file,
-    ))
+    page_context.records.borrow_mut().anchors.push((
+        page_context.item_path.to_rust_path(),
+        page_path.display().to_string(),
+    ));
+
+    Ok((page_context, file))
+}
+
+/// Write a page to its file, or its content to the page of its module when the item is
+/// rendered there (to be included by [`module_page`])
+fn write_page<Main: markup::Render>(
+    global_context: &GlobalContext<'_>,
+    page_context: &PageContext<'_>,
+    file: Option<impl Write>,
+    page: Base<'_, Main>,
+) -> Result<()> {
+    global_context.add_records(page_context);
+    match file {
+        Some(mut file) => writeln!(file, "{}", page)?,
+        None => {
+            let mut html = String::new();
+            page.main
+                .render(&mut html)
+                .context("unable to render the item on the page of its module")?;
+            validate_html(&html, &page_context.filepath.display().to_string());
+            global_context
+                .inlined
+                .borrow_mut()
+                .entry(page_context.filepath.clone())
+                .or_default()
+                .push_str(&html);
+        }
+    }
+    Ok(())
+}
+
+/// Record the anchor of an associated item (method, field, variant, ...) of the page,
+/// keeping the first one when several have the same path (like trait methods)
+fn record_anchor(page_context: &PageContext, item: &Item, id: &HtmlId) {
+    if let Some(name) = &item.name {
+        page_context.records.borrow_mut().anchors.push((
+            format!("{}::{}", page_context.item_path.to_rust_path(), name),
+            format!("{}#{}", page_context.filepath.display(), id),
+        ));
+    }
+}
+
+/// Record the other ids of an associated item, redirected to its id by the anchor shim
+fn record_anchor_aliases(page_context: &PageContext, aliases: Vec<String>, id: &HtmlId) {
+    let id = id.to_string();
+    let mut records = page_context.records.borrow_mut();
+    // The ids of the page are kept (as their own alias) so that they are never redirected
+    records.anchor_aliases.push((id.clone(), id.clone()));
+    records
+        .anchor_aliases
+        .extend(aliases.into_iter().map(|alias| (alias, id.clone())));
 }
 
 /// Helper function to get the item definition in a `markup`able way
@@ -303,10 +1962,161 @@ fn item_definition<'context, 'krate>(
     page_context: &'context PageContext<'context>,
     item: &'krate Item,
 ) -> Result<TokensToHtml<'context, 'krate>> {
-    let tokens = pp::Tokens::from_item(item, &global_context.krate.index)?;
+    let tokens = global_context.tokens(item)?;
     Ok(TokensToHtml(global_context, page_context, tokens))
 }
 
+/// Path, link and kind of the items gated by a feature
+type FeatureItems = Vec<(String, String, &'static str)>;
+
+/// Cargo features of the crate, with their description (from `--features-file`, or else the
+/// `Features` section of the crate docs) and the items whose `#[cfg(..)]` can require them
+fn crate_features(
+    global_context: &GlobalContext<'_>,
+    page_context: &PageContext<'_>,
+    item: &Item,
+) -> Result<Vec<(String, String, FeatureItems)>> {
+    let mut descriptions: BTreeMap<String, String> =
+        feature_descriptions(item.docs.as_deref().unwrap_or_default())
+            .into_iter()
+            .collect();
+    if let Some(path) = &global_context.opt.features_file {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read the features file {:?}", path))?;
+        let file: BTreeMap<String, String> = toml::from_str(&content)
+            .with_context(|| format!("invalid features file {:?}", path))?;
+        descriptions.extend(file);
+    }
+
+    let mut features: BTreeMap<String, (String, FeatureItems)> = descriptions
+        .into_iter()
+        .map(|(name, description)| (name, (description, Vec::new())))
+        .collect();
+    for (id, item) in &global_context.krate.index {
+        if item.crate_id != 0 {
+            continue;
+        }
+        let Some(portability) = Portability::from_attrs(&item.attrs)? else {
+            continue;
+        };
+        let cfg = match Cfg::parse(portability.render_short()) {
+            Ok(cfg) => cfg,
+            Err(err) => {
+                warn!("{:#}", err);
+                continue;
+            }
+        };
+
+        // The items without a page of their own (like the impls) only list their features
+        let page = global_context
+            .krate
+            .paths
+            .get(id)
+            .zip(href(global_context, page_context, id));
+        for feature in cfg.features() {
+            let (_, items) = features.entry(feature.to_string()).or_default();
+            if let Some((summary, (_, link, _, kind))) = &page {
+                let link = link
+                    .to_str()
+                    .with_context(|| format!("unable to convert PathBuf {:?} to str", link))?;
+                items.push((summary.path[1..].join("::"), link.to_string(), *kind));
+            }
+        }
+    }
+
+    Ok(features
+        .into_iter()
+        .map(|(name, (description, mut items))| {
+            items.sort();
+            items.dedup();
+            (name, description, items)
+        })
+        .collect())
+}
+
+/// Table of contents of the sections of a module page listing its items
+struct ModuleTocs<'toc> {
+    primitives: TocSection<'toc>,
+    modules: TocSection<'toc>,
+    macros: TocSection<'toc>,
+    proc_macros: TocSection<'toc>,
+    unions: TocSection<'toc>,
+    structs: TocSection<'toc>,
+    enums: TocSection<'toc>,
+    functions: TocSection<'toc>,
+    traits: TocSection<'toc>,
+    typedefs: TocSection<'toc>,
+    opaque_types: TocSection<'toc>,
+    foreign_types: TocSection<'toc>,
+    constants: TocSection<'toc>,
+    keywords: TocSection<'toc>,
+    failed_items: TocSection<'toc>,
+}
+
+impl ModuleTocs<'_> {
+    fn new() -> Self {
+        let section = |name, id| TocSection {
+            name,
+            id,
+            items: Default::default(),
+        };
+        ModuleTocs {
+            primitives: section(PRIMITIVES, PRIMITIVES_ID),
+            modules: section(MODULES, MODULES_ID),
+            macros: section(MACROS, MACROS_ID),
+            proc_macros: section(PROC_MACROS, PROC_MACROS_ID),
+            unions: section(UNIONS, UNIONS_ID),
+            structs: section(STRUCTS, STRUCTS_ID),
+            enums: section(ENUMS, ENUMS_ID),
+            functions: section(FUNCTIONS, FUNCTIONS_ID),
+            traits: section(TRAITS, TRAITS_ID),
+            typedefs: section(TYPEDEFS, TYPEDEFS_ID),
+            opaque_types: section(OPAQUE_TYPES, OPAQUE_TYPES_ID),
+            foreign_types: section(FOREIGN_TYPES, FOREIGN_TYPES_ID),
+            constants: section(CONSTANTS, CONSTANTS_ID),
+            keywords: section(KEYWORDS, KEYWORDS_ID),
+            failed_items: section(FAILED_ITEMS, FAILED_ITEMS_ID),
+        }
+    }
+}
+
+/// Entries of the sections of a module page listing its items
+#[derive(Default)]
+struct ModuleSections<'a> {
+    imports: Vec<DynRender<'a>>,
+    primitives: Vec<DynRender<'a>>,
+    modules: Vec<DynRender<'a>>,
+    unions: Vec<DynRender<'a>>,
+    structs: Vec<DynRender<'a>>,
+    enums: Vec<DynRender<'a>>,
+    functions: Vec<DynRender<'a>>,
+    traits: Vec<DynRender<'a>>,
+    trait_alias: Vec<DynRender<'a>>,
+    typedefs: Vec<DynRender<'a>>,
+    opaque_types: Vec<DynRender<'a>>,
+    foreign_types: Vec<DynRender<'a>>,
+    constants: Vec<DynRender<'a>>,
+    macros: Vec<DynRender<'a>>,
+    proc_macros: Vec<DynRender<'a>>,
+    keywords: Vec<DynRender<'a>>,
+    failed_items: Vec<DynRender<'a>>,
+}
+
+/// Entry of a section of a module page, whatever its kind
+fn entry<'a>(entry: impl markup::Render + 'a) -> DynRender<'a> {
+    markup::new(move |mut writer| entry.render(&mut writer))
+}
+
+/// Message of a caught panic, when it's a string
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic
+            .downcast_ref::<String>()
+            .map_or("unknown cause", String::as_str),
+    }
+}
+
 /// Module page generation function
 fn module_page<'context>(
     global_context: &'context GlobalContext<'context>,
@@ -315,506 +2125,1906 @@ fn module_page<'context>(
     module_name: &'context str,
     module: &'context Module,
 ) -> Result<PageContext<'context>> {
-    let (page_context, mut file) = base_page(global_context, parent_item_path, item, module_name)?;
-
-    let mut module_page_content = ModulePageContent {
-        imports: Default::default(),
-        modules: Default::default(),
-        unions: Default::default(),
-        structs: Default::default(),
-        enums: Default::default(),
-        functions: Default::default(),
-        traits: Default::default(),
-        trait_alias: Default::default(),
-        typedefs: Default::default(),
-        constants: Default::default(),
-        macros: Default::default(),
-        proc_macros: Default::default(),
+    let (page_context, file) = base_page(global_context, parent_item_path, item, module_name)?;
+    if let Some(only) = &global_context.opt.only {
+        let path = page_context.item_path.to_rust_path();
+        if path == *only {
+            *global_context.only_page.borrow_mut() = Some(page_context.filepath.to_path_buf());
+        } else if only.starts_with(&format!("{}::", path)) {
+            global_context
+                .partial_pages
+                .borrow_mut()
+                .push(page_context.filepath.to_path_buf());
+        }
+    }
+
+    write_module_page(
+        global_context,
+        parent_item_path,
+        &page_context,
+        file,
+        item,
+        module_name,
+        module,
+    )?;
+    Ok(page_context)
+}
+
+/// Render the items of a module (and their pages) and write its page
+fn write_module_page<'a, 'context: 'a>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: Option<&'context ItemPath>,
+    page_context: &'a PageContext<'context>,
+    file: Option<impl Write>,
+    item: &'context Item,
+    module_name: &'context str,
+    module: &'context Module,
+) -> Result<()> {
+    let attributes = CrateAttributes {
+        attributes: match parent_item_path {
+            None => crate_attributes(&item.attrs),
+            Some(_) => Vec::new(),
+        },
+    };
+    let metadata = global_context
+        .metadata
+        .as_ref()
+        .filter(|_| parent_item_path.is_none())
+        .map(|metadata| CrateMetadataPanel { metadata });
+    let features = FeaturesTable {
+        features: match parent_item_path {
+            None => crate_features(global_context, page_context, item)?,
+            Some(_) => Vec::new(),
+        },
     };
 
     // TODO: this could probably be removed
     let filenames = Arena::<PathBuf>::new();
+    let mut tocs = ModuleTocs::new();
+    let mut sections = ModuleSections::default();
 
-    let mut toc_macros = TocSection {
-        name: MACROS,
-        id: MACROS_ID,
-        items: Default::default(),
-    };
-    let mut toc_proc_macros = TocSection {
-        name: PROC_MACROS,
-        id: PROC_MACROS_ID,
-        items: Default::default(),
-    };
-    let mut toc_modules = TocSection {
-        name: MODULES,
-        id: MODULES_ID,
-        items: Default::default(),
-    };
-    let mut toc_unions = TocSection {
-        name: UNIONS,
-        id: UNIONS_ID,
-        items: Default::default(),
-    };
-    let mut toc_structs = TocSection {
-        name: STRUCTS,
-        id: STRUCTS_ID,
-        items: Default::default(),
-    };
-    let mut toc_enums = TocSection {
-        name: ENUMS,
-        id: ENUMS_ID,
-        items: Default::default(),
-    };
-    let mut toc_traits = TocSection {
-        name: TRAITS,
-        id: TRAITS_ID,
-        items: Default::default(),
-    };
-    let mut toc_functions = TocSection {
-        name: FUNCTIONS,
-        id: FUNCTIONS_ID,
-        items: Default::default(),
-    };
-    let mut toc_typedefs = TocSection {
-        name: TYPEDEFS,
-        id: TYPEDEFS_ID,
-        items: Default::default(),
-    };
-    let mut toc_constants = TocSection {
-        name: CONSTANTS,
-        id: CONSTANTS_ID,
-        items: Default::default(),
-    };
-
-    let mut items = module
-        .items
-        .iter()
-        .filter_map(|id| {
-            fn get<'context>(
-                global_context: &'context GlobalContext<'context>,
-                id: &'context Id,
-                name: Option<&'context str>,
-            ) -> Option<Result<(&'context Item, Option<&'context str>)>> {
-                if !id.0.starts_with("0:") {
-                    warn!("ignoring `pub use` of {:?} ({:?})", &name, id);
-                    return None;
-                }
+    let items = module_items(global_context, page_context.item_path, module);
+    for (item, name) in items {
+        let path = format!(
+            "{}::{}",
+            page_context.item_path.to_rust_path(),
+            name.unwrap_or_default()
+        );
+        let span = info_span!("page", path = path.as_str());
+        let _enter = span.enter();
 
-                let item = global_context
-                    .krate
-                    .index
-                    .get(id)
-                    .with_context(|| {
-                        format!("unable to find the item {:?} from module - fatal", id)
-                    })
-                    .ok()?;
+        let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+            render_item(
+                global_context,
+                page_context,
+                &filenames,
+                item,
+                name,
+                &mut tocs,
+                &mut sections,
+            )
+        }))
+        .unwrap_or_else(|panic| match global_context.opt.keep_going {
+            true => Err(anyhow::anyhow!("panicked: {}", panic_message(&*panic))),
+            false => panic::resume_unwind(panic),
+        });
 
-                match &item.inner {
-                    ItemEnum::Import(Import {
-                        name, id: Some(id), ..
-                    }) => get(global_context, &id, Some(&name)),
-                    _ => Some(Ok((item, name.or_else(|| item.name.as_deref())))),
-                }
+        if let Err(err) = rendered {
+            if !global_context.opt.keep_going {
+                return Err(err);
             }
 
-            get(&global_context, id, None)
-        })
-        .collect::<Result<Vec<_>>>()?;
-    items.sort_by(|x_item, y_item| match (&x_item.0.inner, &y_item.0.inner) {
-        (ItemEnum::Module(_), ItemEnum::Module(_)) => x_item.0.name.cmp(&y_item.0.name),
-        (ItemEnum::Module(_), _) => Ordering::Less,
-        (_, ItemEnum::Module(_)) => Ordering::Greater,
-        _ => x_item.0.name.cmp(&y_item.0.name),
-    });
-
-    for (item, name) in items {
-        let summary =
-            MarkdownSummaryLine::from_docs(global_context, &page_context, &item.docs, &item.links);
-        let portability = Portability::from_attrs(&item.attrs)?
-            .as_ref()
-            .map(Portability::render_short);
-        let deprecated = item.deprecation.as_ref().map(|d| match d.since {
-            Some(ref since) if since != "none" => "Deprecated",
-            _ => "Future deprecation",
-        });
-        let unsafety = Option::<&str>::None;
+            // The unnamed items (like the impls) by their id
+            let failed = match name {
+                Some(_) => path.clone(),
+                None => format!("{:?}", item.id),
+            };
+            error!("unable to render {} ({:?}): {:#}", failed, item.id, err);
+            global_context
+                .failures
+                .borrow_mut()
+                .push((failed, format!("{:#}", err)));
 
-        match &item.inner {
-            ItemEnum::Import(_) => {
-                module_page_content.imports.push(ModuleSectionItem {
-                    name: InlineCode {
-                        code: TokensToHtml(
-                            global_context,
-                            &page_context,
-                            pp::Tokens::from_item(item, &global_context.krate.index)?,
-                        ),
-                    },
-                    summary: Option::<String>::None,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::Union(union_) => {
-                let name = name.context("unable to get the name of the union")?;
-                let page_context =
-                    union_page(global_context, page_context.item_path, item, name, union_)?;
+            if let Some(page_context) =
+                failed_item_page(global_context, page_context.item_path, item, name, &err)?
+            {
+                let name = name.unwrap_or_default();
                 let filename = filenames.alloc(page_context.filename);
 
-                toc_unions
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.unions.push(ModuleSectionItem {
+                tocs.failed_items.items.push((
+                    Cow::Borrowed(name),
+                    TocDestination::File(filename),
+                    global_context.summary_line(item),
+                ));
+                sections.failed_items.push(entry(ModuleSectionItem {
                     name: ItemLink {
                         name,
                         link: filename.to_str().with_context(|| {
                             format!("unable to convert PathBuf {:?} to str", filename)
                         })?,
-                        class: "union",
+                        class: "",
+                        title: global_context.summary_line(item),
                     },
-                    unsafety,
-                    summary,
-                    deprecated,
-                    portability,
-                });
+                    summary: err.to_string(),
+                    asyncness: false,
+                    constness: false,
+                    unsafety: Option::<&str>::None,
+                    deprecated: Option::<&str>::None,
+                    portability: Option::<&str>::None,
+                    since: Option::<&str>::None,
+                    visibility: None,
+                }));
             }
-            ItemEnum::Struct(struct_) => {
-                let name = name.context("unable to get the name of the struct")?;
-                let page_context =
-                    struct_page(global_context, page_context.item_path, item, name, struct_)?;
-                let filename = filenames.alloc(page_context.filename);
+        }
+    }
 
-                toc_structs
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.structs.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "struct",
-                    },
-                    unsafety,
-                    summary,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::Enum(enum_) => {
-                let name = name.context("unable to get the name of the enum")?;
-                let page_context =
-                    enum_page(global_context, page_context.item_path, item, name, enum_)?;
-                let filename = filenames.alloc(page_context.filename);
+    let dependencies_filename = PathBuf::from(DEPENDENCIES_HTML);
+    let toc_dependencies = TocSection {
+        name: DEPENDENCIES,
+        id: DEPENDENCIES_ID,
+        items: if parent_item_path.is_none() && !dependencies(global_context.krate).is_empty() {
+            vec![(
+                Cow::Borrowed(DEPENDENCIES),
+                TocDestination::File(&dependencies_filename),
+                None,
+            )]
+        } else {
+            vec![]
+        },
+    };
 
-                toc_enums
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.enums.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "enum",
-                    },
-                    unsafety,
-                    summary,
-                    deprecated,
-                    portability,
+    let unsafe_filename = PathBuf::from(UNSAFE_HTML);
+    let toc_unsafe = TocSection {
+        name: UNSAFE,
+        id: UNSAFE_ID,
+        items: if parent_item_path.is_none() && !unsafe_items(global_context.krate).is_empty() {
+            vec![(
+                Cow::Borrowed(UNSAFE),
+                TocDestination::File(&unsafe_filename),
+                None,
+            )]
+        } else {
+            vec![]
+        },
+    };
+
+    let stats_filename = PathBuf::from(STATS_HTML);
+    let toc_statistics = TocSection {
+        name: STATISTICS,
+        id: STATISTICS_ID,
+        items: if parent_item_path.is_none() {
+            vec![(
+                Cow::Borrowed(STATISTICS),
+                TocDestination::File(&stats_filename),
+                None,
+            )]
+        } else {
+            vec![]
+        },
+    };
+
+    let toc_features = TocSection {
+        name: FEATURES,
+        id: FEATURES_ID,
+        items: features
+            .features
+            .iter()
+            .map(|(name, ..)| {
+                let id = page_context
+                    .ids
+                    .alloc(HtmlId::new(format!("feature.{}", name)));
+                (Cow::Owned(name.clone()), TocDestination::Id(id), None)
+            })
+            .collect(),
+    };
+
+    let toc = vec![
+        toc_features,
+        tocs.primitives,
+        tocs.modules,
+        tocs.macros,
+        tocs.unions,
+        tocs.structs,
+        tocs.enums,
+        tocs.functions,
+        tocs.traits,
+        tocs.typedefs,
+        tocs.opaque_types,
+        tocs.foreign_types,
+        tocs.constants,
+        tocs.proc_macros,
+        tocs.keywords,
+        toc_dependencies,
+        toc_unsafe,
+        toc_statistics,
+        tocs.failed_items,
+    ];
+    let ModuleSections {
+        imports,
+        primitives,
+        modules,
+        unions,
+        structs,
+        enums,
+        functions,
+        traits,
+        trait_alias,
+        typedefs,
+        opaque_types,
+        foreign_types,
+        constants,
+        macros,
+        proc_macros,
+        keywords,
+        failed_items,
+    } = sections;
+    let module_page_content = ModulePageContent {
+        attributes,
+        metadata,
+        features,
+        imports,
+        primitives,
+        modules,
+        unions,
+        structs,
+        enums,
+        functions,
+        traits,
+        trait_alias,
+        typedefs,
+        opaque_types,
+        foreign_types,
+        constants,
+        macros,
+        proc_macros,
+        keywords,
+        failed_items,
+        inlined: global_context
+            .inlined
+            .borrow_mut()
+            .remove(page_context.filepath),
+    };
+
+    let is_top_level = parent_item_path.is_none();
+    let (docs, links) = match global_context.included_docs.get(&item.id) {
+        Some((docs, links)) => (docs, links),
+        None => (&item.docs, &item.links),
+    };
+    let page = Base {
+        infos: BodyInformations::with(global_context, page_context),
+        main: ItemPage {
+            item_banner: MarkdownBanner::from_docs(
+                global_context,
+                page_context,
+                &item.docs,
+                &item.links,
+            ),
+            item_readme: is_top_level
+                .then(|| {
+                    Markdown::from_docs(
+                        global_context,
+                        page_context,
+                        Some(page_context.ids.alloc(HtmlId::new(README_ID.to_string()))),
+                        &global_context.readme,
+                        &global_context.readme_links,
+                    )
+                })
+                .flatten(),
+            item_summary: Option::<String>::None,
+            item_type: if is_top_level { "Crate" } else { "Module" },
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: module_name,
+            item_path: page_context.item_path.display(page_context),
+            item_deprecation: DeprecationNotice::from(&item.deprecation),
+            item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+            item_since: since_version(&item.attrs),
+            item_source_crate: source_crate(global_context, page_context, item),
+            item_must_use: MustUseNotice::from(item),
+            item_notable_traits: NotableTraits::from(global_context, page_context, item),
+            item_contracts: Option::<ContractBadges>::None,
+            item_sections: plugin_sections(global_context, page_context, item)?,
+            item_definition: Option::<String>::None,
+            item_doc: MarkdownWithToc::from_docs(global_context, page_context, docs, links),
+            toc: &toc,
+            content: Some(module_page_content),
+        },
+    };
+
+    write_page(global_context, page_context, file, page)?;
+
+    if global_context.opt.fragments {
+        write_fragment(global_context, page_context, item, &toc)?;
+    }
+    Ok(())
+}
+
+/// Render the page of an item of a module (if it has one), and list it in the sections of
+/// the module page
+fn render_item<'a, 'context: 'a>(
+    global_context: &'context GlobalContext<'context>,
+    page_context: &'a PageContext<'context>,
+    filenames: &'a Arena<PathBuf>,
+    item: &'context Item,
+    name: Option<&'context str>,
+    tocs: &mut ModuleTocs<'a>,
+    sections: &mut ModuleSections<'a>,
+) -> Result<()> {
+    let tooltip = global_context.summary_line(item);
+    let summary =
+        MarkdownSummaryLine::from_docs(global_context, page_context, &item.docs, &item.links);
+    let portability = Portability::from_attrs(&item.attrs)?
+        .as_ref()
+        .map(|portability| ShortPortability {
+            cfg: portability.render_short(),
+            disabled: global_context.cfg_set.is_disabled(&item.attrs),
+        });
+    let deprecated = item.deprecation.as_ref().map(|d| match d.since {
+        Some(ref since) if since != "none" => "Deprecated",
+        _ => "Future deprecation",
+    });
+    let since = since_version(&item.attrs);
+    let visibility = private_visibility(item);
+    let unsafety = Option::<&str>::None;
+    let (asyncness, constness) = (false, false);
+
+    match &item.inner {
+        ItemEnum::Import(import) => {
+            // Summary of the target, or the crate it comes from when it isn't local
+            let target = import
+                .id
+                .as_ref()
+                .and_then(|id| global_context.krate.index.get(id));
+            let external = import
+                .id
+                .as_ref()
+                .filter(|_| target.is_none())
+                .and_then(|id| {
+                    let summary = global_context.krate.paths.get(id)?;
+                    let external_crate = global_context
+                        .krate
+                        .external_crates
+                        .get(&summary.crate_id)?;
+                    let href =
+                        href(global_context, page_context, id).and_then(|(url, path, ..)| {
+                            Some(format!("{}/{}", url?.trim_end_matches('/'), path.display()))
+                        });
+                    Some((external_crate.name.as_str(), href))
                 });
-            }
-            ItemEnum::Function(function_) => {
-                let name = name.context("unable to get the name of the function")?;
-                let page_context = function_page(
-                    global_context,
-                    page_context.item_path,
-                    item,
+
+            sections.imports.push(entry(ModuleSectionItem {
+                name: InlineCode {
+                    code: TokensToHtml(global_context, page_context, global_context.tokens(item)?),
+                },
+                summary: ImportSummary {
+                    summary: target.map(|target| {
+                        MarkdownSummaryLine::from_docs(
+                            global_context,
+                            page_context,
+                            &target.docs,
+                            &target.links,
+                        )
+                    }),
+                    external,
+                },
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Union(union_) => {
+            let name = name.context("unable to get the name of the union")?;
+            let page_context =
+                union_page(global_context, page_context.item_path, item, name, union_)?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.unions.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.unions.push(entry(ModuleSectionItem {
+                name: ItemLink {
                     name,
-                    function_,
-                )?;
-                let filename = filenames.alloc(page_context.filename);
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "union",
+                    title: tooltip.clone(),
+                },
+                asyncness,
+                constness,
+                unsafety,
+                summary,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Struct(struct_) => {
+            let name = name.context("unable to get the name of the struct")?;
+            let page_context =
+                struct_page(global_context, page_context.item_path, item, name, struct_)?;
+            let filename = filenames.alloc(page_context.filename);
 
-                toc_functions
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.functions.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "fn",
-                    },
+            tocs.structs.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.structs.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "struct",
+                    title: tooltip.clone(),
+                },
+                asyncness,
+                constness,
+                unsafety,
+                summary,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Enum(enum_) => {
+            let name = name.context("unable to get the name of the enum")?;
+            let page_context =
+                enum_page(global_context, page_context.item_path, item, name, enum_)?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.enums.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.enums.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "enum",
+                    title: tooltip.clone(),
+                },
+                asyncness,
+                constness,
+                unsafety,
+                summary,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Function(function_) => {
+            let name = name.context("unable to get the name of the function")?;
+            let page_context = function_page(
+                global_context,
+                page_context.item_path,
+                item,
+                name,
+                function_,
+            )?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.functions.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.functions.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "fn",
+                    title: tooltip.clone(),
+                },
+                summary,
+                deprecated,
+                portability,
+                since,
+                visibility,
+                asyncness: function_.header.async_,
+                constness: function_.header.const_,
+                unsafety: if function_.header.unsafe_ {
+                    Some("This function is unsafe to use")
+                } else {
+                    unsafety
+                },
+            }));
+        }
+        ItemEnum::Trait(trait_) => {
+            let name = name.context("unable to get the name of the trait")?;
+            let (page_context, counts) =
+                trait_page(global_context, page_context.item_path, item, name, trait_)?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.traits.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.traits.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "trait",
+                    title: tooltip.clone(),
+                },
+                summary: SummaryWithCounts {
                     summary,
-                    deprecated,
-                    portability,
-                    unsafety: if function_.header.unsafe_ {
-                        Some("This function is unsafe to use")
-                    } else {
-                        unsafety
-                    },
-                });
+                    counts: counts.to_string(),
+                },
+                deprecated,
+                portability,
+                since,
+                visibility,
+                asyncness,
+                constness,
+                unsafety: if trait_.is_unsafe {
+                    Some("This trait is unsafe to use")
+                } else {
+                    unsafety
+                },
+            }));
+        }
+        ItemEnum::TraitAlias(_) => {
+            sections.trait_alias.push(entry(ModuleSectionItem {
+                name: InlineCode {
+                    code: TokensToHtml(global_context, page_context, global_context.tokens(item)?),
+                },
+                summary: Option::<String>::None,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::TypeAlias(typealias_) => {
+            let name = name.context("unable to get the name of the typedef")?;
+            let page_context2 = typealias_page(
+                global_context,
+                page_context.item_path,
+                item,
+                name,
+                typealias_,
+            )?;
+            let filename = filenames.alloc(page_context2.filename);
+
+            tocs.typedefs.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+
+            enum Either<Left, Right> {
+                Left(Left),
+                Right(Right),
             }
-            ItemEnum::Trait(trait_) => {
-                let name = name.context("unable to get the name of the trait")?;
-                let page_context =
-                    trait_page(global_context, page_context.item_path, item, name, trait_)?;
-                let filename = filenames.alloc(page_context.filename);
 
-                toc_traits
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.traits.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "trait",
-                    },
-                    summary,
-                    deprecated,
-                    portability,
-                    unsafety: if trait_.is_unsafe {
-                        Some("This trait is unsafe to use")
-                    } else {
-                        unsafety
-                    },
-                });
+            impl<Left: markup::Render, Right: markup::Render> markup::Render for Either<Left, Right> {
+                fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
+                    match self {
+                        Either::Left(left) => markup::Render::render(left, writer),
+                        Either::Right(right) => markup::Render::render(right, writer),
+                    }
+                }
             }
-            ItemEnum::TraitAlias(_) => {
-                module_page_content.trait_alias.push(ModuleSectionItem {
-                    name: InlineCode {
+
+            sections.typedefs.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "type",
+                    title: tooltip.clone(),
+                },
+                summary: if let Some(summary_line_doc) = summary {
+                    Either::Left(summary_line_doc)
+                } else {
+                    Either::Right(InlineCode {
                         code: TokensToHtml(
                             global_context,
-                            &page_context,
-                            pp::Tokens::from_item(item, &global_context.krate.index)?,
+                            page_context,
+                            pp::Tokens::from_type(&typealias_.type_)?,
                         ),
-                    },
-                    summary: Option::<String>::None,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::TypeAlias(typealias_) => {
-                let name = name.context("unable to get the name of the typedef")?;
-                let page_context2 = typealias_page(
-                    global_context,
-                    page_context.item_path,
-                    item,
+                    })
+                },
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Constant { type_: _, const_ } => {
+            let name = name.context("unable to get the name of the constant")?;
+            let page_context =
+                constant_page(global_context, page_context.item_path, item, name, const_)?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.constants.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.constants.push(entry(ModuleSectionItem {
+                name: ItemLink {
                     name,
-                    typealias_,
-                )?;
-                let filename = filenames.alloc(page_context2.filename);
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "constant",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::OpaqueTy(opaque_ty) => {
+            let name = name.context("unable to get the name of the opaque type")?;
+            let page_context = opaque_ty_page(
+                global_context,
+                page_context.item_path,
+                item,
+                name,
+                opaque_ty,
+            )?;
+            let filename = filenames.alloc(page_context.filename);
 
-                toc_typedefs
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
+            tocs.opaque_types.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.opaque_types.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "type",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::ForeignType => {
+            let name = name.context("unable to get the name of the foreign type")?;
+            let page_context =
+                foreign_type_page(global_context, page_context.item_path, item, name, &())?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.foreign_types.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.foreign_types.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "type",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Static(static_) => {
+            let name = name.context("unable to get the name of the static")?;
+            let page_context =
+                static_page(global_context, page_context.item_path, item, name, static_)?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.constants.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.constants.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "static",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Macro(macro_) => {
+            let name = name.context("unable to get the name of the macro")?;
+            let page_context =
+                macro_page(global_context, page_context.item_path, item, name, macro_)?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.macros.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.macros.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "macro",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::ProcMacro(proc_macro_) => {
+            let name = name.context("unable to get the name of the proc-macro")?;
+            let page_context = proc_macro_page(
+                global_context,
+                page_context.item_path,
+                item,
+                name,
+                proc_macro_,
+            )?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.proc_macros.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.proc_macros.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "proc_macro",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Module(_) if special_module(item).is_some() => {
+            let (kind, name) = special_module(item).unwrap();
+            let (page_context, toc, section, class) = if kind == "keyword" {
+                (
+                    keyword_page(global_context, page_context.item_path, item, name)?,
+                    &mut tocs.keywords,
+                    &mut sections.keywords,
+                    "keyword",
+                )
+            } else {
+                (
+                    primitive_page(global_context, page_context.item_path, item, name, &[])?,
+                    &mut tocs.primitives,
+                    &mut sections.primitives,
+                    "primitive",
+                )
+            };
+            let filename = filenames.alloc(page_context.filename);
+
+            toc.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            section.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class,
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Primitive(primitive) => {
+            let name = primitive.name.as_str();
+            let page_context = primitive_page(
+                global_context,
+                page_context.item_path,
+                item,
+                name,
+                &primitive.impls,
+            )?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.primitives.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.primitives.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "primitive",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        ItemEnum::Module(module_) => {
+            let name = name.context("unable to get the name of the module")?;
+            let page_context = module_page(
+                global_context,
+                Some(page_context.item_path),
+                item,
+                name,
+                module_,
+            )?;
+            let filename = filenames.alloc(page_context.filename);
+
+            tocs.modules.items.push((
+                Cow::Borrowed(name),
+                TocDestination::File(filename),
+                tooltip.clone(),
+            ));
+            sections.modules.push(entry(ModuleSectionItem {
+                name: ItemLink {
+                    name,
+                    link: filename.to_str().with_context(|| {
+                        format!("unable to convert PathBuf {:?} to str", filename)
+                    })?,
+                    class: "mod",
+                    title: tooltip.clone(),
+                },
+                summary,
+                asyncness,
+                constness,
+                unsafety,
+                deprecated,
+                portability,
+                since,
+                visibility,
+            }));
+        }
+        _ => unreachable!("module item shouldn't have a this type of item"),
+    }
+    Ok(())
+}
+
+/// Placeholder page of an item that failed to render (with `--keep-going`),
+/// if such an item can have a page
+fn failed_item_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: &'context ItemPath,
+    item: &'context Item,
+    name: Option<&'context str>,
+    error: &anyhow::Error,
+) -> Result<Option<PageContext<'context>>> {
+    let name = match (name, prefix_item(item)) {
+        (Some(name), Some((_, true))) if !matches!(item.inner, ItemEnum::Import(_)) => name,
+        _ => return Ok(None),
+    };
+    // The item may have failed after taking its page, which the placeholder replaces
+    global_context
+        .pages
+        .borrow_mut()
+        .retain(|_, (_, id)| id.as_ref() != Some(&item.id));
+    let (page_context, file) = base_page(global_context, Some(parent_item_path), item, name)?;
+
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: Option::<String>::None,
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Item",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: name,
+            item_path: page_context.item_path.display(&page_context),
+            item_deprecation: DeprecationNotice::from(&item.deprecation),
+            item_portability: Option::<String>::None,
+            item_since: None,
+            item_source_crate: None,
+            item_must_use: Option::<String>::None,
+            item_notable_traits: Option::<String>::None,
+            item_contracts: Option::<String>::None,
+            item_sections: Vec::new(),
+            item_definition: Option::<String>::None,
+            item_doc: None,
+            toc: &vec![],
+            content: Some(RenderingError {
+                error: &format!("{:#}", error),
+            }),
+        },
+    };
+
+    write_page(global_context, &page_context, file, page)?;
+
+    Ok(Some(page_context))
+}
+
+/// External crates referenced by the public API, with the number of their items used
+fn dependencies(krate: &Crate) -> BTreeMap<u32, usize> {
+    let mut ids = HashSet::new();
+    for (_, item) in ListedItem::all(krate) {
+        if !matches!(item.visibility, Visibility::Public | Visibility::Default) {
+            continue;
+        }
+        // The trait impls aren't listed, only their items, but they use the trait too
+        let impls = match &item.inner {
+            ItemEnum::Struct(Struct { impls, .. })
+            | ItemEnum::Enum(Enum { impls, .. })
+            | ItemEnum::Union(Union { impls, .. }) => impls.as_slice(),
+            _ => &[],
+        };
+        let impls = impls
+            .iter()
+            .filter_map(|id| krate.index.get(id))
+            .filter(|item| {
+                matches!(
+                    &item.inner,
+                    ItemEnum::Impl(Impl {
+                        blanket_impl: None,
+                        ..
+                    })
+                )
+            });
+        for item in std::iter::once(item).chain(impls) {
+            let Ok(tokens) = pp::Tokens::from_item(item, &krate.index) else {
+                continue;
+            };
+            ids.extend(tokens.iter().filter_map(|token| match token {
+                pp::Token::Ident(_, id) => *id,
+                _ => None,
+            }));
+        }
+    }
+
+    let mut dependencies = BTreeMap::new();
+    for summary in ids.into_iter().filter_map(|id| krate.paths.get(id)) {
+        if summary.crate_id != 0 && krate.external_crates.contains_key(&summary.crate_id) {
+            *dependencies.entry(summary.crate_id).or_default() += 1;
+        }
+    }
+    dependencies
+}
+
+/// Local unsafe functions (and methods), unsafe traits and unions of a crate, sorted by path
+fn unsafe_items(krate: &Crate) -> Vec<(ListedItem<'_>, &Item)> {
+    let mut items = ListedItem::all(krate)
+        .filter(|(_, item)| {
+            matches!(
+                &item.inner,
+                ItemEnum::Function(Function {
+                    header: rustdoc_types::Header { unsafe_: true, .. },
+                    ..
+                }) | ItemEnum::Trait(Trait {
+                    is_unsafe: true,
+                    ..
+                }) | ItemEnum::Union(_)
+            )
+        })
+        .collect::<Vec<_>>();
+    items.sort_by_key(|(listed_item, _)| listed_item.sort_key(krate));
+    items
+}
+
+/// Function for generating the page listing the unsafe API of the crate
+fn unsafe_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    krate_page_context: &PageContext<'context>,
+) -> Result<()> {
+    let unsafe_items = unsafe_items(global_context.krate);
+    if unsafe_items.is_empty() {
+        return Ok(());
+    }
+
+    let filename = PathBuf::from(UNSAFE_HTML);
+    let filepath = global_context
+        .files
+        .alloc(PathBuf::from(global_context.krate_name).join(&filename));
+    let page_context = PageContext {
+        item: krate_page_context.item,
+        filepath,
+        filename,
+        item_path: krate_page_context.item_path,
+        ids: Default::default(),
+        impl_ids: Default::default(),
+        assoc_ids: Default::default(),
+        heading_ids: Default::default(),
+        inline_id: None,
+        records: Default::default(),
+        footnote_blocks: Default::default(),
+    };
+
+    // Path, link and kind of each item, and the number of items per module
+    let mut links = Vec::with_capacity(unsafe_items.len());
+    let mut modules: BTreeMap<&[String], (usize, usize, usize)> = BTreeMap::new();
+    for (listed_item, item) in &unsafe_items {
+        let path = &global_context.krate.paths[listed_item.item].path;
+        let (name, link, kind) = listed_item
+            .link(global_context, &page_context)
+            .with_context(|| format!("unable to find the page of {:?}", path))?;
+        let counts = modules.entry(&path[..path.len() - 1]).or_default();
+        match &item.inner {
+            ItemEnum::Function(_) => counts.0 += 1,
+            ItemEnum::Trait(_) => counts.1 += 1,
+            _ => counts.2 += 1,
+        }
+        links.push((name, link, kind, *item));
+    }
+    let modules = modules
+        .into_iter()
+        .map(|(path, counts)| {
+            let dest = match (&global_context.opt.path_template, path.split_last()) {
+                (Some(template), Some((name, modules))) if !modules.is_empty() => {
+                    templated_path(template, modules, "mod", name)
+                }
+                _ => path.iter().collect::<PathBuf>().join("index.html"),
+            };
+            (
+                path.join("::"),
+                relative(page_context.filepath, &dest),
+                counts,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut toc_functions = TocSection {
+        name: UNSAFE_FUNCTIONS,
+        id: UNSAFE_FUNCTIONS_ID,
+        items: vec![],
+    };
+    let mut toc_traits = TocSection {
+        name: UNSAFE_TRAITS,
+        id: UNSAFE_TRAITS_ID,
+        items: vec![],
+    };
+    let mut toc_unions = TocSection {
+        name: UNIONS,
+        id: UNIONS_ID,
+        items: vec![],
+    };
+    let mut content = UnsafePageContent {
+        functions: vec![],
+        traits: vec![],
+        unions: vec![],
+        modules: vec![],
+    };
+    for (name, link, kind, item) in &links {
+        let (toc, section) = match &item.inner {
+            ItemEnum::Function(_) => (&mut toc_functions, &mut content.functions),
+            ItemEnum::Trait(_) => (&mut toc_traits, &mut content.traits),
+            _ => (&mut toc_unions, &mut content.unions),
+        };
+        toc.items.push((
+            Cow::Borrowed(name.as_str()),
+            TocDestination::File(link),
+            None,
+        ));
+        section.push(ModuleSectionItem {
+            name: ItemLink {
+                name: name.as_str(),
+                link: link
+                    .to_str()
+                    .with_context(|| format!("unable to convert PathBuf {:?} to str", link))?,
+                class: kind,
+                title: None,
+            },
+            summary: MarkdownSummaryLine::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            asyncness: false,
+            constness: false,
+            unsafety: Option::<&str>::None,
+            deprecated: Option::<&str>::None,
+            portability: Option::<&str>::None,
+            since: Option::<&str>::None,
+            visibility: None,
+        });
+    }
+    for (name, link, (functions, traits, unions)) in &modules {
+        content.modules.push(ModuleSectionItem {
+            name: ItemLink {
+                name: name.as_str(),
+                link: link
+                    .to_str()
+                    .with_context(|| format!("unable to convert PathBuf {:?} to str", link))?,
+                class: "mod",
+                title: None,
+            },
+            summary: UnsafeCounts(*functions, *traits, *unions).to_string(),
+            asyncness: false,
+            constness: false,
+            unsafety: Option::<&str>::None,
+            deprecated: Option::<&str>::None,
+            portability: Option::<&str>::None,
+            since: Option::<&str>::None,
+            visibility: None,
+        });
+    }
+
+    info!("generating unsafe API of {}", global_context.krate_name);
+    global_context.take_page(
+        page_context.filepath,
+        &format!("{} of {}", UNSAFE, global_context.krate_name),
+        None,
+    )?;
+    let mut file = create_page(
+        global_context,
+        page_context.filepath,
+        FrontMatter::krate(global_context, UNSAFE, UNSAFE_ID),
+    )?;
+
+    let toc = vec![toc_functions, toc_traits, toc_unions];
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: Option::<String>::None,
+            item_readme: Option::<String>::None,
+            item_summary: Some(TraitSummary {
+                counts: UnsafeCounts(
+                    content.functions.len(),
+                    content.traits.len(),
+                    content.unions.len(),
+                )
+                .to_string(),
+            }),
+            item_type: "Unsafe API of",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: UNSAFE,
+            item_path: page_context.item_path.display(&page_context),
+            item_deprecation: Option::<String>::None,
+            item_portability: Option::<String>::None,
+            item_since: None,
+            item_source_crate: None,
+            item_must_use: Option::<String>::None,
+            item_notable_traits: Option::<String>::None,
+            item_contracts: Option::<String>::None,
+            item_sections: Vec::new(),
+            item_definition: Option::<String>::None,
+            item_doc: None,
+            toc: &toc,
+            content: Some(content),
+        },
+    };
+
+    global_context.add_records(&page_context);
+    writeln!(file, "{}", page)?;
+    Ok(())
+}
+
+/// Number of unsafe functions, unsafe traits and unions
+struct UnsafeCounts(usize, usize, usize);
+
+impl std::fmt::Display for UnsafeCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} unsafe function{}, {} unsafe trait{}, {} union{}",
+            self.0,
+            if self.0 == 1 { "" } else { "s" },
+            self.1,
+            if self.1 == 1 { "" } else { "s" },
+            self.2,
+            if self.2 == 1 { "" } else { "s" },
+        )
+    }
+}
+
+/// Share of the items that are documented, as `<documented> (<percent>%)`
+struct Documented(usize, usize);
+
+impl std::fmt::Display for Documented {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let percent = match self.1 {
+            0 => 100.0,
+            total => self.0 as f64 * 100.0 / total as f64,
+        };
+        write!(f, "{} ({:.1}%)", self.0, percent)
+    }
+}
+
+/// Function for generating the page with the statistics of the crate: items per kind and
+/// how many of them are documented, unsafe or generic, and the use of the dependencies
+fn stats_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    krate_page_context: &PageContext<'context>,
+) -> Result<()> {
+    let krate = global_context.krate;
+    let filename = PathBuf::from(STATS_HTML);
+    let filepath = global_context
+        .files
+        .alloc(PathBuf::from(global_context.krate_name).join(&filename));
+    let page_context = PageContext {
+        item: krate_page_context.item,
+        filepath,
+        filename,
+        item_path: krate_page_context.item_path,
+        ids: Default::default(),
+        impl_ids: Default::default(),
+        assoc_ids: Default::default(),
+        heading_ids: Default::default(),
+        inline_id: None,
+        records: Default::default(),
+        footnote_blocks: Default::default(),
+    };
+
+    // The functions of the traits and impls are counted as methods
+    let associated = krate
+        .index
+        .values()
+        .flat_map(|item| match &item.inner {
+            ItemEnum::Trait(trait_) => trait_.items.as_slice(),
+            ItemEnum::Impl(impl_) if !impl_.synthetic && impl_.blanket_impl.is_none() => {
+                impl_.items.as_slice()
+            }
+            _ => &[],
+        })
+        .collect::<HashSet<_>>();
+
+    let mut kinds: BTreeMap<&'static str, (usize, usize)> = BTreeMap::new();
+    let (mut generics, mut deprecated) = (0, 0);
+    for item in krate.index.values().filter(|item| item.crate_id == 0) {
+        if matches!(
+            item.inner,
+            ItemEnum::Impl(_) | ItemEnum::Import(_) | ItemEnum::ExternCrate { .. }
+        ) {
+            continue;
+        }
+
+        let counts = kinds
+            .entry(crate::extract::kind(item, associated.contains(&item.id)))
+            .or_default();
+        counts.0 += 1;
+        if item
+            .docs
+            .as_deref()
+            .is_some_and(|docs| !docs.trim().is_empty())
+        {
+            counts.1 += 1;
+        }
+
+        let params = match &item.inner {
+            ItemEnum::Function(Function { generics, .. })
+            | ItemEnum::Struct(Struct { generics, .. })
+            | ItemEnum::Enum(Enum { generics, .. })
+            | ItemEnum::Union(Union { generics, .. })
+            | ItemEnum::Trait(Trait { generics, .. })
+            | ItemEnum::TypeAlias(TypeAlias { generics, .. }) => generics.params.as_slice(),
+            _ => &[],
+        };
+        if !params.is_empty() {
+            generics += 1;
+        }
+        if item.deprecation.is_some() {
+            deprecated += 1;
+        }
+    }
 
-                enum Either<Left, Right> {
-                    Left(Left),
-                    Right(Right),
-                }
+    let (items, documented) = kinds.values().fold((0, 0), |(items, documented), counts| {
+        (items + counts.0, documented + counts.1)
+    });
+    let unsafe_functions = unsafe_items(krate)
+        .iter()
+        .filter(|(_, item)| matches!(item.inner, ItemEnum::Function(_)))
+        .count();
+    let dependencies = dependencies(krate);
 
-                impl<Left: markup::Render, Right: markup::Render> markup::Render for Either<Left, Right> {
-                    fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
-                        match self {
-                            Either::Left(left) => markup::Render::render(left, writer),
-                            Either::Right(right) => markup::Render::render(right, writer),
-                        }
-                    }
-                }
+    let overview = StatsOverview {
+        rows: vec![
+            ("Items", items.to_string(), None),
+            (
+                "Documented",
+                Documented(documented, items).to_string(),
+                None,
+            ),
+            (
+                "Unsafe functions",
+                unsafe_functions.to_string(),
+                (unsafe_functions != 0).then_some(UNSAFE_HTML),
+            ),
+            ("Generic items", generics.to_string(), None),
+            ("Deprecated items", deprecated.to_string(), None),
+            (
+                "Dependencies",
+                format!(
+                    "{} crate{}, {} items used by the public API",
+                    dependencies.len(),
+                    if dependencies.len() == 1 { "" } else { "s" },
+                    dependencies.values().sum::<usize>()
+                ),
+                (!dependencies.is_empty()).then_some(DEPENDENCIES_HTML),
+            ),
+        ],
+    };
+    let kinds = StatsKinds {
+        rows: kinds
+            .into_iter()
+            .map(|(kind, (count, documented))| {
+                (kind, count, Documented(documented, count).to_string())
+            })
+            .collect(),
+    };
 
-                module_page_content.typedefs.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "type",
-                    },
-                    summary: if let Some(summary_line_doc) = summary {
-                        Either::Left(summary_line_doc)
-                    } else {
-                        Either::Right(InlineCode {
-                            code: TokensToHtml(
-                                global_context,
-                                &page_context,
-                                pp::Tokens::from_type(&typealias_.type_)?,
-                            ),
-                        })
-                    },
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::Constant { type_: _, const_ } => {
-                let name = name.context("unable to get the name of the constant")?;
-                let page_context =
-                    constant_page(global_context, page_context.item_path, item, name, const_)?;
-                let filename = filenames.alloc(page_context.filename);
+    info!("generating statistics of {}", global_context.krate_name);
+    global_context.take_page(
+        page_context.filepath,
+        &format!("{} of {}", STATISTICS, global_context.krate_name),
+        None,
+    )?;
+    let mut file = create_page(
+        global_context,
+        page_context.filepath,
+        FrontMatter::krate(global_context, STATISTICS, STATISTICS_ID),
+    )?;
 
-                toc_constants
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.constants.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "constant",
-                    },
-                    summary,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::Static(static_) => {
-                let name = name.context("unable to get the name of the static")?;
-                let page_context =
-                    static_page(global_context, page_context.item_path, item, name, static_)?;
-                let filename = filenames.alloc(page_context.filename);
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: Option::<String>::None,
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Statistics of",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: STATISTICS,
+            item_path: page_context.item_path.display(&page_context),
+            item_deprecation: Option::<String>::None,
+            item_portability: Option::<String>::None,
+            item_since: None,
+            item_source_crate: None,
+            item_must_use: Option::<String>::None,
+            item_notable_traits: Option::<String>::None,
+            item_contracts: Option::<String>::None,
+            item_sections: Vec::new(),
+            item_definition: Option::<String>::None,
+            item_doc: None,
+            toc: &vec![],
+            content: Some(StatsPageContent {
+                overview: vec![overview],
+                kinds: vec![kinds],
+            }),
+        },
+    };
 
-                toc_constants
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.constants.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "static",
-                    },
-                    summary,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::Macro(macro_) => {
-                let name = name.context("unable to get the name of the macro")?;
-                let page_context =
-                    macro_page(global_context, page_context.item_path, item, name, macro_)?;
-                let filename = filenames.alloc(page_context.filename);
+    global_context.add_records(&page_context);
+    writeln!(file, "{}", page)?;
+    Ok(())
+}
 
-                toc_macros
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.macros.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "macro",
-                    },
-                    summary,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::ProcMacro(proc_macro_) => {
-                let name = name.context("unable to get the name of the proc-macro")?;
-                let page_context = proc_macro_page(
-                    global_context,
-                    page_context.item_path,
-                    item,
-                    name,
-                    proc_macro_,
-                )?;
-                let filename = filenames.alloc(page_context.filename);
+/// Function for generating the page listing the dependencies (external crates) of the crate
+fn dependencies_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    krate_page_context: &PageContext<'context>,
+) -> Result<()> {
+    let dependencies = dependencies(global_context.krate);
+    if dependencies.is_empty() {
+        return Ok(());
+    }
 
-                toc_proc_macros
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.proc_macros.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "proc_macro",
-                    },
-                    summary,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            ItemEnum::Module(module_) => {
-                let name = name.context("unable to get the name of the module")?;
-                let page_context = module_page(
-                    global_context,
-                    Some(page_context.item_path),
-                    item,
-                    name,
-                    module_,
-                )?;
-                let filename = filenames.alloc(page_context.filename);
+    let filename = PathBuf::from(DEPENDENCIES_HTML);
+    let filepath = global_context
+        .files
+        .alloc(PathBuf::from(global_context.krate_name).join(&filename));
+    let page_context = PageContext {
+        item: krate_page_context.item,
+        filepath,
+        filename,
+        item_path: krate_page_context.item_path,
+        ids: Default::default(),
+        impl_ids: Default::default(),
+        assoc_ids: Default::default(),
+        heading_ids: Default::default(),
+        inline_id: None,
+        records: Default::default(),
+        footnote_blocks: Default::default(),
+    };
 
-                toc_modules
-                    .items
-                    .push((Cow::Borrowed(name), TocDestination::File(filename)));
-                module_page_content.modules.push(ModuleSectionItem {
-                    name: ItemLink {
-                        name,
-                        link: filename.to_str().with_context(|| {
-                            format!("unable to convert PathBuf {:?} to str", filename)
-                        })?,
-                        class: "mod",
-                    },
-                    summary,
-                    unsafety,
-                    deprecated,
-                    portability,
-                });
-            }
-            _ => unreachable!("module item shouldn't have a this type of item"),
-        }
+    let mut items = Vec::with_capacity(dependencies.len());
+    for (crate_id, count) in dependencies {
+        let external_crate = &global_context.krate.external_crates[&crate_id];
+        items.push(ModuleSectionItem {
+            name: DependencyLink {
+                name: &external_crate.name,
+                url: external_crate.html_root_url.as_ref().map(|html_root_url| {
+                    format!(
+                        "{}/{}/index.html",
+                        html_root_url.trim_end_matches('/'),
+                        external_crate.name
+                    )
+                }),
+            },
+            summary: format!(
+                "{} item{} used by the public API",
+                count,
+                if count > 1 { "s" } else { "" }
+            ),
+            asyncness: false,
+            constness: false,
+            unsafety: Option::<&str>::None,
+            deprecated: Option::<&str>::None,
+            portability: Option::<&str>::None,
+            since: Option::<&str>::None,
+            visibility: None,
+        });
     }
 
-    let is_top_level = parent_item_path.is_none();
+    info!("generating dependencies of {}", global_context.krate_name);
+    global_context.take_page(
+        page_context.filepath,
+        &format!("{} of {}", DEPENDENCIES, global_context.krate_name),
+        None,
+    )?;
+    let mut file = create_page(
+        global_context,
+        page_context.filepath,
+        FrontMatter::krate(global_context, DEPENDENCIES, DEPENDENCIES_ID),
+    )?;
+
     let page = Base {
         infos: BodyInformations::with(global_context, &page_context),
         main: ItemPage {
-            item_type: if is_top_level { "Crate" } else { "Module" },
-            item_name: module_name,
+            item_banner: Option::<String>::None,
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Dependencies of",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: DEPENDENCIES,
             item_path: page_context.item_path.display(&page_context),
-            item_deprecation: DeprecationNotice::from(&item.deprecation),
-            item_portability: PortabilityNotice::from(&item.attrs)?,
+            item_deprecation: Option::<String>::None,
+            item_portability: Option::<String>::None,
+            item_since: None,
+            item_source_crate: None,
+            item_must_use: Option::<String>::None,
+            item_notable_traits: Option::<String>::None,
+            item_contracts: Option::<String>::None,
+            item_sections: Vec::new(),
             item_definition: Option::<String>::None,
-            item_doc: MarkdownWithToc::from_docs(
+            item_doc: None,
+            toc: &vec![],
+            content: Some(ModuleSection {
+                name: DEPENDENCIES,
+                id: DEPENDENCIES_ID,
+                items: &items,
+            }),
+        },
+    };
+
+    global_context.add_records(&page_context);
+    writeln!(file, "{}", page)?;
+    Ok(())
+}
+
+/// Function for generating the page with the blanket impls shared by all the types
+fn blanket_impls_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    krate_page_context: &PageContext<'context>,
+) -> Result<()> {
+    let shared_blanket_impls = match &global_context.shared_blanket_impls {
+        Some(shared_blanket_impls) if !shared_blanket_impls.impls.is_empty() => {
+            shared_blanket_impls
+        }
+        _ => return Ok(()),
+    };
+
+    let filename = PathBuf::from(BLANKET_IMPLS_HTML);
+    let filepath = global_context
+        .files
+        .alloc(PathBuf::from(global_context.krate_name).join(&filename));
+    let page_context = PageContext {
+        item: krate_page_context.item,
+        filepath,
+        filename,
+        item_path: krate_page_context.item_path,
+        ids: Default::default(),
+        impl_ids: Default::default(),
+        assoc_ids: Default::default(),
+        heading_ids: Default::default(),
+        inline_id: None,
+        records: Default::default(),
+        footnote_blocks: Default::default(),
+    };
+
+    let mut toc_blanket_traits = TocSection {
+        name: BLANKET_IMPLEMENTATIONS,
+        id: BLANKET_IMPLEMENTATIONS_ID,
+        items: vec![],
+    };
+    let impls = shared_blanket_impls
+        .impls
+        .values()
+        .map(|(item, _)| {
+            let impl_ = match &item.inner {
+                ItemEnum::Impl(impl_) => impl_,
+                _ => unreachable!("only impls are shared"),
+            };
+            CodeEnchantedWithExtras::from_items(
                 global_context,
                 &page_context,
-                &item.docs,
-                &item.links,
-            ),
-            toc: &vec![
-                toc_modules,
-                toc_macros,
-                toc_unions,
-                toc_structs,
-                toc_enums,
-                toc_functions,
-                toc_traits,
-                toc_typedefs,
-                toc_constants,
-                toc_proc_macros,
-            ],
-            content: Some(module_page_content),
+                TocSupplier::Top(&mut toc_blanket_traits),
+                item,
+                impl_,
+                true,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    info!("generating blanket impls of {}", global_context.krate_name);
+    global_context.take_page(
+        page_context.filepath,
+        &format!(
+            "{} of {}",
+            BLANKET_IMPLEMENTATIONS, global_context.krate_name
+        ),
+        None,
+    )?;
+    let mut file = create_page(
+        global_context,
+        page_context.filepath,
+        FrontMatter::krate(
+            global_context,
+            BLANKET_IMPLEMENTATIONS,
+            BLANKET_IMPLEMENTATIONS_ID,
+        ),
+    )?;
+
+    let toc = vec![toc_blanket_traits];
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: Option::<String>::None,
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Blanket Implementations of",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: BLANKET_IMPLEMENTATIONS,
+            item_path: page_context.item_path.display(&page_context),
+            item_deprecation: Option::<String>::None,
+            item_portability: Option::<String>::None,
+            item_since: None,
+            item_source_crate: None,
+            item_must_use: Option::<String>::None,
+            item_notable_traits: Option::<String>::None,
+            item_contracts: Option::<String>::None,
+            item_sections: Vec::new(),
+            item_definition: Option::<String>::None,
+            item_doc: None,
+            toc: &toc,
+            content: Some(GeneralSection {
+                name: BLANKET_IMPLEMENTATIONS,
+                id: BLANKET_IMPLEMENTATIONS_ID,
+                items: &impls,
+            }),
         },
     };
 
+    global_context.add_records(&page_context);
     writeln!(file, "{}", page)?;
+    Ok(())
+}
 
-    Ok(page_context)
+/// Write the search results page of the crate (listing the results of its `?q=` query)
+/// and, with `--base-url`, the OpenSearch descriptor leading the browsers to it
+fn search_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    krate_page_context: &PageContext<'context>,
+) -> Result<()> {
+    let filename = PathBuf::from(SEARCH_HTML);
+    let filepath = global_context
+        .files
+        .alloc(PathBuf::from(global_context.krate_name).join(&filename));
+    let page_context = PageContext {
+        item: krate_page_context.item,
+        filepath,
+        filename,
+        item_path: krate_page_context.item_path,
+        ids: Default::default(),
+        impl_ids: Default::default(),
+        assoc_ids: Default::default(),
+        heading_ids: Default::default(),
+        inline_id: None,
+        records: Default::default(),
+        footnote_blocks: Default::default(),
+    };
+
+    info!("generating search page of {}", global_context.krate_name);
+    // Not recorded, the results of a query don't belong in the sitemap
+    global_context.take_page(
+        page_context.filepath,
+        &format!("{} of {}", SEARCH_RESULTS, global_context.krate_name),
+        None,
+    )?;
+    let mut file = create_page(
+        global_context,
+        page_context.filepath,
+        FrontMatter::krate(global_context, SEARCH_RESULTS, SEARCH_RESULTS_ID),
+    )?;
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: SearchResults {
+            krate_name: global_context.krate_name,
+        },
+    };
+    writeln!(file, "{}", page)?;
+
+    if let Some(base_url) = &global_context.opt.base_url {
+        let krate_name = Escaped(global_context.krate_name);
+        let base_url = Escaped(base_url);
+        let descriptor = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+  <ShortName>{krate_name} docs</ShortName>
+  <Description>Search in the documentation of {krate_name}</Description>
+  <InputEncoding>UTF-8</InputEncoding>
+  <Image type="image/svg+xml">{base_url}/{icon}</Image>
+  <Url type="text/html" method="get" template="{base_url}/{krate_name}/{page}?q={{searchTerms}}"/>
+</OpenSearchDescription>
+"#,
+            icon = Escaped(global_context.static_files.get(&RUST_SVG)),
+            page = SEARCH_HTML,
+        );
+        dump_to(
+            global_context
+                .opt
+                .output
+                .join(global_context.krate_name)
+                .join(OPENSEARCH_XML),
+            descriptor.as_bytes(),
+        )
+        .context("unable to write the OpenSearch descriptor")?;
+    }
+    Ok(())
+}
+
+/// Render the markdown chapters of `--guide` in the `guide/` directory of the crate, in the
+/// order of their files after the introduction (`index.md` or `README.md`, else a list of the
+/// chapters)
+fn guide_pages<'context>(
+    global_context: &'context GlobalContext<'context>,
+    krate_page_context: &PageContext<'context>,
+) -> Result<()> {
+    let Some(guide) = &global_context.opt.guide else {
+        return Ok(());
+    };
+
+    let mut files = std::fs::read_dir(guide)
+        .with_context(|| format!("unable to read the guide directory {:?}", guide))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    files.retain(|path| path.extension() == Some("md".as_ref()));
+    files.sort();
+
+    // Page, title and content of each chapter
+    let mut chapters = files
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .with_context(|| format!("invalid name of the chapter {:?}", path))?;
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("unable to read the chapter {:?}", path))?;
+            let title = first_heading(&content).unwrap_or_else(|| name.replace(['-', '_'], " "));
+            Ok((chapter_page(name), title, content))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    chapters.sort_by_key(|(page, ..)| page != "index.html");
+    if chapters
+        .iter()
+        .filter(|(page, ..)| page == "index.html")
+        .count()
+        > 1
+    {
+        anyhow::bail!("the guide {:?} has both an index.md and a README.md", guide);
+    }
+    if chapters.first().map(|(page, ..)| page.as_str()) != Some("index.html") {
+        let mut content = format!("# {}\n\n", GUIDE);
+        for (page, title, _) in &chapters {
+            content.push_str(&format!("- [{}]({})\n", title, page));
+        }
+        chapters.insert(0, ("index.html".to_string(), GUIDE.to_string(), content));
+    }
+
+    let directory = global_context
+        .opt
+        .output
+        .join(global_context.krate_name)
+        .join(GUIDE_DIR);
+    DirBuilder::new()
+        .recursive(true)
+        .create(&directory)
+        .with_context(|| format!("unable to create the guide dir: {}", directory.display()))?;
+
+    info!(
+        "generating the {} chapters of the guide of {}",
+        chapters.len(),
+        global_context.krate_name
+    );
+    for (index, (page, title, content)) in chapters.iter().enumerate() {
+        let filename = PathBuf::from(page);
+        let filepath = global_context.files.alloc(
+            PathBuf::from(global_context.krate_name)
+                .join(GUIDE_DIR)
+                .join(&filename),
+        );
+        let page_context = PageContext {
+            item: krate_page_context.item,
+            filepath,
+            filename,
+            item_path: krate_page_context.item_path,
+            ids: Default::default(),
+            impl_ids: Default::default(),
+            assoc_ids: Default::default(),
+            heading_ids: Default::default(),
+            inline_id: None,
+            records: Default::default(),
+            footnote_blocks: Default::default(),
+        };
+        let links = readme_links(global_context.krate, content);
+        let chapter = |index: usize| {
+            chapters
+                .get(index)
+                .map(|(page, title, _)| (title.as_str(), page.as_str()))
+        };
+
+        global_context.take_page(
+            page_context.filepath,
+            &format!("{} of {}", title, global_context.krate_name),
+            None,
+        )?;
+        let mut file = create_page(
+            global_context,
+            page_context.filepath,
+            FrontMatter::krate(global_context, title, GUIDE_ID),
+        )?;
+        let page = Base {
+            infos: BodyInformations::with(global_context, &page_context),
+            main: GuidePage {
+                chapters: chapters
+                    .iter()
+                    .enumerate()
+                    .map(|(other, (page, title, _))| {
+                        (title.as_str(), page.as_str(), other == index)
+                    })
+                    .collect(),
+                previous: index.checked_sub(1).and_then(chapter),
+                next: chapter(index + 1),
+                content: MarkdownChapter::new(global_context, &page_context, content, &links),
+            },
+        };
+        global_context.add_records(&page_context);
+        writeln!(file, "{}", page)?;
+    }
+    Ok(())
+}
+
+/// Crate defining an item re-exported from an other crate, linked to the root of its docs:
+/// the one rendered along with this crate when it's one of the inputs, else its documentation
+/// root
+fn source_crate<'krate>(
+    global_context: &GlobalContext<'krate>,
+    page_context: &PageContext<'_>,
+    item: &Item,
+) -> Option<SourceCrate<'krate>> {
+    let external_crate = global_context.krate.external_crates.get(&item.crate_id)?;
+    let name = &external_crate.name;
+
+    let is_input = global_context
+        .opt
+        .files
+        .iter()
+        .any(|file| input_crate_name(file) == Some(name.as_str()));
+    let url = if is_input {
+        Some(format!(
+            "{}/{}/index.html",
+            top_of(page_context.filepath).display(),
+            name
+        ))
+    } else {
+        external_crate
+            .html_root_url
+            .as_ref()
+            .or_else(|| global_context.external_urls.get(&item.crate_id))
+            .map(|root| format!("{}/{}/index.html", root.trim_end_matches('/'), name))
+    };
+    Some(SourceCrate { name, url })
+}
+
+/// At-a-glance counts of the methods and implementors of a trait
+struct TraitCounts {
+    required: usize,
+    provided: usize,
+    implementors: usize,
+}
+
+impl std::fmt::Display for TraitCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} required method{}, {} provided, {} implementor{}",
+            self.required,
+            if self.required == 1 { "" } else { "s" },
+            self.provided,
+            self.implementors,
+            if self.implementors == 1 { "" } else { "s" },
+        )
+    }
 }
 
 /// Function for generating a Trait page
@@ -824,17 +4034,19 @@ fn trait_page<'context>(
     item: &'context Item,
     name: &'context str,
     trait_: &'context Trait,
-) -> Result<PageContext<'context>> {
-    let (page_context, mut file) = base_page(global_context, Some(parent_item_path), item, name)?;
+) -> Result<(PageContext<'context>, TraitCounts)> {
+    let (page_context, file) = base_page(global_context, Some(parent_item_path), item, name)?;
     let definition = item_definition(global_context, &page_context, item)?;
 
     let mut trait_page_content = TraitPageContent {
+        inline_id: page_context.inline_id.as_deref(),
         associated_types: Default::default(),
         associated_consts: Default::default(),
         required_methods: Default::default(),
         provided_methods: Default::default(),
         implementations_foreign_types: Default::default(),
         implementors: Default::default(),
+        required_by: Default::default(),
         auto_implementors: Default::default(),
     };
 
@@ -883,8 +4095,9 @@ fn trait_page<'context>(
             ))
         })
         .collect::<Result<Vec<_>>>()?;
-    items.sort_by(|(_, x_name), (_, y_name)| x_name.cmp(y_name));
+    items.sort_by_key(|(_, x_name)| *x_name);
 
+    let items_open = global_context.section_open(Section::TraitItems, items.len());
     for (item, _name) in items {
         match &item.inner {
             ItemEnum::Function(func) => {
@@ -900,14 +4113,18 @@ fn trait_page<'context>(
                     )
                 };
 
-                who.push(CodeEnchanted::from_item(
+                let mut method = CodeEnchanted::from_item(
                     global_context,
                     &page_context,
                     None,
                     Some(toc),
                     item,
-                    true,
-                )?);
+                    items_open,
+                )?;
+                if func.has_body {
+                    method.default_body = default_body(global_context, item);
+                }
+                who.push(method);
             }
             ItemEnum::AssocConst { .. } => {
                 trait_page_content
@@ -918,7 +4135,7 @@ fn trait_page<'context>(
                         None,
                         Some(&mut toc_associated_consts),
                         item,
-                        true,
+                        items_open,
                     )?);
             }
             ItemEnum::AssocType { .. } => {
@@ -930,7 +4147,7 @@ fn trait_page<'context>(
                         None,
                         Some(&mut toc_associated_types),
                         item,
-                        true,
+                        items_open,
                     )?);
             }
             _ => warn!("ignore {:?}", item.inner),
@@ -939,37 +4156,153 @@ fn trait_page<'context>(
 
     let impls = fetch_impls(global_context, &trait_.implementations)?;
 
-    for (item, impl_, _name) in &impls {
-        let (toc, who) = match type_id(&impl_.for_) {
-            Ok(id) if !id.0.starts_with("0:") => (
-                &mut toc_implementation_foreign_types,
-                &mut trait_page_content.implementations_foreign_types,
-            ),
-            Err(Some(ItemKind::Primitive)) => (
+    let implementors_open = global_context.section_open(Section::Implementors, impls.len());
+    // The impls for `Foo`, `&Foo`, `&mut Foo`, ... are grouped under `Foo`, by section and
+    // type and not by name
+    let keys = impls
+        .iter()
+        .map(|(item, impl_, name)| {
+            let for_ = global_context.impl_class(&item.id).map(|class| class.for_);
+            let foreign = matches!(for_, Some(ImplFor::Foreign | ImplFor::Primitive));
+            let (type_id, group) = match underlying_type(&impl_.for_) {
+                Type::ResolvedPath(path) => (
+                    Some(&path.id),
+                    path.name.rsplit("::").next().unwrap_or(name),
+                ),
+                Type::Generic(type_name) | Type::Primitive(type_name) => (None, type_name.as_str()),
+                _ => (None, name.as_str()),
+            };
+            (foreign, type_id, group)
+        })
+        .collect::<Vec<_>>();
+    // The types named alike are all told apart by their path
+    let mut group_types: HashMap<_, HashSet<_>> = HashMap::new();
+    for (foreign, type_id, group) in &keys {
+        group_types
+            .entry((*foreign, *group))
+            .or_default()
+            .insert(*type_id);
+    }
+    // Index of the group of the impls for a type, by section and type
+    let mut group_indexes = HashMap::new();
+    for ((item, impl_, _), key) in impls.iter().zip(keys) {
+        let (foreign, type_id, group) = key;
+        let (toc, groups) = match foreign {
+            true => (
                 &mut toc_implementation_foreign_types,
                 &mut trait_page_content.implementations_foreign_types,
             ),
-            _ => (&mut toc_implementors, &mut trait_page_content.implementors),
+            false => (&mut toc_implementors, &mut trait_page_content.implementors),
         };
 
-        who.push(CodeEnchantedWithExtras::from_items(
+        let index = *group_indexes.entry(key).or_insert_with(|| {
+            let clash = group_types[&(foreign, group)].len() > 1;
+            let full_path = type_id.and_then(|id| global_context.krate.paths.get(id));
+            let group = match full_path {
+                Some(summary) if clash => Cow::Owned(summary.path.join("::")),
+                _ => Cow::Borrowed(group),
+            };
+            groups.push((group, vec![]));
+            groups.len() - 1
+        });
+        let impl_ = CodeEnchantedWithExtras::from_items(
             global_context,
             &page_context,
             TocSupplier::Top(toc),
             item,
             impl_,
-            false,
-        )?);
+            implementors_open,
+        )?;
+        groups[index].1.push(impl_);
+    }
+
+    let mut toc_required_by = TocSection {
+        name: REQUIRED_BY,
+        id: REQUIRED_BY_ID,
+        items: vec![],
+    };
+    let required_by = global_context
+        .required_by
+        .get(&item.id)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|listed_item| {
+            let (name, path, kind) = listed_item.link(global_context, &page_context)?;
+            Some((name, path, kind, listed_item.id()))
+        })
+        .collect::<Vec<_>>();
+    for (name, path, kind, id) in &required_by {
+        toc_required_by.items.push((
+            Cow::Borrowed(name.as_str()),
+            TocDestination::File(path),
+            None,
+        ));
+        trait_page_content.required_by.push(ModuleSectionItem {
+            name: ItemLink {
+                name: name.as_str(),
+                link: path
+                    .to_str()
+                    .with_context(|| format!("unable to convert PathBuf {:?} to str", path))?,
+                class: kind,
+                title: None,
+            },
+            summary: MarkdownSummaryLine::from_docs(
+                global_context,
+                &page_context,
+                &global_context.krate.index[id].docs,
+                &global_context.krate.index[id].links,
+            ),
+            asyncness: false,
+            constness: false,
+            unsafety: Option::<&str>::None,
+            deprecated: Option::<&str>::None,
+            portability: Option::<&str>::None,
+            since: Option::<&str>::None,
+            visibility: None,
+        });
     }
 
+    let counts = TraitCounts {
+        required: trait_page_content.required_methods.len(),
+        provided: trait_page_content.provided_methods.len(),
+        implementors: impls.len(),
+    };
+
+    let toc = vec![
+        toc_associated_types,
+        toc_associated_consts,
+        toc_required_methods,
+        toc_provided_methods,
+        toc_implementation_foreign_types,
+        toc_implementors,
+        toc_required_by,
+    ];
     let page = Base {
         infos: BodyInformations::with(global_context, &page_context),
         main: ItemPage {
+            item_banner: MarkdownBanner::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            item_readme: Option::<String>::None,
+            item_summary: Some(TraitSummary {
+                counts: counts.to_string(),
+            }),
             item_type: "Trait",
+            inline_id: page_context.inline_id.as_deref(),
             item_name: name,
             item_definition: Some(definition),
             item_deprecation: DeprecationNotice::from(&item.deprecation),
-            item_portability: PortabilityNotice::from(&item.attrs)?,
+            item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+            item_since: since_version(&item.attrs),
+            item_source_crate: source_crate(global_context, &page_context, item),
+            item_must_use: MustUseNotice::from(item),
+            item_notable_traits: NotableTraits::from(global_context, &page_context, item),
+            item_contracts: Option::<ContractBadges>::None,
+            item_sections: plugin_sections(global_context, &page_context, item)?,
             item_path: page_context.item_path.display(&page_context),
             item_doc: MarkdownWithToc::from_docs(
                 global_context,
@@ -977,21 +4310,18 @@ fn trait_page<'context>(
                 &item.docs,
                 &item.links,
             ),
-            toc: &vec![
-                toc_associated_types,
-                toc_associated_consts,
-                toc_required_methods,
-                toc_provided_methods,
-                toc_implementation_foreign_types,
-                toc_implementors,
-            ],
+            toc: &toc,
             content: Some(trait_page_content),
         },
     };
 
-    writeln!(file, "{}", page)?;
+    write_page(global_context, &page_context, file, page)?;
 
-    Ok(page_context)
+    if global_context.opt.fragments {
+        write_fragment(global_context, &page_context, item, &toc)?;
+    }
+
+    Ok((page_context, counts))
 }
 
 /// Function for generating the content of an struct, union or enum
@@ -999,10 +4329,10 @@ fn struct_union_enum_content<'context, 'krate>(
     global_context: &'context GlobalContext<'krate>,
     page_context: &'context PageContext<'context>,
     title: &'static str,
-    variants: &[Id],
+    variants: Vec<&Id>,
     impls: &[Id],
 ) -> Result<(Vec<TocSection<'context>>, impl markup::Render + 'context)> {
-    let impls = fetch_impls(global_context, &impls)?;
+    let impls = fetch_impls(global_context, impls)?;
 
     let mut toc_variants = TocSection {
         name: VARIANTS,
@@ -1029,6 +4359,12 @@ fn struct_union_enum_content<'context, 'krate>(
         id: TRAIT_IMPLEMENTATIONS_ID,
         items: vec![],
     };
+    let mut toc_derived_traits = TocSection {
+        name: DERIVED_TRAIT_IMPLEMENTATIONS,
+        id: DERIVED_TRAIT_IMPLEMENTATIONS_ID,
+        items: vec![],
+    };
+    let mut derived_trait_impls = Vec::new();
     let mut toc_auto_traits = TocSection {
         name: AUTO_TRAIT_IMPLEMENTATIONS,
         id: AUTO_TRAIT_IMPLEMENTATIONS_ID,
@@ -1040,8 +4376,39 @@ fn struct_union_enum_content<'context, 'krate>(
         items: vec![],
     };
 
+    let shared_blanket_impl = |item: &Item| {
+        global_context
+            .shared_blanket_impls
+            .as_ref()
+            .and_then(|shared| shared.get(item))
+    };
+
+    let count = |filter: &dyn Fn(&Item, &Impl) -> bool| {
+        impls
+            .iter()
+            .filter(|(item, impl_, _)| filter(item, impl_))
+            .count()
+    };
+    let methods_open = global_context.section_open(
+        Section::Methods,
+        impls
+            .iter()
+            .filter(|(_, impl_, _)| impl_.trait_.is_none())
+            .map(|(_, impl_, _)| impl_.items.len())
+            .sum(),
+    );
+    let auto_traits_open = global_context.section_open(
+        Section::AutoTraitImplementations,
+        count(&|_, impl_| impl_.synthetic),
+    );
+    let blanket_open = global_context.section_open(
+        Section::BlanketImplementations,
+        count(&|item, impl_| impl_.blanket_impl.is_some() && shared_blanket_impl(item).is_none()),
+    );
+
     // TODO: Move all the filtering logic directly in the map above
-    let content = StructUnionEnumContent {
+    let mut content = StructUnionEnumContent {
+        inline_id: page_context.inline_id.as_deref(),
         title,
         variants: variants
             .iter()
@@ -1061,9 +4428,10 @@ fn struct_union_enum_content<'context, 'krate>(
             })
             .collect::<Result<Vec<_>>>()?,
         traits: TraitsWithItems {
+            inline_id: page_context.inline_id.as_deref(),
             implementations: impls
                 .iter()
-                .filter(|(_, impl_, _)| matches!(impl_.trait_, None))
+                .filter(|(_, impl_, _)| impl_.trait_.is_none())
                 .map(|(item, impl_, _)| {
                     CodeEnchantedWithExtras::from_items(
                         global_context,
@@ -1075,57 +4443,102 @@ fn struct_union_enum_content<'context, 'krate>(
                         ),
                         item,
                         impl_,
-                        true,
+                        methods_open,
                     )
                 })
                 .collect::<Result<Vec<_>>>()?,
-            trait_implementations: impls
-                .iter()
-                .filter_map(
-                    |(item, impl_, _)| match (&impl_.trait_, &impl_.blanket_impl) {
-                        (Some(rustdoc_types::Path { id, .. }), None) => {
-                            match is_auto_trait(global_context.krate, id) {
-                                Ok(Some((false, _))) => Some(CodeEnchantedWithExtras::from_items(
-                                    global_context,
-                                    page_context,
-                                    TocSupplier::Top(&mut toc_traits),
-                                    item,
-                                    impl_,
-                                    false,
-                                )),
-                                Err(e) => Some(Err(e)),
-                                _ => None,
+            trait_implementations: {
+                let mut trait_impls = Vec::new();
+                for (item, impl_, _) in &impls {
+                    match global_context.impl_class(&item.id) {
+                        Some(ImplClass {
+                            trait_: Some((false, crate_id)),
+                            ..
+                        }) => trait_impls.push((crate_id, item, impl_)),
+                        Some(ImplClass { trait_: None, .. }) => {
+                            if let (Some(trait_), None) = (&impl_.trait_, &impl_.blanket_impl) {
+                                warn!(
+                                    "unable to find the trait (for auto-trait checking) {:?} -- skipping",
+                                    trait_.id
+                                );
                             }
                         }
-                        _ => None,
-                    },
-                )
-                .collect::<Result<Vec<_>>>()?,
+                        _ => {}
+                    }
+                }
+
+                // Local traits first, then the external ones grouped by their crate
+                trait_impls.sort_by_key(|(crate_id, ..)| {
+                    (*crate_id != 0, crate_name(global_context, *crate_id))
+                });
+
+                let derived = trait_impls
+                    .iter()
+                    .filter(|(_, item, _)| global_context.opt.group_derived && is_derived(item))
+                    .count();
+                let derived_open =
+                    global_context.section_open(Section::DerivedImplementations, derived);
+                let traits_open = global_context
+                    .section_open(Section::TraitImplementations, trait_impls.len() - derived);
+
+                let mut groups: Vec<(&str, Vec<_>)> = Vec::new();
+                for (crate_id, item, impl_) in trait_impls {
+                    if global_context.opt.group_derived && is_derived(item) {
+                        derived_trait_impls.push(CodeEnchantedWithExtras::from_items(
+                            global_context,
+                            page_context,
+                            TocSupplier::Top(&mut toc_derived_traits),
+                            item,
+                            impl_,
+                            derived_open,
+                        )?);
+                        continue;
+                    }
+
+                    let impl_ = CodeEnchantedWithExtras::from_items(
+                        global_context,
+                        page_context,
+                        TocSupplier::Top(&mut toc_traits),
+                        item,
+                        impl_,
+                        traits_open,
+                    )?;
+
+                    let name = crate_name(global_context, crate_id);
+                    match groups.last_mut() {
+                        Some((group, impls)) if *group == name => impls.push(impl_),
+                        _ => groups.push((name, vec![impl_])),
+                    }
+                }
+                groups
+            },
+            derived_trait_implementations: derived_trait_impls,
             auto_trait_implementations: impls
                 .iter()
-                .filter_map(
-                    |(item, impl_, _)| match (&impl_.trait_, &impl_.blanket_impl) {
-                        (Some(rustdoc_types::Path { id, .. }), None) => {
-                            match is_auto_trait(global_context.krate, id) {
-                                Ok(Some((true, _))) => Some(CodeEnchantedWithExtras::from_items(
-                                    global_context,
-                                    page_context,
-                                    TocSupplier::Top(&mut toc_auto_traits),
-                                    item,
-                                    impl_,
-                                    false,
-                                )),
-                                Err(e) => Some(Err(e)),
-                                _ => None,
-                            }
-                        }
-                        _ => None,
-                    },
-                )
+                .filter(|(item, _, _)| {
+                    matches!(
+                        global_context.impl_class(&item.id),
+                        Some(ImplClass {
+                            trait_: Some((true, _)),
+                            ..
+                        })
+                    )
+                })
+                .map(|(item, impl_, _)| {
+                    CodeEnchantedWithExtras::from_items(
+                        global_context,
+                        page_context,
+                        TocSupplier::Top(&mut toc_auto_traits),
+                        item,
+                        impl_,
+                        auto_traits_open,
+                    )
+                })
                 .collect::<Result<Vec<_>>>()?,
             blanket_implementations: impls
                 .iter()
-                .filter(|(_item, impl_, _)| matches!(impl_.blanket_impl, Some(_)))
+                .filter(|(_item, impl_, _)| impl_.blanket_impl.is_some())
+                .filter(|(item, _, _)| shared_blanket_impl(item).is_none())
                 .map(|(item, impl_, _)| {
                     CodeEnchantedWithExtras::from_items(
                         global_context,
@@ -1133,13 +4546,65 @@ fn struct_union_enum_content<'context, 'krate>(
                         TocSupplier::Top(&mut toc_blanket_traits),
                         item,
                         impl_,
-                        false,
+                        blanket_open,
                     )
                 })
                 .collect::<Result<Vec<_>>>()?,
+            shared_blanket_implementations: impls
+                .iter()
+                .filter_map(|(item, _, _)| Some((item, shared_blanket_impl(item)?)))
+                .map(|(item, (generic, shared_id))| {
+                    let id = id(item).map(|(name, id)| {
+                        let id = &*page_context.ids.alloc(page_context.unique_impl_id(id));
+                        toc_blanket_traits
+                            .items
+                            .push((name, TocDestination::Id(id), None));
+                        id
+                    });
+                    let shared_page =
+                        PathBuf::from(global_context.krate_name).join(BLANKET_IMPLS_HTML);
+
+                    Ok(SharedImplLink {
+                        code: TokensToHtml(
+                            global_context,
+                            page_context,
+                            pp::Tokens::from_item(generic, &global_context.krate.index)?,
+                        ),
+                        id,
+                        href: format!(
+                            "{}{}",
+                            relative(page_context.filepath, &shared_page).display(),
+                            shared_id.with_pound()
+                        ),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
         },
     };
 
+    let traits = &mut content.traits;
+    let impls_count = traits
+        .trait_implementations
+        .iter()
+        .map(|(_, impls)| impls.len())
+        .sum::<usize>()
+        + traits.derived_trait_implementations.len()
+        + traits.auto_trait_implementations.len()
+        + traits.blanket_implementations.len();
+    if impls_count > LAZY_IMPLS_THRESHOLD {
+        defer_impls(
+            global_context,
+            page_context,
+            traits
+                .trait_implementations
+                .iter_mut()
+                .flat_map(|(_, impls)| impls.iter_mut())
+                .chain(traits.derived_trait_implementations.iter_mut())
+                .chain(traits.auto_trait_implementations.iter_mut())
+                .chain(traits.blanket_implementations.iter_mut()),
+        )?;
+    }
+
     Ok((
         vec![
             toc_variants,
@@ -1147,6 +4612,7 @@ fn struct_union_enum_content<'context, 'krate>(
             toc_assoc_types,
             toc_assoc_consts,
             toc_traits,
+            toc_derived_traits,
             toc_auto_traits,
             toc_blanket_traits,
         ],
@@ -1154,6 +4620,237 @@ fn struct_union_enum_content<'context, 'krate>(
     ))
 }
 
+/// Move the body of the collapsed impls to a side script, only loaded when one of
+/// them is expanded, to keep the pages of types with a lot of impls light
+fn defer_impls<'a, 'id: 'a, Code, Documentation, Deprecation, Extra>(
+    global_context: &GlobalContext<'_>,
+    page_context: &PageContext<'_>,
+    impls: impl Iterator<
+        Item = &'a mut CodeEnchantedWithExtras<
+            Code,
+            Documentation,
+            Deprecation,
+            &'id HtmlId,
+            Extra,
+        >,
+    >,
+) -> Result<()>
+where
+    Code: markup::Render + 'a,
+    Documentation: markup::Render + 'a,
+    Deprecation: markup::Render + 'a,
+    Extra: markup::Render + 'a,
+{
+    let lazy_src = page_context.filename.with_extension(LAZY_IMPLS_EXTENSION);
+    let lazy_src = lazy_src.to_str().context("non utf-8 file name")?;
+
+    let mut bodies = BTreeMap::new();
+    for impl_ in impls {
+        if let (Some(id), false) = (impl_.id, impl_.open) {
+            if impl_.doc.is_some() || !impl_.extras.is_empty() {
+                let body = ImplBody {
+                    doc: &impl_.doc,
+                    deprecation: &impl_.deprecation,
+                    extras: &impl_.extras,
+                };
+                let body = body.to_string();
+                validate_html(&body, lazy_src);
+                bodies.insert(id.to_string(), body);
+                impl_.lazy_src = Some(lazy_src.to_string());
+            }
+        }
+    }
+
+    if !bodies.is_empty() {
+        debug!("deferring {} impl bodies to {:?}", bodies.len(), lazy_src);
+        let bodies =
+            serde_json::to_string(&bodies).context("unable to serialize the impl bodies")?;
+        dump_to(
+            global_context
+                .opt
+                .output
+                .join(page_context.filepath.with_file_name(lazy_src)),
+            format!("rdLazyImplsLoaded({});\n", bodies).as_bytes(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Constant page generation function
+fn constant_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: &'context ItemPath,
+    item: &'context Item,
+    name: &'context str,
+    const_: &'context Constant,
+) -> Result<PageContext<'context>> {
+    let (page_context, file) = base_page(global_context, Some(parent_item_path), item, name)?;
+    let definition = item_definition(global_context, &page_context, item)?;
+
+    let toc = vec![];
+    let content = ConstantContent {
+        inline_id: page_context.inline_id.as_deref(),
+        value: const_
+            .value
+            .as_deref()
+            .filter(|value| *value != const_.expr),
+        expr: Some(&*const_.expr).filter(|expr| pp::is_long_expr(expr)),
+    };
+
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: MarkdownBanner::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Constant",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: name,
+            item_definition: Some(definition),
+            item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+            item_since: since_version(&item.attrs),
+            item_source_crate: source_crate(global_context, &page_context, item),
+            item_must_use: MustUseNotice::from(item),
+            item_notable_traits: NotableTraits::from(global_context, &page_context, item),
+            item_contracts: Option::<ContractBadges>::None,
+            item_sections: plugin_sections(global_context, &page_context, item)?,
+            item_deprecation: DeprecationNotice::from(&item.deprecation),
+            item_path: page_context.item_path.display(&page_context),
+            item_doc: MarkdownWithToc::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            toc: &toc,
+            content: Some(content),
+        },
+    };
+
+    write_page(global_context, &page_context, file, page)?;
+
+    if global_context.opt.fragments {
+        write_fragment(global_context, &page_context, item, &toc)?;
+    }
+
+    Ok(page_context)
+}
+
+/// Keyword page generation function, for the modules with `#[doc(keyword = "..")]`
+fn keyword_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: &'context ItemPath,
+    item: &'context Item,
+    name: &'context str,
+) -> Result<PageContext<'context>> {
+    let (page_context, file) = base_page(global_context, Some(parent_item_path), item, name)?;
+
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: MarkdownBanner::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Keyword",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: name,
+            item_definition: Option::<String>::None,
+            item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+            item_since: since_version(&item.attrs),
+            item_source_crate: source_crate(global_context, &page_context, item),
+            item_must_use: MustUseNotice::from(item),
+            item_notable_traits: NotableTraits::from(global_context, &page_context, item),
+            item_contracts: Option::<ContractBadges>::None,
+            item_sections: plugin_sections(global_context, &page_context, item)?,
+            item_deprecation: DeprecationNotice::from(&item.deprecation),
+            item_path: page_context.item_path.display(&page_context),
+            item_doc: MarkdownWithToc::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            toc: &vec![],
+            content: Option::<String>::None,
+        },
+    };
+
+    write_page(global_context, &page_context, file, page)?;
+
+    if global_context.opt.fragments {
+        write_fragment(global_context, &page_context, item, &[])?;
+    }
+
+    Ok(page_context)
+}
+
+/// Primitive type page generation function, with the impls of the primitive
+fn primitive_page<'context>(
+    global_context: &'context GlobalContext<'context>,
+    parent_item_path: &'context ItemPath,
+    item: &'context Item,
+    name: &'context str,
+    impls: &'context [Id],
+) -> Result<PageContext<'context>> {
+    let (page_context, file) = base_page(global_context, Some(parent_item_path), item, name)?;
+
+    let (toc, content) =
+        struct_union_enum_content(global_context, &page_context, "Fields", vec![], impls)?;
+
+    let page = Base {
+        infos: BodyInformations::with(global_context, &page_context),
+        main: ItemPage {
+            item_banner: MarkdownBanner::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            item_readme: Option::<String>::None,
+            item_summary: Option::<String>::None,
+            item_type: "Primitive Type",
+            inline_id: page_context.inline_id.as_deref(),
+            item_name: name,
+            item_definition: Option::<String>::None,
+            item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+            item_since: since_version(&item.attrs),
+            item_source_crate: source_crate(global_context, &page_context, item),
+            item_must_use: MustUseNotice::from(item),
+            item_notable_traits: NotableTraits::from(global_context, &page_context, item),
+            item_contracts: Option::<ContractBadges>::None,
+            item_sections: plugin_sections(global_context, &page_context, item)?,
+            item_deprecation: DeprecationNotice::from(&item.deprecation),
+            item_path: page_context.item_path.display(&page_context),
+            item_doc: MarkdownWithToc::from_docs(
+                global_context,
+                &page_context,
+                &item.docs,
+                &item.links,
+            ),
+            toc: &toc,
+            content: Some(content),
+        },
+    };
+
+    write_page(global_context, &page_context, file, page)?;
+
+    if global_context.opt.fragments {
+        write_fragment(global_context, &page_context, item, &toc)?;
+    }
+
+    Ok(page_context)
+}
+
 macro_rules! ç {
     ($ty:ty => $fn:ident $type:literal $title:literal $fields:expr) => {
         /// Function for generating a $ty page
@@ -1164,7 +4861,7 @@ macro_rules! ç {
             name: &'context str,
             inner: &'context $ty,
         ) -> Result<PageContext<'context>> {
-            let (page_context, mut file) =
+            let (page_context, file) =
                 base_page(global_context, Some(parent_item_path), item, name)?;
             let definition = item_definition(global_context, &page_context, item)?;
 
@@ -1172,17 +4869,32 @@ macro_rules! ç {
                 global_context,
                 &page_context,
                 $title,
-                $fields(&inner),
+                $fields(global_context.krate, &inner),
                 &inner.impls,
             )?;
 
             let page = Base {
                 infos: BodyInformations::with(global_context, &page_context),
                 main: ItemPage {
+                    item_banner: MarkdownBanner::from_docs(
+                        global_context,
+                        &page_context,
+                        &item.docs,
+                        &item.links,
+                    ),
+                    item_readme: Option::<String>::None,
+                    item_summary: Option::<String>::None,
                     item_type: $type,
+                    inline_id: page_context.inline_id.as_deref(),
                     item_name: name,
                     item_definition: Some(definition),
-                    item_portability: PortabilityNotice::from(&item.attrs)?,
+                    item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+                    item_since: since_version(&item.attrs),
+                    item_source_crate: source_crate(global_context, &page_context, item),
+                    item_must_use: MustUseNotice::from(item),
+                    item_notable_traits: NotableTraits::from(global_context, &page_context, item),
+                    item_contracts: Option::<ContractBadges>::None,
+                    item_sections: plugin_sections(global_context, &page_context, item)?,
                     item_deprecation: DeprecationNotice::from(&item.deprecation),
                     item_path: page_context.item_path.display(&page_context),
                     item_doc: MarkdownWithToc::from_docs(
@@ -1196,8 +4908,11 @@ macro_rules! ç {
                 },
             };
 
-            writeln!(file, "{}", page)?;
-            drop(page);
+            write_page(global_context, &page_context, file, page)?;
+
+            if global_context.opt.fragments {
+                write_fragment(global_context, &page_context, item, &toc)?;
+            }
 
             Ok(page_context)
         }
@@ -1208,8 +4923,8 @@ macro_rules! ù {
     ($ty:ty => $fn:ident $type:literal $title:literal $fields:ident) => {
         ç!($ty => $fn $type $title {
             // HACK: This is a giant hack, we should do better
-            fn ids<'a>(ty: &'a $ty) -> &'a [Id] {
-                &ty.$fields
+            fn ids<'a>(_krate: &Crate, ty: &'a $ty) -> Vec<&'a Id> {
+                ty.$fields.iter().collect()
             }
             ids
         });
@@ -1226,16 +4941,38 @@ macro_rules! é {
             name: &'context str,
             #[allow(unused)] inner: &'context $ty,
         ) -> Result<PageContext<'context>> {
-            let (page_context, mut file) = base_page(global_context, Some(parent_item_path), item, name)?;
+            let (page_context, file) =
+                base_page(global_context, Some(parent_item_path), item, name)?;
             let definition = item_definition(global_context, &page_context, item)?;
+            // The sections are already in the table of contents of the docs
+            let contracts = match item.inner {
+                ItemEnum::Function(_) => ContractBadges::from(&page_context, None, item),
+                _ => None,
+            };
+            let toc = Vec::new();
 
             let page = Base {
                 infos: BodyInformations::with(global_context, &page_context),
                 main: ItemPage {
+                    item_banner: MarkdownBanner::from_docs(
+                        global_context,
+                        &page_context,
+                        &item.docs,
+                        &item.links,
+                    ),
+                    item_readme: Option::<String>::None,
+                    item_summary: Option::<String>::None,
                     item_type: $type,
+                    inline_id: page_context.inline_id.as_deref(),
                     item_name: name,
                     item_definition: Some(definition),
-                    item_portability: PortabilityNotice::from(&item.attrs)?,
+                    item_portability: PortabilityNotice::from(global_context, &item.attrs)?,
+                    item_since: since_version(&item.attrs),
+                    item_source_crate: source_crate(global_context, &page_context, item),
+                    item_must_use: MustUseNotice::from(item),
+                    item_notable_traits: NotableTraits::from(global_context, &page_context, item),
+                    item_contracts: contracts,
+                    item_sections: plugin_sections(global_context, &page_context, item)?,
                     item_deprecation: DeprecationNotice::from(&item.deprecation),
                     item_path: page_context.item_path.display(&page_context),
                     item_doc: MarkdownWithToc::from_docs(
@@ -1244,12 +4981,16 @@ macro_rules! é {
                         &item.docs,
                         &item.links,
                     ),
-                    toc: /* TODO: Optional */ &vec![],
+                    toc: &toc,
                     content: Option::<String>::None,
                 },
             };
 
-            writeln!(file, "{}", page)?;
+            write_page(global_context, &page_context, file, page)?;
+
+            if global_context.opt.fragments {
+                write_fragment(global_context, &page_context, item, &[])?;
+            }
 
             Ok(page_context)
         }
@@ -1258,12 +4999,19 @@ macro_rules! é {
 
 ç!(Struct => struct_page "Struct" "Fields" {
     // HACK: This is a giant hack, we should do better
-    fn ids<'a>(struct_: &'a Struct) -> &'a [Id] {
+    fn ids<'a>(krate: &Crate, struct_: &'a Struct) -> Vec<&'a Id> {
         match &struct_.kind {
-            StructKind::Unit => &[],
-            // TODO: This should return fields but it's a `Vec<Option<Id>>` and not `Vec<Id>`
-            StructKind::Tuple(_fields) => &[],
-            StructKind::Plain { fields, fields_stripped: _ } => &fields,
+            StructKind::Unit => vec![],
+            // Like rustdoc, tuple fields are only listed when at least one is documented
+            StructKind::Tuple(fields) => {
+                let fields: Vec<&Id> = fields.iter().flatten().collect();
+                if fields.iter().any(|id| krate.index.get(id).is_some_and(|field| field.docs.is_some())) {
+                    fields
+                } else {
+                    vec![]
+                }
+            }
+            StructKind::Plain { fields, fields_stripped: _ } => fields.iter().collect(),
         }
     }
     ids
@@ -1271,10 +5019,11 @@ macro_rules! é {
 ù!(Union => union_page "Union" "Fields" fields);
 ù!(Enum => enum_page "Enum" "Variants" variants);
 é!(TypeAlias => typealias_page "Type Definition");
+é!(OpaqueTy => opaque_ty_page "Opaque Type");
+é!(() => foreign_type_page "Foreign Type");
 é!(str => macro_page "Macro");
 é!(ProcMacro => proc_macro_page "Proc-Macro");
 é!(Function => function_page "Function");
-é!(Constant => constant_page "Constant");
 é!(Static => static_page "Static");
 
 impl<'context, 'krate>
@@ -1283,6 +5032,8 @@ impl<'context, 'krate>
         Markdown<'context, 'krate, 'context>,
         DeprecationNotice<'context>,
         &'context HtmlId,
+        NotableTraits<TokensToHtml<'context, 'krate>>,
+        ContractBadges,
     >
 {
     fn from_item(
@@ -1293,14 +5044,30 @@ impl<'context, 'krate>
         item: &'krate Item,
         open: bool,
     ) -> Result<Self> {
-        let id = if let Some((name, mut id)) = id(global_context.krate, item) {
-            if let Some(parent_id) = parent_id {
+        let id = if let Some((name, mut id)) = id(item) {
+            let mut aliases = vec![id.to_string()];
+            aliases.extend(parent_id.map(|parent_id| format!("{}.{}", parent_id, id)));
+            aliases.extend(rustdoc_id(item).map(|id| id.to_string()));
+
+            if global_context.opt.rustdoc_compat_urls {
+                id = page_context.rustdoc_unique_id(rustdoc_id(item).unwrap_or(id));
+            } else if let Some(parent_id) = parent_id {
                 id = parent_id + id;
+            } else {
+                // The items of the inherent impls aren't scoped by their impl, and two of them
+                // can share a name (like `new` in `impl Foo<u8>` and `impl Foo<u16>`)
+                id = page_context.rustdoc_unique_id(id);
             }
             let id = page_context.ids.alloc(id);
+            page_context
+                .assoc_ids
+                .borrow_mut()
+                .insert(item.id.clone(), id.to_string());
+            record_anchor(page_context, item, id);
+            record_anchor_aliases(page_context, aliases, id);
 
             if let Some(toc_section) = toc_section {
-                toc_section.items.push((name, TocDestination::Id(id)));
+                toc_section.items.push((name, TocDestination::Id(id), None));
             }
             Some(&*id)
         } else {
@@ -1308,16 +5075,18 @@ impl<'context, 'krate>
         };
 
         Ok(Self {
-            code: TokensToHtml(
-                global_context,
-                page_context,
-                pp::Tokens::from_item(item, &global_context.krate.index)?,
-            ),
+            code: TokensToHtml(global_context, page_context, global_context.tokens(item)?),
             doc: Markdown::from_docs(global_context, page_context, id, &item.docs, &item.links),
             deprecation: DeprecationNotice::from(&item.deprecation),
+            notable_traits: NotableTraits::from(global_context, page_context, item),
+            contracts: match item.inner {
+                ItemEnum::Function(_) => ContractBadges::from(page_context, id, item),
+                _ => None,
+            },
             id,
             open,
             source_href: Option::<String>::None,
+            default_body: None,
         })
     }
 }
@@ -1333,6 +5102,8 @@ impl<'context, 'krate>
             Markdown<'context, 'krate, 'context>,
             DeprecationNotice<'context>,
             &'context HtmlId,
+            NotableTraits<TokensToHtml<'context, 'krate>>,
+            ContractBadges,
         >,
     >
 {
@@ -1344,25 +5115,74 @@ impl<'context, 'krate>
         impl_: &'krate Impl,
         open: bool,
     ) -> Result<Self> {
-        let parent_id = if let TocSupplier::Top(toc_top_section) = &mut toc_section {
-            if let Some((name, id)) = id(global_context.krate, item) {
+        let (parent_id, legacy_id) = if let TocSupplier::Top(toc_top_section) = &mut toc_section {
+            if let Some((name, id)) = id(item) {
+                let rustdoc_id =
+                    rustdoc_impl_id(impl_).filter(|_| global_context.opt.rustdoc_compat_urls);
+                let (id, legacy_id) = match rustdoc_id {
+                    // The stable id of the impl is redirected to the one of rustdoc
+                    Some(rustdoc_id) => (page_context.rustdoc_unique_id(rustdoc_id), Some(id)),
+                    None => (
+                        page_context.unique_impl_id(id),
+                        legacy_impl_id(global_context.krate, item),
+                    ),
+                };
                 let id = page_context.ids.alloc(id);
+                let legacy_id = legacy_id
+                    .map(|legacy_id| page_context.inline_prefixed(legacy_id))
+                    .filter(|legacy_id| legacy_id.to_string() != id.to_string())
+                    .map(|legacy_id| &*page_context.ids.alloc(legacy_id));
+
+                if let Some(legacy_id) = legacy_id {
+                    page_context
+                        .records
+                        .borrow_mut()
+                        .impl_redirects
+                        .push((legacy_id.to_string(), id.to_string()));
+                }
 
-                toc_top_section.items.push((name, TocDestination::Id(id)));
-                Some(&*id)
+                toc_top_section
+                    .items
+                    .push((name, TocDestination::Id(id), None));
+                (Some(&*id), legacy_id)
             } else {
-                None
+                (None, None)
             }
         } else {
-            None
+            (None, None)
         };
 
+        // The module of the impl when it isn't the one of the item of the page
+        let module = match page_context.item.inner {
+            ItemEnum::Module(_) => None,
+            _ => defining_module(global_context, item).filter(|module| {
+                defining_module(global_context, page_context.item) != Some(module)
+            }),
+        }
+        .map(|module| ImplModule {
+            path: global_context.krate.paths[module].path.join("::"),
+            href: href(global_context, page_context, module)
+                .map(|(_, path, _, _)| path.display().to_string()),
+        });
+
+        // The cfg of the impl, often the reason for splitting the impls of a type
+        let portability =
+            Portability::from_attrs(&item.attrs)?.map(|portability| ImplPortability {
+                cfg: portability.render_short().to_string(),
+                disabled: global_context.cfg_set.is_disabled(&item.attrs),
+            });
+
+        let trait_items = impl_
+            .trait_
+            .as_ref()
+            .and_then(|trait_| global_context.krate.index.get(&trait_.id))
+            .and_then(|trait_| match &trait_.inner {
+                ItemEnum::Trait(trait_) => Some(&trait_.items),
+                _ => None,
+            });
+
         Ok(CodeEnchantedWithExtras {
-            code: TokensToHtml(
-                global_context,
-                page_context,
-                pp::Tokens::from_item(item, &global_context.krate.index)?,
-            ),
+            code: TokensToHtml(global_context, page_context, global_context.tokens(item)?),
             doc: Markdown::from_docs(
                 global_context,
                 page_context,
@@ -1376,6 +5196,10 @@ impl<'context, 'krate>
             deprecation: DeprecationNotice::from(&item.deprecation),
             open,
             source_href: Option::<String>::None,
+            lazy_src: None,
+            derived: is_derived(item),
+            module,
+            portability,
             extras: impl_
                 .items
                 .iter()
@@ -1384,7 +5208,7 @@ impl<'context, 'krate>
                         format!("unable to find the impl item {:?} -- fatal", id)
                     })?;
 
-                    CodeEnchanted::from_item(
+                    let mut enchanted = CodeEnchanted::from_item(
                         global_context,
                         page_context,
                         parent_id,
@@ -1402,10 +5226,21 @@ impl<'context, 'krate>
                         },
                         item,
                         open,
-                    )
+                    )?;
+
+                    // Link the name of the item to its declaration on the trait page
+                    if let Some(trait_item) = trait_items.and_then(|trait_items| {
+                        trait_items.iter().find(|id| {
+                            global_context.krate.index.get(id).map(|i| &i.name) == Some(&item.name)
+                        })
+                    }) {
+                        enchanted.code.2.retarget(&item.id, trait_item);
+                    }
+                    Ok(enchanted)
                 })
                 .collect::<Result<Vec<_>>>()?,
             id: parent_id,
+            legacy_id,
         })
     }
 }
@@ -1424,15 +5259,11 @@ impl<'context, 'krate>
         parent_id: &'context HtmlId,
         item: &'krate Item,
     ) -> Result<Self> {
-        let (_, id) = id(global_context.krate, item).context("TODO")?;
+        let (_, id) = id(item).context("TODO")?;
         let id = page_context.ids.alloc(parent_id + id);
 
         Ok(Self {
-            def: TokensToHtml(
-                global_context,
-                page_context,
-                pp::Tokens::from_item(item, &global_context.krate.index)?,
-            ),
+            def: TokensToHtml(global_context, page_context, global_context.tokens(item)?),
             id,
             doc: Markdown::from_docs(
                 global_context,
@@ -1466,21 +5297,22 @@ impl<'context, 'krate>
         toc_section: &mut TocSection<'context>,
         item: &'krate Item,
     ) -> Result<Self> {
-        let parent_id = if let Some((name, id)) = id(global_context.krate, item) {
-            let id = page_context.ids.alloc(id);
-            toc_section.items.push((name, TocDestination::Id(id)));
+        let parent_id = if let Some((name, id)) = id(item) {
+            let id = page_context.ids.alloc(page_context.inline_prefixed(id));
+            page_context
+                .assoc_ids
+                .borrow_mut()
+                .insert(item.id.clone(), id.to_string());
+            record_anchor(page_context, item, id);
+            toc_section.items.push((name, TocDestination::Id(id), None));
             &*id
         } else {
             unreachable!()
         };
 
         Ok(VariantEnchantedWithExtras {
-            id: &*parent_id,
-            def: TokensToHtml(
-                global_context,
-                page_context,
-                pp::Tokens::from_item(item, &global_context.krate.index)?,
-            ),
+            id: parent_id,
+            def: TokensToHtml(global_context, page_context, global_context.tokens(item)?),
             doc: Markdown::from_docs(
                 global_context,
                 page_context,
@@ -1540,17 +5372,46 @@ impl<'context, 'krate>
     }
 }
 
+/// Write an identifier, with a break opportunity after each `::` of its path
+fn write_ident(writer: &mut impl std::fmt::Write, ident: &str, wbr: bool) -> std::fmt::Result {
+    if !wbr {
+        return escape_html(writer, ident);
+    }
+
+    for (index, part) in ident.split("::").enumerate() {
+        if index != 0 {
+            writer.write_str("::<wbr>")?;
+        }
+        escape_html(writer, part)?;
+    }
+    Ok(())
+}
+
 /// Convert a [`pp::Tokens`] struct to an `markup`able output
-struct TokensToHtml<'context, 'krate>(
-    &'context GlobalContext<'krate>,
-    &'context PageContext<'context>,
-    pp::Tokens<'krate>,
+pub(super) struct TokensToHtml<'context, 'krate>(
+    pub(super) &'context GlobalContext<'krate>,
+    pub(super) &'context PageContext<'context>,
+    pub(super) pp::Tokens<'krate>,
 );
 
+impl Drop for TokensToHtml<'_, '_> {
+    fn drop(&mut self) {
+        self.0.recycle(std::mem::take(&mut self.2));
+    }
+}
+
 impl<'context, 'krate /*, 'tokens */> markup::Render
     for TokensToHtml<'context, 'krate /*, 'tokens*/>
 {
     fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
+        if let Some(stats) = &self.0.stats {
+            stats.add_tokens(self.1.filepath, self.2.len());
+        }
+
+        // Break opportunities in the (long) paths and generics
+        let wbr = !self.0.opt.no_wbr;
+        let classes = &self.0.opt.syntax_classes;
+
         let mut in_where_clause = false;
         for token in &*self.2 {
             match token {
@@ -1558,7 +5419,8 @@ impl<'context, 'krate /*, 'tokens */> markup::Render
                     writer.write_str("<span")?;
 
                     if let Some(id) = id {
-                        writer.write_str(" class=\"ident")?;
+                        writer.write_str(" class=\"")?;
+                        writer.write_str(&classes.ident)?;
                         if let Some((external_crate_url, relative_path, fragment, type_of)) =
                             href(self.0, self.1, id)
                         {
@@ -1568,26 +5430,26 @@ impl<'context, 'krate /*, 'tokens */> markup::Render
 
                             writer.write_str("<a href=\"")?;
                             if let Some(external_crate_url) = external_crate_url {
-                                writer.write_str(external_crate_url)?;
+                                escape_html(writer, external_crate_url)?;
                                 if !external_crate_url.ends_with('/') {
                                     writer.write_str("/")?;
                                 }
                             }
-                            writer.write_str(relative_path.to_str().unwrap())?;
+                            escape_html(writer, relative_path.to_str().unwrap())?;
                             if let Some(fragment) = fragment {
                                 writer.write_str("#")?;
-                                writer.write_str(&fragment)?;
+                                escape_html(writer, &fragment)?;
                             }
                             writer.write_str("\">")?;
-                            writer.write_str(ident)?;
+                            write_ident(writer, ident, wbr)?;
                             writer.write_str("</a>")?;
                         } else {
                             writer.write_str("\">")?;
-                            writer.write_str(ident)?;
+                            write_ident(writer, ident, wbr)?;
                         }
                     } else {
                         writer.write_str(">")?;
-                        writer.write_str(ident)?;
+                        write_ident(writer, ident, wbr)?;
                     }
 
                     writer.write_str("</span>")?;
@@ -1598,10 +5460,10 @@ impl<'context, 'krate /*, 'tokens */> markup::Render
                             warn!("already in where clause");
                         }
                         in_where_clause = true;
-                        writer.write_str("<span class=\"where-clause\">")?;
+                        write!(writer, "<span class=\"{}\">", classes.where_clause)?;
                     }
-                    writer.write_str("<span class=\"kw\">")?;
-                    writer.write_str(kw)?;
+                    write!(writer, "<span class=\"{}\">", classes.kw)?;
+                    escape_html(writer, kw)?;
                     writer.write_str("</span>")?;
                 }
                 pp::Token::Ponct(ponct) => {
@@ -1609,23 +5471,21 @@ impl<'context, 'krate /*, 'tokens */> markup::Render
                         writer.write_str("</span>")?;
                         in_where_clause = false;
                     }
-                    writer.write_str("<span class=\"ponct\">")?;
-                    match *ponct {
-                        ">" => writer.write_str("&gt;")?,
-                        "<" => writer.write_str("&lt;")?,
-                        "&" => writer.write_str("&amp;")?,
-                        _ => writer.write_str(ponct)?,
-                    }
+                    write!(writer, "<span class=\"{}\">", classes.ponct)?;
+                    escape_html(writer, ponct)?;
                     writer.write_str("</span>")?;
+                    if wbr && matches!(*ponct, "::" | "<" | ",") {
+                        writer.write_str("<wbr>")?;
+                    }
                 }
                 pp::Token::Attr(attr) => {
-                    writer.write_str("<span class=\"attr\">")?;
-                    writer.write_str(attr)?;
+                    write!(writer, "<span class=\"{}\">", classes.attr)?;
+                    escape_html(writer, attr)?;
                     writer.write_str("</span>")?;
                 }
                 pp::Token::Primitive(primitive) => {
-                    writer.write_str("<span class=\"primitive\">")?;
-                    writer.write_str(primitive)?;
+                    write!(writer, "<span class=\"{}\">", classes.primitive)?;
+                    escape_html(writer, primitive)?;
                     writer.write_str("</span>")?;
                 }
                 pp::Token::Special(special) => match special {
@@ -1648,3 +5508,30 @@ impl<'context, 'krate /*, 'tokens */> markup::Render
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sitemap_of_the_pages() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("rd-render-{}-sitemap", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let pages = vec![
+            PathBuf::from("demo").join("index.html"),
+            PathBuf::from("demo").join("inner").join("struct.Foo.html"),
+        ]
+        .into_iter()
+        .collect();
+        write_sitemap(&dir, "https://docs.example.com", &pages)?;
+
+        let sitemap = std::fs::read_to_string(dir.join(SITEMAP_XML))?;
+        std::fs::remove_dir_all(&dir)?;
+        assert!(sitemap.starts_with("<?xml"));
+        assert!(sitemap.contains("<loc>https://docs.example.com/demo/index.html</loc>"));
+        assert!(sitemap.contains("<loc>https://docs.example.com/demo/inner/struct.Foo.html</loc>"));
+        assert!(sitemap.ends_with("</urlset>\n"));
+        Ok(())
+    }
+}
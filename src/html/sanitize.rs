@@ -0,0 +1,453 @@
+//! Sanitization of the raw HTML in the docs
+
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::iter::Peekable;
+
+use super::escape::escape_html;
+
+/// Tags allowed in the raw HTML of the docs
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "center",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "picture",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "small",
+    "source",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "tt",
+    "u",
+    "ul",
+    "var",
+    "wbr",
+];
+
+/// Attributes allowed on all the allowed tags (not `id`, which could collide with the ids of
+/// the page)
+const ALLOWED_ATTRIBUTES: &[&str] = &["align", "class", "lang", "title"];
+
+/// Attributes allowed only on some tags
+const ALLOWED_TAG_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("a", "name"),
+    ("details", "open"),
+    ("img", "alt"),
+    ("img", "height"),
+    ("img", "src"),
+    ("img", "width"),
+    ("ol", "start"),
+    ("source", "media"),
+    ("source", "srcset"),
+    ("source", "type"),
+    ("td", "colspan"),
+    ("td", "rowspan"),
+    ("th", "colspan"),
+    ("th", "rowspan"),
+];
+
+/// Attributes holding an url (or a list of url with `srcset`)
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "srcset"];
+
+/// Url schemes allowed in the url attributes
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Sanitize the raw HTML events: unknown tags are escaped (shown as text), unknown attributes
+/// and urls with an unknown scheme (also in the Markdown links) are dropped, comments are removed
+pub(super) struct Sanitize<'a, I: Iterator<Item = Event<'a>>> {
+    inner: Peekable<I>,
+    allow_raw_html: bool,
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Sanitize<'a, I> {
+    pub(super) fn new(iter: I, allow_raw_html: bool) -> Self {
+        Self {
+            inner: iter.peekable(),
+            allow_raw_html,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Iterator for Sanitize<'a, I> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.inner.next()?;
+        if self.allow_raw_html {
+            return Some(event);
+        }
+
+        match event {
+            Event::Html(html) => {
+                // A tag may be split across the lines of a HTML block
+                let mut html = html.into_string();
+                while let Some(Event::Html(next)) = self.inner.peek() {
+                    html.push_str(next);
+                    self.inner.next();
+                }
+                Some(Event::Html(CowStr::from(sanitize_html(&html))))
+            }
+            Event::InlineHtml(html) => Some(Event::InlineHtml(CowStr::from(sanitize_html(&html)))),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if !is_safe_url(&dest_url) => Some(Event::Start(Tag::Link {
+                link_type,
+                dest_url: CowStr::Borrowed(""),
+                title,
+                id,
+            })),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if !is_safe_url(&dest_url) => Some(Event::Start(Tag::Image {
+                link_type,
+                dest_url: CowStr::Borrowed(""),
+                title,
+                id,
+            })),
+            event => Some(event),
+        }
+    }
+}
+
+/// Sanitize a fragment of raw HTML
+pub(super) fn sanitize_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = match comment.find("-->") {
+                Some(end) => &comment[end + 3..],
+                None => "",
+            };
+            continue;
+        }
+
+        match parse_tag(rest) {
+            Some((tag, len)) if ALLOWED_TAGS.contains(&tag.name.as_str()) => {
+                write_tag(&mut out, &tag);
+                rest = &rest[len..];
+            }
+            Some((_, len)) => {
+                escape_html(&mut out, &rest[..len]).unwrap();
+                rest = &rest[len..];
+            }
+            None => {
+                out.push_str("&lt;");
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A parsed HTML tag
+struct HtmlTag<'a> {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(String, Option<&'a str>)>,
+}
+
+/// Parse the tag at the start of `html`, returning it and its length
+fn parse_tag(html: &str) -> Option<(HtmlTag<'_>, usize)> {
+    let bytes = html.as_bytes();
+    let mut pos = 1;
+
+    let closing = bytes.get(pos) == Some(&b'/');
+    if closing {
+        pos += 1;
+    }
+    if !bytes.get(pos)?.is_ascii_alphabetic() {
+        return None;
+    }
+
+    let name_start = pos;
+    while bytes.get(pos).is_some_and(|b| b.is_ascii_alphanumeric()) {
+        pos += 1;
+    }
+    let name = html[name_start..pos].to_ascii_lowercase();
+
+    let mut attributes = Vec::new();
+    let mut self_closing = false;
+    loop {
+        while bytes.get(pos)?.is_ascii_whitespace() {
+            pos += 1;
+        }
+        match bytes.get(pos)? {
+            b'>' => break,
+            b'/' => {
+                self_closing = true;
+                pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+        self_closing = false;
+
+        let attr_start = pos;
+        while !matches!(bytes.get(pos)?, b'>' | b'/' | b'=') && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let attr_name = html[attr_start..pos].to_ascii_lowercase();
+
+        while bytes.get(pos)?.is_ascii_whitespace() {
+            pos += 1;
+        }
+        let value = if bytes[pos] == b'=' {
+            pos += 1;
+            while bytes.get(pos)?.is_ascii_whitespace() {
+                pos += 1;
+            }
+            let (value, len) = match bytes.get(pos)? {
+                quote @ (b'"' | b'\'') => {
+                    let end = html[pos + 1..].find(*quote as char)?;
+                    (&html[pos + 1..pos + 1 + end], end + 2)
+                }
+                _ => {
+                    let end = html[pos..]
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .unwrap_or(html.len() - pos);
+                    (&html[pos..pos + end], end)
+                }
+            };
+            pos += len;
+            Some(value)
+        } else {
+            None
+        };
+        attributes.push((attr_name, value));
+    }
+
+    Some((
+        HtmlTag {
+            name,
+            closing,
+            self_closing,
+            attributes,
+        },
+        pos + 1,
+    ))
+}
+
+/// Write back an allowed tag with only its allowed attributes
+fn write_tag(out: &mut String, tag: &HtmlTag<'_>) {
+    out.push('<');
+    if tag.closing {
+        out.push('/');
+        out.push_str(&tag.name);
+        out.push('>');
+        return;
+    }
+    out.push_str(&tag.name);
+
+    for (name, value) in &tag.attributes {
+        let allowed = ALLOWED_ATTRIBUTES.contains(&name.as_str())
+            || ALLOWED_TAG_ATTRIBUTES.contains(&(tag.name.as_str(), name.as_str()));
+        if !allowed {
+            continue;
+        }
+        if URL_ATTRIBUTES.contains(&name.as_str()) && !value.is_some_and(is_safe_url) {
+            continue;
+        }
+
+        out.push(' ');
+        out.push_str(name);
+        if let Some(value) = value {
+            out.push_str("=\"");
+            // Keep the entities already present in the value
+            for c in value.chars() {
+                match c {
+                    '"' => out.push_str("&quot;"),
+                    '<' => out.push_str("&lt;"),
+                    '>' => out.push_str("&gt;"),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+        }
+    }
+
+    if tag.self_closing {
+        out.push_str(" /");
+    }
+    out.push('>');
+}
+
+/// Whether the url (or all the urls of a `srcset`) is relative or has an allowed scheme
+fn is_safe_url(url: &str) -> bool {
+    url.split(',').all(|candidate| {
+        let candidate: String = candidate
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect();
+        let scheme = candidate.split(['/', '?', '#']).next().unwrap_or_default();
+
+        // An encoded `:` (like `&#58;`) could hide a scheme
+        if scheme.contains('&') {
+            return false;
+        }
+        match scheme.split_once(':') {
+            Some((scheme, _)) => ALLOWED_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()),
+            None => true,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Parser};
+
+    fn render(markdown: &str) -> String {
+        let mut out = String::new();
+        html::push_html(&mut out, Sanitize::new(Parser::new(markdown), false));
+        out
+    }
+
+    #[test]
+    fn javascript_urls() {
+        for url in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " java\tscript:alert(1)",
+            "&#106;avascript:alert(1)",
+            "javascript&#58;alert(1)",
+            "&#x6A;avascript&colon;alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+        ] {
+            let html = sanitize_html(&format!("<a href=\"{}\">x</a>", url));
+            assert_eq!(html, "<a>x</a>", "{:?}", url);
+        }
+        assert_eq!(
+            render("[x](javascript:alert(1)) ![y](JAVASCRIPT:alert(1))"),
+            "<p><a href=\"\">x</a> <img src=\"\" alt=\"y\" /></p>\n"
+        );
+        assert_eq!(
+            sanitize_html(
+                "<a href=\"https://example.com/?a=1&amp;b=2\">x</a> <a href=\"../x.html#y\">y</a>"
+            ),
+            "<a href=\"https://example.com/?a=1&amp;b=2\">x</a> <a href=\"../x.html#y\">y</a>"
+        );
+    }
+
+    #[test]
+    fn unquoted_attributes() {
+        assert_eq!(
+            sanitize_html("<a href=javascript:alert(1) title=x>y</a>"),
+            "<a title=\"x\">y</a>"
+        );
+        assert_eq!(
+            sanitize_html("<img src=x onerror=alert(1) alt=a>"),
+            "<img src=\"x\" alt=\"a\">"
+        );
+        // No backslash escapes in HTML: `b"` is an attribute name
+        assert_eq!(
+            sanitize_html("<img src='a.png' class=\"a\\\"b\" title='\"><script>'/>"),
+            "<img src=\"a.png\" class=\"a\\\" title=\"&quot;&gt;&lt;script&gt;\" />"
+        );
+    }
+
+    #[test]
+    fn unknown_tags_and_attributes() {
+        assert_eq!(
+            sanitize_html("<script>alert(1)</script><p id=\"main\" style=\"x\" class=\"c\">a</p>"),
+            "&lt;script&gt;alert(1)&lt;/script&gt;<p class=\"c\">a</p>"
+        );
+        assert_eq!(sanitize_html("a < b <3"), "a &lt; b &lt;3");
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(sanitize_html("a<!-- <script> -->b<!---->c"), "abc");
+        assert_eq!(sanitize_html("a<!-- <img src=x onerror=alert(1)>"), "a");
+    }
+
+    #[test]
+    fn srcset() {
+        assert_eq!(
+            sanitize_html(
+                "<picture><source srcset=\"a.png 1x, b.png 2x\" media=\"(x)\"></picture>"
+            ),
+            "<picture><source srcset=\"a.png 1x, b.png 2x\" media=\"(x)\"></picture>"
+        );
+        assert_eq!(
+            sanitize_html("<source srcset=\"a.png 1x, javascript:alert(1) 2x\">"),
+            "<source>"
+        );
+    }
+
+    #[test]
+    fn split_across_events() {
+        // A HTML block is given line by line
+        assert_eq!(
+            render("<div\nclass=\"a\"\nonclick=\"alert(1)\">\n<a href=\"javascript:alert(1)\">x</a>\n</div>\n"),
+            "<div class=\"a\">\n<a>x</a>\n</div>\n"
+        );
+        assert_eq!(
+            render("<div>\n<!--\n<script>alert(1)</script>\n-->\n</div>\n"),
+            "<div>\n\n</div>\n"
+        );
+        assert_eq!(
+            render("Some <img\nsrc=\"x\" onerror=\"alert(1)\"> text"),
+            "<p>Some <img src=\"x\"> text</p>\n"
+        );
+    }
+}
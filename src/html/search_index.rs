@@ -0,0 +1,158 @@
+//! Search index emitters, writing the collected item paths in several formats
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+use super::constants::*;
+use super::render::{ItemPath, SearchPreview};
+use crate::IndexFormat;
+
+/// An entry of the search index: the path of an item (to its page) and its preview
+pub(super) struct SearchEntry<'a> {
+    pub(super) path: &'a ItemPath,
+    pub(super) preview: &'a SearchPreview,
+}
+
+/// A format of the search index
+pub(super) trait IndexEmitter {
+    /// Name of the file of the index, in the directory of the crate
+    fn filename(&self) -> &'static str;
+
+    /// Serialize the entries of the index
+    fn emit(&self, entries: &[SearchEntry<'_>]) -> Result<Vec<u8>>;
+}
+
+/// Emitter of a format of the search index
+pub(super) fn emitter(format: IndexFormat) -> &'static dyn IndexEmitter {
+    match format {
+        IndexFormat::Js => &JsEmitter,
+        IndexFormat::Json => &JsonEmitter,
+        IndexFormat::Binary => &BinaryEmitter,
+    }
+}
+
+/// The javascript index loaded by `search.js`, declaring `INDEX` and `INDEX_PREVIEWS`
+struct JsEmitter;
+
+impl IndexEmitter for JsEmitter {
+    fn filename(&self) -> &'static str {
+        SEARCH_INDEX_JS
+    }
+
+    fn emit(&self, entries: &[SearchEntry<'_>]) -> Result<Vec<u8>> {
+        let mut search = String::new();
+
+        search.push_str("\n\nconst INDEX = JSON.parse('[");
+        for (ientry, entry) in entries.iter().enumerate() {
+            if ientry != 0 {
+                search.push(',');
+            }
+            search.push_str("{\"components\":[");
+            for (icomponent, component) in entry.path.0.iter().enumerate() {
+                if icomponent != 0 {
+                    search.push(',');
+                }
+                search.push_str("{\"name\":\"");
+                search.push_str(&component.name);
+                search.push_str("\",\"lower_case_name\":\"");
+                search.push_str(&component.name.to_ascii_lowercase());
+                search.push_str("\",\"kind\":\"");
+                search.push_str(component.kind);
+                search.push_str("\"}");
+            }
+
+            let last = entry.path.0.last().unwrap();
+            search.push_str("],\"filepath\":\"");
+            search.push_str(&format!("{}", last.filepath.display()));
+            search.push_str("\"}");
+        }
+        search.push_str("]');\n");
+        search.push_str("\nconst INDEX_PREVIEWS = ");
+        search.push_str(
+            &serde_json::to_string(&entries.iter().map(|e| e.preview).collect::<Vec<_>>())
+                .context("unable to serialize the search previews")?,
+        );
+        search.push_str(";\n");
+
+        Ok(search.into_bytes())
+    }
+}
+
+/// A plain JSON index, for the external tools
+struct JsonEmitter;
+
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    path: Vec<&'a str>,
+    kind: &'static str,
+    filepath: String,
+    #[serde(flatten)]
+    preview: &'a SearchPreview,
+}
+
+impl IndexEmitter for JsonEmitter {
+    fn filename(&self) -> &'static str {
+        SEARCH_INDEX_JSON
+    }
+
+    fn emit(&self, entries: &[SearchEntry<'_>]) -> Result<Vec<u8>> {
+        let entries = entries
+            .iter()
+            .map(|entry| {
+                let last = entry.path.0.last().unwrap();
+                JsonEntry {
+                    path: entry.path.0.iter().map(|c| c.name.as_str()).collect(),
+                    kind: last.kind,
+                    filepath: last.filepath.display().to_string(),
+                    preview: entry.preview,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_vec(&entries).context("unable to serialize the json search index")
+    }
+}
+
+/// A compact binary index: the `RDIX` magic, the version and the number of entries as
+/// little-endian `u32`, then for each entry its number of components, each component
+/// (name and kind) and its file path, the strings being prefixed by their `u32` length
+struct BinaryEmitter;
+
+impl BinaryEmitter {
+    const MAGIC: &'static [u8; 4] = b"RDIX";
+    const VERSION: u32 = 1;
+
+    fn push_str(buf: &mut Vec<u8>, s: &str) -> Result<()> {
+        let len = u32::try_from(s.len()).context("string too long for the binary index")?;
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl IndexEmitter for BinaryEmitter {
+    fn filename(&self) -> &'static str {
+        SEARCH_INDEX_BIN
+    }
+
+    fn emit(&self, entries: &[SearchEntry<'_>]) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(Self::MAGIC);
+        buf.extend_from_slice(&Self::VERSION.to_le_bytes());
+        buf.extend_from_slice(&u32::try_from(entries.len())?.to_le_bytes());
+
+        for entry in entries {
+            buf.extend_from_slice(&u32::try_from(entry.path.0.len())?.to_le_bytes());
+            for component in &entry.path.0 {
+                Self::push_str(&mut buf, &component.name)?;
+                Self::push_str(&mut buf, component.kind)?;
+            }
+
+            let last = entry.path.0.last().unwrap();
+            Self::push_str(&mut buf, &last.filepath.display().to_string())?;
+        }
+
+        Ok(buf)
+    }
+}
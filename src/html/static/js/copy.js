@@ -0,0 +1,15 @@
+// Copy the code of the blocks (definitions and examples) with their "copy" button
+
+document.addEventListener("click", (e) => {
+  let button = e.target.closest(".rd-copy");
+  if (button === null) {
+    return;
+  }
+
+  let code = button.parentElement.querySelector("code");
+  navigator.clipboard.writeText(code.innerText).then(() => {
+    let icon = button.querySelector("i");
+    icon.classList.replace("bi-clipboard", "bi-check2");
+    setTimeout(() => icon.classList.replace("bi-check2", "bi-clipboard"), 2000);
+  });
+});
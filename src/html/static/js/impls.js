@@ -0,0 +1,55 @@
+// Bodies of the collapsed impls of pages with a lot of impls are deferred to a side
+// script (`<page>.impls.js`), only loaded when one of them is expanded
+
+const rdLazyImplsScripts = new Map();
+
+function rdLazyImplsLoad(src) {
+  if (!rdLazyImplsScripts.has(src)) {
+    rdLazyImplsScripts.set(src, new Promise((resolve, reject) => {
+      let script = document.createElement("script");
+      script.src = src;
+      script.onload = resolve;
+      script.onerror = reject;
+      document.body.appendChild(script);
+    }));
+  }
+  return rdLazyImplsScripts.get(src);
+}
+
+// Called by the side scripts with the bodies of the impls, by impl id
+function rdLazyImplsLoaded(bodies) {
+  for (const [id, body] of Object.entries(bodies)) {
+    let details = document.getElementById(id)?.querySelector("details[data-rd-lazy-src]");
+    if (details) {
+      details.removeAttribute("data-rd-lazy-src");
+      details.insertAdjacentHTML("beforeend", body);
+    }
+  }
+}
+
+document.addEventListener("toggle", (e) => {
+  if (e.target.open && e.target.dataset.rdLazySrc !== undefined) {
+    rdLazyImplsLoad(e.target.dataset.rdLazySrc);
+  }
+}, true);
+
+// Expand the (collapsed) sections and impls containing the target of the url
+function rdOpenTarget() {
+  let target = document.getElementById(decodeURIComponent(window.location.hash.slice(1)));
+  if (target !== null) {
+    for (let details = target.closest("details"); details !== null; details = details.parentElement.closest("details")) {
+      details.open = true;
+    }
+    target.scrollIntoView();
+  }
+  return target;
+}
+
+window.addEventListener("hashchange", rdOpenTarget);
+
+// The target of the url may be inside a deferred body
+if (window.location.hash !== "" && rdOpenTarget() === null) {
+  let sources = new Set([...document.querySelectorAll("details[data-rd-lazy-src]")]
+    .map((details) => details.dataset.rdLazySrc));
+  Promise.all([...sources].map(rdLazyImplsLoad)).then(rdOpenTarget);
+}
@@ -0,0 +1,21 @@
+// Redirect the fragments in the formats of the older versions of rd (or of rustdoc) to the
+// current ids of the page, so that the deep links keep working after an upgrade
+
+const rdRedirectPage = document.currentScript.dataset.rdPage;
+
+function rdRedirectAnchor() {
+  let hash = decodeURIComponent(window.location.hash.slice(1));
+  if (hash === "" || document.getElementById(hash) !== null || typeof RD_ANCHOR_ALIASES === "undefined") {
+    return;
+  }
+
+  let id = RD_ANCHOR_ALIASES[rdRedirectPage]?.[hash];
+  if (id !== undefined) {
+    history.replaceState(null, "", "#" + encodeURIComponent(id));
+  }
+}
+
+// Registered before the other listeners, that will then see the redirected fragment
+window.addEventListener("hashchange", rdRedirectAnchor);
+
+rdRedirectAnchor();
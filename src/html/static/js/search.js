@@ -3,11 +3,14 @@ const MAX_SEARCH_ELEMENTS = 25;
 const rdSearchMenu = document.getElementById('rd-search-menu');
 const rdSearchInput = document.getElementById('rd-search-input');
 const rdSearchItems = document.getElementById('rd-search-items');
+// Only on the search results page, listing all the results rather than the menu
+const rdSearchResults = document.getElementById('search-results');
 const rdSearchForm = document.getElementById('rd-search-form');
 
 rdSearchInput.addEventListener('input', rdSearchInputChange);
 rdSearchForm.addEventListener('focusout', rdSearchFormFocusOut);
 rdSearchForm.addEventListener('focusin', rdSearchFormFocusIn);
+rdSearchInput.addEventListener('keydown', rdSearchInputKeyDown);
 
 document.onkeyup = (e) => {
   if (e.ctrlKey && e.key === "/") {
@@ -26,73 +29,86 @@ function rdSearchFormFocusIn(e) {
   rdSearchInputFocused = true;
 }
 
-function rdSearchInputChange(e) {
-  if (rdSearchInput.value !== "") {
-    rdSearchItemsClear("block");
+// Indexes of the items matching the query, the best ones first
+function rdSearchMatches(query, versions) {
+  let inputValues = query.toLowerCase().split("::").map((v) => v.trim());
 
-    let inputValues = rdSearchInput.value.toLowerCase().split("::");    
-
-    // Original from https://stackoverflow.com/a/34152244 : CC BY-SA 4.0
-    function rdHasSubArray(master, sub) {
-        return sub.every((i => v => i = rdIncludesIndexOf(master, v, i) + 1)(0));
+  let hidePrivate = document.documentElement.classList.contains("rd-hide-private");
+  let results = [];
+  for (const [index, item] of INDEX.entries()) {
+    if (hidePrivate && INDEX_PREVIEWS[index].visibility !== undefined) {
+      continue;
     }
-
-    function rdIncludesIndexOf(array, who, starti) {
-      for (var i = starti; i < array.length; i++) {
-        if (array[i].lower_case_name.includes(who) === true) {
-          return (i);
-        }
-      }
-      return (-1);
+    let score = rdSearchScore(item.components, inputValues);
+    if (score === null && rdSearchAliasMatches(index, query)) {
+      score = RD_SCORE_ALIAS;
     }
+    if (score !== null && rdSearchVersionMatches(index, versions)) {
+      results.push([score, index]);
+    }
+  }
+  // `sort` is stable, equally scored items stay in the order of the index
+  results.sort((a, b) => b[0] - a[0]);
+  return results.map(([_, index]) => index);
+}
 
-    let matches = 0;
-    for (const item of INDEX) {
-      if (rdHasSubArray(item.components, inputValues) === true) {
-        var block = document.createElement("a");
-        block.classList.add("rd-search-item");
-        
-        for (const [index, c] of item.components.entries()) {
-          var span = document.createElement("span");
-          span.classList.add(c.kind);
-          span.innerText = c.name;
-
-          block.appendChild(span);
-          if (index + 1 != item.components.length) {
-            block.innerText += "::";
-          }
-        }
+function rdSearchInputChange(e) {
+  if (rdSearchResults) {
+    rdSearchPageShow(1);
+  } else if (rdSearchInput.value !== "") {
+    rdSearchItemsClear("block");
 
-        var mod_name = item.filepath.split('/')[0];
-        var v = window.location.pathname.split('/');
+    let [query, versions] = rdSearchVersionFilters(rdSearchInput.value);
+    let results = rdSearchMatches(query, versions);
 
-        var before = "";
-        for (var i = v.length - 1; i >= 0; i--) {
-          if (v[i] == mod_name) {
-            break;
-          }
-          before += "../";
-        }
+    let itemsResults = document.createElement("div");
+    let matches = 0;
+    for (const index of results.slice(0, MAX_SEARCH_ELEMENTS)) {
+      itemsResults.appendChild(rdSearchItemBlock(index));
+      matches += 1;
+    }
+    rdSearchNoResult(itemsResults, matches);
 
-        block.href = before + item.filepath;
-        rdSearchItems.appendChild(block);
+    if (typeof DOCS_INDEX !== "undefined") {
+      let docsResults = document.createElement("div");
+      let docsMatches = 0;
+      for (const page of rdSearchDocs(query).filter((page) => rdSearchVersionMatches(page, versions))) {
+        docsResults.appendChild(rdSearchItemBlock(page));
 
-        matches += 1;
-        if (matches == MAX_SEARCH_ELEMENTS) {
+        docsMatches += 1;
+        if (docsMatches == MAX_SEARCH_ELEMENTS) {
           break;
         }
       }
-    }
-
-    if (matches == 0) {
-      var block = document.createElement("span");
-      block.classList.add("ps-2");
-      block.classList.add("pe-2");
+      rdSearchNoResult(docsResults, docsMatches);
+      docsResults.style.display = "none";
 
-      block.innerText = "Sorry, no result for your query.";
+      let tabs = document.createElement("div");
+      tabs.classList.add("rd-search-tabs");
+      for (const [name, results, count] of [["Items", itemsResults, matches], ["In docs", docsResults, docsMatches]]) {
+        var tab = document.createElement("button");
+        tab.type = "button";
+        tab.classList.add("btn", "btn-sm", "btn-link");
+        tab.innerText = name + " (" + count + ")";
+        tab.addEventListener("click", (e) => {
+          for (const other of [itemsResults, docsResults]) {
+            other.style.display = other === results ? "block" : "none";
+          }
+          for (const other of tabs.children) {
+            other.classList.toggle("active", other === e.currentTarget);
+          }
+          rdSearchSelect(results.querySelector(".rd-search-item"));
+        });
+        tabs.appendChild(tab);
+      }
+      tabs.firstChild.classList.add("active");
 
-      rdSearchItems.appendChild(block);
+      rdSearchItems.appendChild(tabs);
+      rdSearchItems.appendChild(rdSearchBody([itemsResults, docsResults]));
+    } else {
+      rdSearchItems.appendChild(rdSearchBody([itemsResults]));
     }
+    rdSearchSelect(itemsResults.querySelector(".rd-search-item"));
 
     var windowUrl = new URL(window.location);
     windowUrl.searchParams.set('search', rdSearchInput.value);
@@ -106,6 +122,274 @@ function rdSearchInputChange(e) {
   }
 }
 
+function rdSearchItemBlock(index) {
+  var item = INDEX[index];
+  var block = document.createElement("a");
+  block.classList.add("rd-search-item");
+  block.dataset.index = index;
+  block.setAttribute("role", "option");
+  let summary = typeof INDEX_PREVIEWS !== "undefined" ? INDEX_PREVIEWS[index]?.summary : null;
+  if (summary) {
+    block.title = summary;
+  }
+  block.addEventListener("mouseenter", (e) => rdSearchSelect(e.currentTarget));
+
+  for (const [index, c] of item.components.entries()) {
+    var span = document.createElement("span");
+    span.classList.add(c.kind);
+    span.innerText = c.name;
+
+    block.appendChild(span);
+    if (index + 1 != item.components.length) {
+      block.innerText += "::";
+    }
+  }
+
+  var icon = document.createElement("i");
+  icon.classList.add("rd-kind");
+  icon.dataset.kind = item.components[item.components.length - 1].kind;
+  icon.setAttribute("aria-hidden", "true");
+  block.prepend(icon);
+
+  var mod_name = item.filepath.split('/')[0];
+  var v = window.location.pathname.split('/');
+
+  var before = "";
+  for (var i = v.length - 1; i >= 0; i--) {
+    if (v[i] == mod_name) {
+      break;
+    }
+    before += "../";
+  }
+
+  block.href = before + item.filepath;
+  return block;
+}
+
+// The results next to the preview pane of the selected one
+function rdSearchBody(results) {
+  let body = document.createElement("div");
+  body.classList.add("rd-search-body");
+
+  let lists = document.createElement("div");
+  lists.classList.add("rd-search-lists");
+  results.forEach((r) => lists.appendChild(r));
+  body.appendChild(lists);
+
+  let preview = document.createElement("div");
+  preview.classList.add("rd-search-preview");
+  preview.id = "rd-search-preview";
+  body.appendChild(preview);
+
+  return body;
+}
+
+// Make `block` the selected result and show its preview
+function rdSearchSelect(block) {
+  for (const active of rdSearchItems.querySelectorAll(".rd-search-item.active")) {
+    active.classList.remove("active");
+    active.removeAttribute("aria-selected");
+  }
+
+  let preview = document.getElementById("rd-search-preview");
+  if (preview === null) {
+    return;
+  }
+  preview.replaceChildren();
+
+  if (block === null) {
+    return;
+  }
+  block.classList.add("active");
+  block.setAttribute("aria-selected", "true");
+  block.scrollIntoView({ block: "nearest" });
+
+  let title = block.cloneNode(true);
+  title.classList.remove("rd-search-item", "active");
+  title.classList.add("rd-search-preview-title");
+  title.removeAttribute("role");
+  title.removeAttribute("aria-selected");
+  preview.appendChild(title);
+
+  let data = typeof INDEX_PREVIEWS !== "undefined" ? INDEX_PREVIEWS[block.dataset.index] : null;
+  if (data && data.signature) {
+    let pre = document.createElement("pre");
+    let code = document.createElement("code");
+    code.innerText = data.signature;
+    pre.appendChild(code);
+    preview.appendChild(pre);
+  }
+
+  let summary = document.createElement("p");
+  if (data && data.summary) {
+    summary.innerText = data.summary;
+  } else {
+    summary.classList.add("text-muted");
+    summary.innerText = "No documentation.";
+  }
+  preview.appendChild(summary);
+}
+
+// Arrow keys move the selection in the visible results, enter follows it
+function rdSearchInputKeyDown(e) {
+  let blocks = [...rdSearchItems.querySelectorAll(".rd-search-item")]
+    .filter((b) => b.offsetParent !== null);
+  let current = blocks.findIndex((b) => b.classList.contains("active"));
+
+  if (e.key === "ArrowDown" || e.key === "ArrowUp") {
+    if (blocks.length != 0) {
+      let next = e.key === "ArrowDown" ? current + 1 : current - 1;
+      rdSearchSelect(blocks[(next + blocks.length) % blocks.length]);
+    }
+    e.preventDefault();
+  } else if (e.key === "Enter") {
+    if (current != -1) {
+      window.location.href = blocks[current].href;
+    }
+    e.preventDefault();
+  }
+}
+
+function rdSearchNoResult(results, matches) {
+  if (matches == 0) {
+    var block = document.createElement("span");
+    block.classList.add("ps-2");
+    block.classList.add("pe-2");
+
+    block.innerText = "Sorry, no result for your query.";
+
+    results.appendChild(block);
+  }
+}
+
+// Extract the `since:x.y` (added in x.y or later) and `until:x.y` (available in x.y)
+// filters from the query
+function rdSearchVersionFilters(input) {
+  let versions = {};
+  let query = input.replace(/\b(since|until):(\S*)/g, (_, filter, version) => {
+    versions[filter] = version;
+    return "";
+  });
+  return [query.trim(), versions];
+}
+
+function rdCompareVersions(x, y) {
+  let xs = x.split(".").map((n) => parseInt(n) || 0);
+  let ys = y.split(".").map((n) => parseInt(n) || 0);
+  for (let i = 0; i < Math.max(xs.length, ys.length); i++) {
+    if ((xs[i] || 0) != (ys[i] || 0)) {
+      return (xs[i] || 0) - (ys[i] || 0);
+    }
+  }
+  return 0;
+}
+
+const RD_SCORE_ALIAS = 90;
+
+// Score of the path `components` for the query segments (`io::read` gives `["io", "read"]`),
+// higher is better, or `null` when they aren't all found in order in the path: the last
+// segment matching the name of the item counts the most, then exact segments, segments
+// matching consecutive components and finally shorter paths
+function rdSearchScore(components, segments) {
+  let positions = [];
+  let start = 0;
+  for (const segment of segments) {
+    let position = components.findIndex((c, i) => i >= start && c.lower_case_name.includes(segment));
+    if (position == -1) {
+      return null;
+    }
+    positions.push(position);
+    start = position + 1;
+  }
+
+  let score = 0;
+  let name = components[components.length - 1].lower_case_name;
+  let last = segments[segments.length - 1];
+  if (name === last) {
+    score += 100;
+  } else if (name.startsWith(last)) {
+    score += 60;
+  } else if (name.includes(last)) {
+    score += 30;
+  }
+
+  for (const [i, position] of positions.entries()) {
+    if (i + 1 != positions.length && components[position].lower_case_name === segments[i]) {
+      score += 10;
+    }
+    if (i != 0 && positions[i - 1] + 1 == position) {
+      score += 5;
+    }
+  }
+  return score - components.length;
+}
+
+// Whether the query is one of the `#[doc(alias)]` of the item
+function rdSearchAliasMatches(index, query) {
+  let aliases = typeof INDEX_PREVIEWS !== "undefined" ? INDEX_PREVIEWS[index]?.aliases : null;
+  return aliases?.some((alias) => alias.toLowerCase() === query.toLowerCase()) === true;
+}
+
+// Items without a known version are considered as always available
+function rdSearchVersionMatches(index, versions) {
+  let since = typeof INDEX_PREVIEWS !== "undefined" ? INDEX_PREVIEWS[index]?.since : null;
+  if (versions.since !== undefined && (!since || rdCompareVersions(since, versions.since) < 0)) {
+    return false;
+  }
+  if (versions.until !== undefined && since && rdCompareVersions(since, versions.until) > 0) {
+    return false;
+  }
+  return true;
+}
+
+// Must be kept in sync with `fulltext.rs`
+const RD_STOP_WORDS = new Set([
+  "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+  "its", "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+  "they", "this", "to", "was", "will", "with",
+]);
+
+// Must be kept in sync with `fulltext.rs`
+function rdStem(word) {
+  if (word.length > 4 && word.endsWith("ies")) {
+    return word.slice(0, -3) + "y";
+  } else if (word.length > 5 && word.endsWith("ing")) {
+    return word.slice(0, -3);
+  } else if (word.length > 4 && word.endsWith("ed")) {
+    return word.slice(0, -2);
+  } else if (word.length > 3 && word.endsWith("s") && !word.endsWith("ss")) {
+    return word.slice(0, -1);
+  }
+  return word;
+}
+
+// Pages whose documentation contains all the terms of the query (the last one as a prefix)
+function rdSearchDocs(query) {
+  let words = query.split(/[^\p{L}\p{N}]+/u)
+    .filter((w) => w.length > 1)
+    .map((w) => w.toLowerCase())
+    .filter((w) => !RD_STOP_WORDS.has(w));
+  let terms = words.map(rdStem);
+  if (terms.length == 0) {
+    return [];
+  }
+
+  let pages = null;
+  for (const [index, term] of terms.entries()) {
+    let termPages = new Set(DOCS_INDEX[term] || []);
+    if (index + 1 == terms.length) {
+      let prefix = words[words.length - 1];
+      for (const [key, keyPages] of Object.entries(DOCS_INDEX)) {
+        if (key.startsWith(prefix)) {
+          keyPages.forEach((page) => termPages.add(page));
+        }
+      }
+    }
+    pages = pages === null ? termPages : new Set([...pages].filter((page) => termPages.has(page)));
+  }
+  return [...pages].sort((a, b) => a - b);
+}
+
 function rdHistoryReplace(url, title) {
   window.history.replaceState({}, title, url);
   document.title = title;
@@ -123,9 +407,104 @@ function rdSearchItemsClear(display) {
   }
 }
 
+const RD_RESULTS_PER_PAGE = 50;
+
+// Kind of the items listed on the search results page, all of them when `null`
+var rdSearchKind = null;
+
+// List the page `page` of the results on the search results page, along with the number of
+// results of each kind and the links to the other pages, and keep them in its url
+function rdSearchPageShow(page) {
+  let [query, versions] = rdSearchVersionFilters(rdSearchInput.value);
+  let results = query !== "" ? rdSearchMatches(query, versions) : [];
+
+  let kinds = new Map();
+  for (const index of results) {
+    let kind = rdSearchItemKind(index);
+    kinds.set(kind, (kinds.get(kind) || 0) + 1);
+  }
+  if (rdSearchKind !== null && !kinds.has(rdSearchKind)) {
+    rdSearchKind = null;
+  }
+  let filtered = rdSearchKind === null
+    ? results
+    : results.filter((index) => rdSearchItemKind(index) === rdSearchKind);
+  let pages = Math.max(1, Math.ceil(filtered.length / RD_RESULTS_PER_PAGE));
+  page = Math.min(Math.max(page, 1), pages);
+
+  let filters = document.getElementById("rd-search-kinds");
+  filters.replaceChildren();
+  if (results.length != 0) {
+    for (const [kind, count] of [[null, results.length], ...kinds.entries()]) {
+      let filter = document.createElement("button");
+      filter.type = "button";
+      filter.classList.add("btn", "btn-sm", "btn-link");
+      filter.classList.toggle("active", kind === rdSearchKind);
+      filter.innerText = (kind === null ? "All" : kind) + " (" + count + ")";
+      filter.addEventListener("click", () => {
+        rdSearchKind = kind;
+        rdSearchPageShow(1);
+      });
+      filters.appendChild(filter);
+    }
+  }
+
+  rdSearchResults.replaceChildren();
+  let start = (page - 1) * RD_RESULTS_PER_PAGE;
+  for (const index of filtered.slice(start, start + RD_RESULTS_PER_PAGE)) {
+    rdSearchResults.appendChild(rdSearchItemBlock(index));
+  }
+  if (query !== "") {
+    rdSearchNoResult(rdSearchResults, filtered.length);
+  }
+
+  let navigation = document.getElementById("rd-search-pages");
+  navigation.replaceChildren();
+  if (pages > 1) {
+    for (const [name, target] of [["Previous", page - 1], ["Next", page + 1]]) {
+      let link = document.createElement("button");
+      link.type = "button";
+      link.classList.add("btn", "btn-sm", "btn-link");
+      link.innerText = name;
+      link.disabled = target < 1 || target > pages;
+      link.addEventListener("click", () => {
+        rdSearchPageShow(target);
+        window.scrollTo(0, 0);
+      });
+      navigation.appendChild(link);
+    }
+    let position = document.createElement("span");
+    position.classList.add("text-muted", "ps-2");
+    position.innerText = "Page " + page + " of " + pages;
+    navigation.insertBefore(position, navigation.lastChild);
+  }
+
+  var windowUrl = new URL(window.location);
+  windowUrl.searchParams.delete("search");
+  for (const [param, value, unset] of [["q", rdSearchInput.value, ""], ["kind", rdSearchKind, null], ["page", page, 1]]) {
+    if (value === unset) {
+      windowUrl.searchParams.delete(param);
+    } else {
+      windowUrl.searchParams.set(param, value);
+    }
+  }
+  rdHistoryReplace(windowUrl, query !== "" ? "Result for " + rdSearchInput.value + " - Rust" : originalWindowTitle);
+}
+
+function rdSearchItemKind(index) {
+  let components = INDEX[index].components;
+  return components[components.length - 1].kind;
+}
+
 const originalWindowTitle = document.title;
 const windowSearchParams = new URLSearchParams(window.location.search);
-if (windowSearchParams.get("search") !== null) {
+if (rdSearchResults) {
+  // `?search=` being the query of the other pages
+  rdSearchInput.value = windowSearchParams.get("q") ?? windowSearchParams.get("search") ?? "";
+  rdSearchKind = windowSearchParams.get("kind");
+  rdSearchPageShow(parseInt(windowSearchParams.get("page")) || 1);
+  rdSearchInput.focus();
+} else if (windowSearchParams.get("search") !== null) {
   rdSearchInputValue(windowSearchParams.get("search"));
   rdSearchInput.focus();
 }
\ No newline at end of file
@@ -0,0 +1,58 @@
+const RD_SETTINGS_KEY = "rd-settings";
+
+const rdOptionsForm = document.getElementById('rd-options-form');
+
+function rdSettingsLoad() {
+  try {
+    return JSON.parse(window.localStorage.getItem(RD_SETTINGS_KEY)) || {};
+  } catch (e) {
+    return {};
+  }
+}
+
+function rdSettingsSave(settings) {
+  try {
+    window.localStorage.setItem(RD_SETTINGS_KEY, JSON.stringify(settings));
+  } catch (e) {
+    // Storage may be unavailable (private browsing, file://, ...)
+  }
+}
+
+function rdSettingsApply(settings) {
+  if (settings.collapseImpls !== undefined) {
+    for (const details of document.querySelectorAll("details.rd-impl")) {
+      details.open = !settings.collapseImpls;
+    }
+  }
+
+  document.documentElement.classList.toggle("rd-hide-source-links", settings.sourceLinks === false);
+  document.documentElement.classList.toggle("rd-wrap-signatures", settings.wrapSignatures === true);
+  document.documentElement.classList.toggle("rd-hide-private", settings.privateItems === false);
+
+  if (settings.theme === "light" || settings.theme === "dark") {
+    darkmode.setDarkMode(settings.theme === "dark");
+  } else if (settings.theme === "auto") {
+    darkmode.resetDarkMode();
+  }
+}
+
+function rdSettingsChange(e) {
+  let settings = rdSettingsLoad();
+  settings[e.target.name] = e.target.type === "checkbox" ? e.target.checked : e.target.value;
+
+  rdSettingsSave(settings);
+  rdSettingsApply(settings);
+}
+
+const rdSettings = rdSettingsLoad();
+for (const input of rdOptionsForm.elements) {
+  if (rdSettings[input.name] !== undefined) {
+    if (input.type === "checkbox") {
+      input.checked = rdSettings[input.name];
+    } else {
+      input.value = rdSettings[input.name];
+    }
+  }
+  input.addEventListener('change', rdSettingsChange);
+}
+rdSettingsApply(rdSettings);
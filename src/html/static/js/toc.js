@@ -0,0 +1,78 @@
+// Scroll-spy of the "On this page" table of contents: highlight the link of the section
+// currently visible and expand the groups containing it
+
+const rdToc = document.getElementById("TableOfContents");
+
+// Links of the toc by the id of their target
+const rdTocLinks = new Map();
+
+if (rdToc !== null) {
+  for (const link of rdToc.querySelectorAll("a[href^='#']")) {
+    let id = decodeURIComponent(link.getAttribute("href").slice(1));
+    if (id && !rdTocLinks.has(id)) {
+      rdTocLinks.set(id, link);
+    }
+  }
+}
+
+let rdTocActive = null;
+
+function rdTocSetActive(link) {
+  if (link === rdTocActive) {
+    return;
+  }
+  if (rdTocActive !== null) {
+    rdTocActive.classList.remove("active");
+    rdTocActive.removeAttribute("aria-current");
+  }
+  rdTocActive = link;
+  link.classList.add("active");
+  link.setAttribute("aria-current", "location");
+
+  for (let group = link.closest("ul.collapse"); group !== null; group = group.parentElement.closest("ul.collapse")) {
+    if (!group.classList.contains("show")) {
+      bootstrap.Collapse.getOrCreateInstance(group, { toggle: false }).show();
+    }
+  }
+}
+
+// Targets currently intersecting the viewport
+const rdTocVisible = new Set();
+
+if (rdTocLinks.size !== 0 && "IntersectionObserver" in window) {
+  const observer = new IntersectionObserver((entries) => {
+    for (const entry of entries) {
+      if (entry.isIntersecting) {
+        rdTocVisible.add(entry.target);
+      } else {
+        rdTocVisible.delete(entry.target);
+      }
+    }
+
+    // The first visible target in the document order is the current section
+    let current = null;
+    for (const target of rdTocVisible) {
+      if (current === null || current.compareDocumentPosition(target) & Node.DOCUMENT_POSITION_PRECEDING) {
+        current = target;
+      }
+    }
+    if (current !== null) {
+      rdTocSetActive(rdTocLinks.get(current.id));
+    }
+  }, { rootMargin: "-64px 0px -40% 0px" });
+
+  for (const id of rdTocLinks.keys()) {
+    let target = document.getElementById(id);
+    if (target !== null) {
+      observer.observe(target);
+    }
+  }
+}
+
+// The toggles are links, activated by Enter; also activate them with Space like buttons
+rdToc?.addEventListener("keydown", (e) => {
+  if (e.key === " " && e.target.classList.contains("rd-btn-toc")) {
+    e.preventDefault();
+    e.target.click();
+  }
+});
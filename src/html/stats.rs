@@ -0,0 +1,102 @@
+//! Rendering statistics (`--stats`), to guide performance work and spot pathological pages
+
+use anyhow::{Context as _, Result};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Number of pages listed in the "largest pages" part of the report
+const LARGEST_PAGES: usize = 10;
+
+/// Statistics collected while rendering a crate
+pub(super) struct RenderStats {
+    start: Instant,
+    /// Number of items rendered, by kind
+    items: RefCell<BTreeMap<&'static str, usize>>,
+    /// Number of pretty-printed tokens rendered, by page
+    tokens: RefCell<HashMap<PathBuf, usize>>,
+}
+
+impl Default for RenderStats {
+    fn default() -> Self {
+        RenderStats {
+            start: Instant::now(),
+            items: Default::default(),
+            tokens: Default::default(),
+        }
+    }
+}
+
+impl RenderStats {
+    /// Count an item of this kind
+    pub(super) fn add_item(&self, kind: &'static str) {
+        *self.items.borrow_mut().entry(kind).or_default() += 1;
+    }
+
+    /// Count the pretty-printed tokens rendered on a page
+    pub(super) fn add_tokens(&self, page: &Path, count: usize) {
+        *self
+            .tokens
+            .borrow_mut()
+            .entry(page.to_path_buf())
+            .or_default() += count;
+    }
+
+    /// Write the report, the size of the pages being read from the output directory
+    pub(super) fn report<'a>(
+        &self,
+        writer: &mut impl Write,
+        krate_name: &str,
+        output: &Path,
+        pages: impl Iterator<Item = &'a PathBuf>,
+    ) -> Result<()> {
+        let tokens = self.tokens.borrow();
+        // The items rendered on the page of their module share its file
+        let pages = pages.collect::<BTreeSet<_>>();
+        let mut sizes = pages
+            .into_iter()
+            .map(|page| {
+                let path = output.join(page);
+                let metadata = std::fs::metadata(&path)
+                    .with_context(|| format!("unable to get the metadata of {:?}", path))?;
+                Ok((metadata.len(), tokens.get(page).copied().unwrap_or(0), page))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        sizes.sort_by(|x, y| y.0.cmp(&x.0).then_with(|| x.2.cmp(y.2)));
+
+        writeln!(writer, "statistics of {}", krate_name)?;
+        writeln!(writer, "  rendered in {:.2?}", self.start.elapsed())?;
+
+        let items = self.items.borrow();
+        writeln!(writer, "  items: {}", items.values().sum::<usize>())?;
+        for (kind, count) in items.iter() {
+            writeln!(writer, "    {:<16} {:>8}", kind, count)?;
+        }
+
+        writeln!(writer, "  pages: {}", sizes.len())?;
+        writeln!(
+            writer,
+            "  bytes written: {}",
+            sizes.iter().map(|(size, _, _)| size).sum::<u64>()
+        )?;
+        writeln!(
+            writer,
+            "  pretty-printed tokens: {}",
+            tokens.values().sum::<usize>()
+        )?;
+
+        writeln!(writer, "  largest pages:")?;
+        for (size, tokens, page) in sizes.iter().take(LARGEST_PAGES) {
+            writeln!(
+                writer,
+                "    {:>10} bytes {:>8} tokens  {}",
+                size,
+                tokens,
+                page.display()
+            )?;
+        }
+        Ok(())
+    }
+}
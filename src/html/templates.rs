@@ -1,17 +1,63 @@
 //! HTML templates
 
+use std::borrow::Cow;
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use super::constants::*;
+use super::escape::escape_html;
+use super::id::Id as HtmlId;
 use super::markdown::MarkdownWithToc;
+use super::plugins::PluginSection;
 use super::render::{GlobalContext, PageContext, TocSection};
 use super::utils::*;
+use crate::metadata::CrateMetadata;
+use crate::MermaidSource;
 
 pub struct BodyInformations<'a> {
     page_title: String,
+    page_path: String,
     krate_name: &'a str,
     root_path: PathBuf,
+    livereload: Option<u16>,
+    /// Url of mermaid, with `--mermaid`
+    mermaid: Option<String>,
+    full_text_search: bool,
+    opensearch: bool,
+    guide: bool,
+    private_items: bool,
+    vendored: bool,
+    year: u32,
+    static_files: &'a StaticFileNames,
+}
+
+/// A documentation heading: level, name and id
+type TocHeading<'id> = (u32, String, &'id HtmlId);
+
+/// Split the headings into the top-level ones along with their sub-headings
+fn toc_nodes<'a, 'id>(
+    mut entries: &'a [TocHeading<'id>],
+) -> Vec<(&'a TocHeading<'id>, &'a [TocHeading<'id>])> {
+    let mut nodes = Vec::new();
+    while let Some((entry, rest)) = entries.split_first() {
+        let children = rest
+            .iter()
+            .position(|(level, ..)| *level <= entry.0)
+            .unwrap_or(rest.len());
+        nodes.push((entry, &rest[..children]));
+        entries = &rest[children..];
+    }
+    nodes
+}
+
+/// Id of a section of the page of an item, prefixed by the anchor of the item when it's
+/// rendered on the page of its module (`--module-single-page`)
+fn section_id(inline_id: Option<&str>, id: &str) -> String {
+    match inline_id {
+        Some(inline_id) => format!("{}.{}", inline_id, id),
+        None => id.to_string(),
+    }
 }
 
 fn anchor<'a>(id: &'a str) -> impl markup::Render + 'a {
@@ -22,16 +68,16 @@ fn anchor<'a>(id: &'a str) -> impl markup::Render + 'a {
     impl<'a> markup::Render for Anchor<'a> {
         fn render(&self, writer: &mut impl std::fmt::Write) -> std::fmt::Result {
             writer.write_str("#")?;
-            writer.write_str(self.id)
+            escape_html(writer, self.id)
         }
     }
 
     Anchor { id }
 }
 
-impl<'context, 'krate> BodyInformations<'krate> {
+impl<'context> BodyInformations<'context> {
     pub(super) fn with(
-        global_context: &'context GlobalContext<'krate>,
+        global_context: &'context GlobalContext<'_>,
         page_context: &'context PageContext<'context>,
     ) -> Self {
         let mut page_title = String::with_capacity(32);
@@ -52,10 +98,59 @@ impl<'context, 'krate> BodyInformations<'krate> {
 
         Self {
             page_title,
+            page_path: format!("{}", page_context.filepath.display()),
             krate_name: global_context.krate_name,
             root_path: top_of(page_context.filepath),
+            livereload: global_context.opt.livereload,
+            mermaid: match global_context.opt.mermaid {
+                Some(MermaidSource::Bundled) => global_context
+                    .bundled_mermaid
+                    .as_ref()
+                    .map(|name| format!("{}/{}", top_of(page_context.filepath).display(), name)),
+                Some(MermaidSource::Cdn) => Some(MERMAID_CDN.to_string()),
+                None => None,
+            },
+            full_text_search: global_context.opt.full_text_search,
+            opensearch: global_context.opt.base_url.is_some(),
+            guide: global_context.opt.guide.is_some(),
+            private_items: global_context.krate.includes_private,
+            vendored: global_context.opt.vendored_assets.is_some(),
+            year: global_context.year,
+            static_files: &global_context.static_files,
+        }
+    }
+
+    /// Url of a static file of rd, from this page
+    fn static_href(&self, file: &StaticFile) -> String {
+        format!(
+            "{}/{}",
+            self.root_path.display(),
+            self.static_files.get(file)
+        )
+    }
+
+    /// Url of a third-party asset, from this page
+    fn asset_href(&self, asset: &Asset) -> String {
+        if self.vendored {
+            format!(
+                "{}/{}/{}",
+                self.root_path.display(),
+                VENDOR_DIR,
+                asset.vendored
+            )
+        } else {
+            asset.cdn.to_string()
         }
     }
+
+    /// Subresource integrity of a third-party asset, only checked on the CDN ones
+    fn asset_integrity(&self, asset: &Asset) -> Option<&'static str> {
+        asset.integrity.filter(|_| !self.vendored)
+    }
+
+    fn asset_crossorigin(&self) -> Option<&'static str> {
+        (!self.vendored).then_some("anonymous")
+    }
 }
 
 markup::define! {
@@ -67,22 +162,46 @@ markup::define! {
                 meta[charset="utf-8"];
                 meta[name="viewport", content="width=device-width, initial-scale=1"];
                 meta[name="color-scheme", content="light dark"];
-                link[href="https://cdn.jsdelivr.net/npm/bootstrap-dark-5@1.1.3/dist/css/bootstrap-blackbox.min.css", integrity="sha384-nXtYGwAUBOgb4M8Eo9xOK3Er3bVPQo1HguUNWf/RheIagsbCaP3ZaYqVeUqHEr20", rel="stylesheet", crossorigin="anonymous"];
-                link[href="https://cdn.jsdelivr.net/npm/bootstrap-icons@1.5.0/font/bootstrap-icons.css", integrity="sha384-tKLJeE1ALTUwtXlaGjJYM3sejfssWdAaWR2s97axw4xkiAdMzQjtOjgcyw0Y50KU", rel="stylesheet", crossorigin="anonymous"];
-                link[href=format!("{}/{}", infos.root_path.display(), STYLE_CSS), rel="stylesheet"];
-                link[href=format!("{}/{}", infos.root_path.display(), RUST_SVG), rel="icon", type="image/svg+xml"];
+                link[href=infos.asset_href(&BOOTSTRAP_CSS), integrity=infos.asset_integrity(&BOOTSTRAP_CSS), rel="stylesheet", crossorigin=infos.asset_crossorigin()];
+                link[href=infos.asset_href(&BOOTSTRAP_ICONS_CSS), integrity=infos.asset_integrity(&BOOTSTRAP_ICONS_CSS), rel="stylesheet", crossorigin=infos.asset_crossorigin()];
+                link[href=infos.static_href(&STYLE_CSS), rel="stylesheet"];
+                link[href=infos.static_href(&RUST_SVG), rel="icon", type="image/svg+xml"];
+                @if infos.opensearch {
+                    link[href=format!("{}/{}/{}", infos.root_path.display(), infos.krate_name, OPENSEARCH_XML), rel="search", type="application/opensearchdescription+xml", title=format!("{} docs", infos.krate_name)];
+                }
             }
             body {
-                @Header { krate_name: infos.krate_name, rust: &format!("{}/{}", infos.root_path.display(), RUST_SVG), krate_path: &format!("{}/{}/index.html", infos.root_path.display(), infos.krate_name) }
+                @Header { krate_name: infos.krate_name, rust: &infos.static_href(&RUST_SVG), krate_path: &format!("{}/{}/index.html", infos.root_path.display(), infos.krate_name), search_path: &format!("{}/{}/{}", infos.root_path.display(), infos.krate_name, SEARCH_HTML), guide_path: infos.guide.then(|| format!("{}/{}/{}/index.html", infos.root_path.display(), infos.krate_name, GUIDE_DIR)).as_deref() }
                 @Search { krate_name: infos.krate_name }
                 #main[class="container-xxl"] {
                     @main
                 }
-                @Footer { year: 2022 }
-                script[src="https://cdn.jsdelivr.net/npm/bootstrap@5.1.0/dist/js/bootstrap.min.js", integrity="sha384-cn7l7gDp0eyniUwwAZgrzD06kc/tftFf19TOAs2zVinnD/C7E91j9yyk5//jjpt/", crossorigin="anonymous"] {}
-                script[src="https://cdn.jsdelivr.net/npm/bootstrap-dark-5@1.1.3/dist/js/darkmode.min.js", integrity="sha384-A4SLs39X/aUfwRclRaXvNeXNBTLZdnZdHhhteqbYFS2jZTRD79tKeFeBn7SGXNpi", crossorigin="anonymous"] {}
+                @Footer { year: infos.year }
+                @Options { private_items: infos.private_items }
+                script[src=infos.asset_href(&BOOTSTRAP_JS), integrity=infos.asset_integrity(&BOOTSTRAP_JS), crossorigin=infos.asset_crossorigin()] {}
+                script[src=infos.asset_href(&DARKMODE_JS), integrity=infos.asset_integrity(&DARKMODE_JS), crossorigin=infos.asset_crossorigin()] {}
                 script[src=format!("{}/{}/{}", infos.root_path.display(), infos.krate_name, SEARCH_INDEX_JS)] {}
-                script[src=format!("{}/{}", infos.root_path.display(), SEARCH_JS)] {}
+                @if infos.full_text_search {
+                    script[src=format!("{}/{}/{}", infos.root_path.display(), infos.krate_name, SEARCH_DOCS_INDEX_JS)] {}
+                }
+                script[src=infos.static_href(&SEARCH_JS)] {}
+                script[src=format!("{}/{}/{}", infos.root_path.display(), infos.krate_name, ANCHOR_ALIASES_JS)] {}
+                script[src=infos.static_href(&REDIRECT_JS), "data-rd-page"=&infos.page_path] {}
+                script[src=infos.static_href(&IMPLS_JS)] {}
+                script[src=infos.static_href(&SETTINGS_JS)] {}
+                script[src=infos.static_href(&TOC_JS)] {}
+                script[src=infos.static_href(&COPY_JS)] {}
+                @if let Some(mermaid) = &infos.mermaid {
+                    script[src=mermaid] {}
+                    script {
+                        @markup::raw("mermaid.initialize({ startOnLoad: true, theme: document.documentElement.dataset.bsColorScheme === \"dark\" ? \"dark\" : \"default\" });")
+                    }
+                }
+                @if let Some(port) = infos.livereload {
+                    script {
+                        @markup::raw(format!("new WebSocket(\"ws://localhost:{}\").onmessage = () => location.reload();", port))
+                    }
+                }
             }
         }
     }
@@ -93,81 +212,176 @@ markup::define! {
         ItemPath: markup::Render,
         Deprecation: markup::Render,
         Portability: markup::Render,
+        Banner: markup::Render,
+        Readme: markup::Render,
+        Summary: markup::Render,
+        MustUse: markup::Render,
+        NotableTraits: markup::Render,
+        Contracts: markup::Render,
         Content: markup::Render
     > (
         item_type: &'a str,
         item_name: &'a str,
+        inline_id: Option<&'a str>,
         item_path: ItemPath,
         toc: &'a Vec<TocSection<'a>>,
+        item_banner: Option<Banner>,
+        item_readme: Option<Readme>,
         item_definition: Option<Definition>,
+        item_summary: Option<Summary>,
         item_deprecation: Option<Deprecation>,
         item_portability: Option<Portability>,
+        item_since: Option<&'a str>,
+        item_source_crate: Option<SourceCrate<'a>>,
+        item_must_use: Option<MustUse>,
+        item_notable_traits: Option<NotableTraits>,
+        item_contracts: Option<Contracts>,
         item_doc: Option<MarkdownWithToc<'a, 'a, 'a>>,
+        item_sections: Vec<(HtmlId, PluginSection)>,
         content: Option<Content>
     ) {
-        div[class="rd-main"] {
-            div[class="rd-intro"] {
-                h1[id="item-title", class="rd-anchor item-title"] {
-                    @item_type
-                    " "
-                    @item_path
+        @if let Some(inline_id) = inline_id {
+            section[id=inline_id, class="rd-anchor rd-inline-item"] {
+                h2[class="item-title"] {
+                    a[href=anchor(inline_id)] {
+                        @item_type
+                        " "
+                        @item_name
+                    }
+                    @item_source_crate
+                    @if let Some(since) = item_since {
+                        span[class="rd-since float-end", title="Available since this version"] { "Since " @since }
+                    }
                 }
+                @item_banner
                 @if item_definition.is_some() {
-                    pre[id="item-definition", class="rd-anchor item-definition"] {
+                    pre[class="item-definition"] {
+                        @CopyButton {}
                         code {
                             @item_definition
                         }
+                        @item_notable_traits
                     }
                 }
+                @if item_contracts.is_some() {
+                    div[class="rd-contracts mb-2"] { @item_contracts }
+                }
+                @item_summary
                 @item_deprecation
                 @item_portability
+                @item_must_use
                 @if item_doc.is_some() {
-                    details[id="item-documentation", class="rd-anchor item-documentation", open=""] {
-                        summary {
-                            "Documentation"
-                        }
-                        div[class = "mt-2"] {
-                            @item_doc
-                        }
+                    div[class="item-documentation"] {
+                        @item_doc
+                    }
+                }
+                @for (id, plugin) in item_sections.iter() {
+                    div[id=id, class="rd-anchor item-documentation"] {
+                        h3 { @plugin.title }
+                        @markup::raw(&plugin.html)
                     }
                 }
+                @content
             }
-            div[id="rd-docs-nav", class="rd-toc ps-xl-3 collapse"] {
-                strong[class="d-block h6 my-2 pb-2 border-bottom"] { "On this page" }
-                nav#TableOfContents {
-                    ul {
-                        li {
-                            a[href="#item-title", class="d-inline-flex align-items-center rounded"] { strong { @item_name } }
+        } else {
+            div[class="rd-main"] {
+                div[class="rd-intro"] {
+                    h1[id="item-title", class="rd-anchor item-title"] {
+                        @item_type
+                        " "
+                        @item_path
+                        @item_source_crate
+                        @if let Some(since) = item_since {
+                            span[class="rd-since float-end", title="Available since this version"] { "Since " @since }
+                        }
+                    }
+                    @item_banner
+                    @if item_definition.is_some() {
+                        pre[id="item-definition", class="rd-anchor item-definition"] {
+                            @CopyButton {}
+                            code {
+                                @item_definition
+                            }
+                            @item_notable_traits
                         }
-                        @if let Some(item_doc) = item_doc {
+                    }
+                    @if item_contracts.is_some() {
+                        div[id=CONTRACTS_ID, class="rd-anchor rd-contracts mb-2"] { @item_contracts }
+                    }
+                    @item_summary
+                    @item_deprecation
+                    @item_portability
+                    @item_must_use
+                    @if item_readme.is_some() {
+                        details[id=README_ID, class="rd-anchor item-documentation", open=""] {
+                            summary {
+                                @README
+                            }
+                            div[class = "mt-2"] {
+                                @item_readme
+                            }
+                        }
+                    }
+                    @if item_doc.is_some() {
+                        details[id="item-documentation", class="rd-anchor item-documentation", open=""] {
+                            summary {
+                                "Documentation"
+                            }
+                            div[class = "mt-2"] {
+                                @item_doc
+                            }
+                        }
+                    }
+                    @for (id, plugin) in item_sections.iter() {
+                        details[id=id, class="rd-anchor item-documentation", open=""] {
+                            summary {
+                                @plugin.title
+                            }
+                            div[class = "mt-2"] {
+                                @markup::raw(&plugin.html)
+                            }
+                        }
+                    }
+                }
+                div[id="rd-docs-nav", class="rd-toc ps-xl-3 collapse"] {
+                    strong[class="d-block h6 my-2 pb-2 border-bottom"] { "On this page" }
+                    nav#TableOfContents {
+                        ul {
                             li {
-                                @if item_doc.4.borrow_mut().len() == 0 {
-                                    a[href="#item-documentation", class="d-inline-block align-items-center rounded"] { strong { "Documentation" } }
-                                } else {
-                                    a[class="rd-btn-toc d-inline-block align-items-center rounded bi bi-caret-right-fill", href="#item-documentation", "data-bs-toggle"="collapse", "data-bs-target"="#toc-documentation", "aria-expanded"="true", "aria-current"="true"] { strong { "Documentation" } }
-                                    ul[id="toc-documentation", class="collapse show"] {
-                                        @for (level, ref name, destination) in item_doc.4.borrow_mut().iter() {
-                                            @if *level == 1 {
-                                                li {
-                                                    a[href=destination.with_pound(), class="d-inline-block align-items-center rounded"] {
-                                                        @name
-                                                    }
-                                                }
-                                            }
+                                a[href="#item-title", class="d-inline-flex align-items-center rounded"] { strong { @item_name } }
+                            }
+                            @if item_readme.is_some() {
+                                li {
+                                    a[href=anchor(README_ID), class="d-inline-block align-items-center rounded"] { strong { @README } }
+                                }
+                            }
+                            @if let Some(item_doc) = item_doc {
+                                li {
+                                    @if item_doc.4.borrow_mut().is_empty() {
+                                        a[href="#item-documentation", class="d-inline-block align-items-center rounded"] { strong { "Documentation" } }
+                                    } else {
+                                        a[class="rd-btn-toc d-inline-block align-items-center rounded bi bi-caret-right-fill", href="#item-documentation", "data-bs-toggle"="collapse", "data-bs-target"="#toc-documentation", role="button", "aria-controls"="toc-documentation", "aria-expanded"="true"] { strong { "Documentation" } }
+                                        ul[id="toc-documentation", class="collapse show"] {
+                                            @DocumentationToc { entries: &item_doc.4.borrow(), depth: 0 }
                                         }
                                     }
                                 }
                             }
-                        }
-                        @for TocSection { name: section_name, id: section_id, items: section_items } in toc.iter() {
-                            @if !section_items.is_empty() {
+                            @for (id, plugin) in item_sections.iter() {
                                 li {
-                                    a[class="rd-btn-toc d-inline-block align-items-center rounded bi bi-caret-right-fill", href=format!("#{}", section_id), "data-bs-toggle"="collapse", "data-bs-target"=format!("#toc-{}", section_id), "aria-expanded"="true", "aria-current"="true"] { strong { @section_name } }
-                                    ul[id=format!("toc-{}", section_id), class="collapse show"] {
-                                        @for (ref name, destination) in section_items {
-                                            li {
-                                                a[href=destination, class="d-inline-block align-items-center rounded"] {
-                                                    @name.deref()
+                                    a[href=id.with_pound(), class="d-inline-block align-items-center rounded"] { strong { @plugin.title } }
+                                }
+                            }
+                            @for TocSection { name: section_name, id: section_id, items: section_items } in toc.iter() {
+                                @if !section_items.is_empty() {
+                                    li {
+                                        a[class="rd-btn-toc d-inline-block align-items-center rounded bi bi-caret-right-fill", href=format!("#{}", section_id), "data-bs-toggle"="collapse", "data-bs-target"=format!("#toc-{}", section_id), role="button", "aria-controls"=format!("toc-{}", section_id), "aria-expanded"="true"] { strong { @section_name } }
+                                        ul[id=format!("toc-{}", section_id), class="collapse show"] {
+                                            @for (ref name, destination, tooltip) in section_items {
+                                                li {
+                                                    a[href=destination, class="d-inline-block align-items-center rounded", title=tooltip.as_deref()] {
+                                                        @name.deref()
+                                                    }
                                                 }
                                             }
                                         }
@@ -177,9 +391,28 @@ markup::define! {
                         }
                     }
                 }
+                div[class="rd-content"] {
+                    @content
+                }
             }
-            div[class="rd-content"] {
-                @content
+        }
+    }
+
+    DocumentationToc<'a>(entries: &'a [TocHeading<'a>], depth: u32) {
+        @for ((_, name, id), children) in toc_nodes(entries) {
+            li {
+                @if children.is_empty() {
+                    a[href=id.with_pound(), class="d-inline-block align-items-center rounded"] {
+                        @name
+                    }
+                } else {
+                    a[class="rd-btn-toc d-inline-block align-items-center rounded bi bi-caret-right-fill", href=id.with_pound(), "data-bs-toggle"="collapse", "data-bs-target"=format!("#toc-{}", id), role="button", "aria-controls"=format!("toc-{}", id), "aria-expanded"=if *depth == 0 { "true" } else { "false" }] {
+                        @name
+                    }
+                    ul[id=format!("toc-{}", id), class=if *depth == 0 { "collapse show" } else { "collapse" }] {
+                        @DocumentationToc { entries: children, depth: depth + 1 }
+                    }
+                }
             }
         }
     }
@@ -203,27 +436,66 @@ markup::define! {
 
     PortabilityNotice<
         'portability
-    > (message: &'portability str, portability: &'portability str) {
-        div[class="alert alert-primary", role="alert"] {
+    > (message: &'portability str, portability: &'portability str, disabled: bool) {
+        div[class={ if *disabled { "alert alert-warning" } else { "alert alert-primary" } }, role="alert"] {
             i[class="bi bi-info-circle me-2"] {}
             @message
             code { @portability }
         }
     }
 
+    ShortPortability<'a>(cfg: &'a str, disabled: bool) {
+        @cfg
+        @if *disabled {
+            " (not enabled)"
+        }
+    }
+
+    TraitSummary(counts: String) {
+        p[class="rd-item-summary text-muted"] {
+            i[class="bi bi-list-check me-2"] {}
+            @counts
+        }
+    }
+
+    SummaryWithCounts<Summary: markup::Render>(summary: Summary, counts: String) {
+        @summary
+        small[class="rd-counts text-muted"] { @counts }
+    }
+
+    MustUseNotice<
+        'must_use
+    > (message: &'static str, reason: Option<Cow<'must_use, str>>) {
+        div[class="alert alert-secondary alert-sm", role="note"] {
+            i[class="bi bi-hand-index me-2"] {}
+            @message
+            @if let Some(reason) = reason {
+                ": "
+                @reason.as_ref()
+            }
+        }
+    }
+
     ModuleSectionItem<
         Item: markup::Render,
         Summary: markup::Render,
         Unsafety: markup::Render,
         Deprecated: markup::Render,
         Portability: markup::Render,
-    > (name: Item, summary: Summary, deprecated: Option<Deprecated>, unsafety: Option<Unsafety>, portability: Option<Portability>) {
-        div {
+        Since: markup::Render,
+    > (name: Item, summary: Summary, deprecated: Option<Deprecated>, asyncness: bool, constness: bool, unsafety: Option<Unsafety>, portability: Option<Portability>, since: Option<Since>, visibility: Option<&'static str>) {
+        div["data-visibility"=visibility] {
             p {
                 @name
                 @if deprecated.is_some() {
                     span[class="badge bg-warning text-wrap text-dark ms-1"] { "Deprecated" }
                 }
+                @if *constness {
+                    span[class="badge rounded-pill rd-chip rd-chip-const ms-1"] { "const" }
+                }
+                @if *asyncness {
+                    span[class="badge rounded-pill rd-chip rd-chip-async ms-1"] { "async" }
+                }
                 @if unsafety.is_some() {
                     " "
                     span[role="tooltip", class="rd-tooltip"] {
@@ -238,15 +510,18 @@ markup::define! {
                         span[class="rd-tooltip-data"] { @portability }
                     }
                 }
+                @if let Some(since) = since {
+                    span[class="badge rounded-pill rd-chip rd-chip-since ms-1"] { "Since " @since }
+                }
             }
         }
-        div { @summary }
+        div["data-visibility"=visibility] { @summary }
     }
 
     ModuleSection<
         'name,
         Item: markup::Render,
-    > (name: &'name str, id: &'static str, items: &'name Vec<Item>) {
+    > (name: &'name str, id: &'name str, items: &'name Vec<Item>) {
         @if !items.is_empty() {
             section {
                 h2[id=id, class="rd-anchor"] {
@@ -267,7 +542,7 @@ markup::define! {
     GeneralSection<
         'name,
         Item: markup::Render,
-    > (name: &'name str, id: &'static str, items: &'name Vec<Item>) {
+    > (name: &'name str, id: &'name str, items: &'name Vec<Item>) {
         @if !items.is_empty() {
             section {
                 h2[id=id, class="rd-anchor"] {
@@ -283,6 +558,59 @@ markup::define! {
         }
     }
 
+    CollapsedSection<
+        'name,
+        Item: markup::Render,
+    > (name: &'name str, id: &'name str, items: &'name Vec<Item>) {
+        @if !items.is_empty() {
+            section {
+                details[class="rd-collapsed-section"] {
+                    summary {
+                        h2[id=id, class="rd-anchor d-inline-block"] {
+                            @name
+                            a["aria-label"="anchor", href=anchor(id)] {
+                                i[class="bi bi-hash"] {}
+                            }
+                        }
+                    }
+                    @for item in *items {
+                        @item
+                    }
+                }
+            }
+        }
+    }
+
+    GroupedSection<
+        'name,
+        Group: AsRef<str>,
+        Item: markup::Render,
+    > (name: &'name str, id: &'name str, groups: &'name Vec<(Group, Vec<Item>)>) {
+        @if !groups.is_empty() {
+            section {
+                h2[id=id, class="rd-anchor"] {
+                    @name
+                    a["aria-label"="anchor", href=anchor(id)] {
+                        i[class="bi bi-hash"] {}
+                    }
+                }
+                @for (group, items) in *groups {
+                    @if groups.len() > 1 {
+                        h3[id=format!("{}-{}", id, group.as_ref()), class="rd-anchor h5 mt-3"] {
+                            @group.as_ref()
+                            a["aria-label"="anchor", href=format!("#{}-{}", id, group.as_ref())] {
+                                i[class="bi bi-hash"] {}
+                            }
+                        }
+                    }
+                    @for item in items {
+                        @item
+                    }
+                }
+            }
+        }
+    }
+
     InlineCode<Code: markup::Render> (code: Code) {
         code[class="inline-code"] { @code }
     }
@@ -292,7 +620,7 @@ markup::define! {
         Code: markup::Render,
     > (code: Code, source_href: &'source Option<String>) {
         @if source_href.is_some() {
-            a[class = "float-right", href = source_href] {
+            a[class = "float-right rd-source-link", href = source_href] {
                 "[src]"
             }
         }
@@ -304,20 +632,48 @@ markup::define! {
         Documentation: markup::Render,
         Deprecation: markup::Render,
         Id: markup::Render,
-    > (code: Code, doc: Option<Documentation>, deprecation: Option<Deprecation>, id: Option<Id>, open: bool, source_href: Option<String>) {
+        NotableTraits: markup::Render,
+        Contracts: markup::Render,
+    > (code: Code, doc: Option<Documentation>, deprecation: Option<Deprecation>, notable_traits: Option<NotableTraits>, contracts: Option<Contracts>, id: Option<Id>, open: bool, source_href: Option<String>, default_body: Option<String>) {
         div[id=id, class="mt-2 mb-2 rd-anchor"] {
             @if doc.is_some() {
                 details[open=open] {
                     summary {
                         @InlineCodeWithSource { code, source_href }
+                        @notable_traits
+                        @contracts
                         @deprecation
                     }
                     div[class="mt-2 item-documentation"] { @doc }
                 }
             } else {
                 @InlineCodeWithSource { code, source_href }
+                @notable_traits
+                @contracts
                 @deprecation
             }
+            @if let Some(source) = default_body {
+                details[class="rd-default-body"] {
+                    summary { "Default implementation" }
+                    pre {
+                        @CopyButton {}
+                        code[class="language-rust"] { @source }
+                    }
+                }
+            }
+        }
+    }
+
+    NotableTraits<Impl: markup::Render>(impls: Vec<Impl>) {
+        " "
+        span[role="tooltip", class="rd-tooltip rd-notable-traits"] {
+            i[class="bi bi-info-circle"] {}
+            span[class="rd-tooltip-data"] {
+                strong { "Notable traits" }
+                @for impl_ in impls {
+                    div { code[class="inline-code"] { @impl_ } }
+                }
+            }
         }
     }
 
@@ -327,28 +683,101 @@ markup::define! {
         Deprecation: markup::Render,
         Id: markup::Render,
         Extra: markup::Render,
-    > (code: Code, doc: Option<Documentation>, deprecation: Option<Deprecation>, extras: Vec<Extra>, id: Option<Id>, open: bool, source_href: Option<String>) {
+    > (code: Code, doc: Option<Documentation>, deprecation: Option<Deprecation>, extras: Vec<Extra>, id: Option<Id>, legacy_id: Option<Id>, open: bool, source_href: Option<String>, lazy_src: Option<String>, derived: bool, module: Option<ImplModule>, portability: Option<ImplPortability>) {
         div[id=id, class="mt-2 mb-2 rd-anchor"] {
-            @if doc.is_some() || !extras.is_empty() {
-                details[open=open] {
+            @if let Some(legacy_id) = legacy_id {
+                span[id=legacy_id, class="rd-anchor"] {}
+            }
+            @if let Some(lazy_src) = lazy_src {
+                details[class="rd-impl", "data-rd-lazy-src"=lazy_src] {
                     summary {
                         @InlineCodeWithSource { code, source_href }
+                        @if *derived {
+                            @DerivedBadge {}
+                        }
+                        @module
+                        @portability
                     }
-                    @deprecation
-                    div[class="mt-2 item-documentation"] { @doc }
-                    div[style = "padding-left:1.5rem;"] {
-                        @for extra in extras {
-                            @extra
+                }
+            } else if doc.is_some() || !extras.is_empty() {
+                details[class="rd-impl", open=open] {
+                    summary {
+                        @InlineCodeWithSource { code, source_href }
+                        @if *derived {
+                            @DerivedBadge {}
                         }
+                        @module
+                        @portability
                     }
+                    @ImplBody { doc, deprecation, extras }
                 }
             } else {
                 @InlineCodeWithSource { code, source_href }
+                @if *derived {
+                    @DerivedBadge {}
+                }
+                @module
+                @portability
                 @deprecation
             }
         }
     }
 
+    ImplModule(path: String, href: Option<String>) {
+        " "
+        span[class="rd-impl-module"] {
+            "implemented in "
+            @if let Some(href) = href {
+                a[href=href] { code { @path } }
+            } else {
+                code { @path }
+            }
+        }
+    }
+
+    ContractBadges(sections: Vec<(&'static str, HtmlId)>) {
+        @for (name, id) in sections {
+            a[class=format!("badge rounded-pill rd-chip rd-chip-{} ms-1", name.to_ascii_lowercase()), href=id.with_pound(), title=format!("Documents its {}", name.to_ascii_lowercase())] { @name }
+        }
+    }
+
+    CopyButton() {
+        button[type="button", class="rd-copy", title="Copy to clipboard", "aria-label"="Copy to clipboard"] {
+            i[class="bi bi-clipboard", "aria-hidden"="true"] {}
+        }
+    }
+
+    ImplPortability(cfg: String, disabled: bool) {
+        " "
+        span[class="rd-impl-cfg", title="Only available with this cfg"] {
+            i[class="bi bi-gear me-1"] {}
+            code { @cfg }
+            @if *disabled {
+                " (not enabled)"
+            }
+        }
+    }
+
+    DerivedBadge() {
+        " "
+        span[class="badge rounded-pill rd-chip rd-chip-derived", title="Implemented with #[derive]"] { "Derived" }
+    }
+
+    ImplBody<
+        'a,
+        Documentation: markup::Render,
+        Deprecation: markup::Render,
+        Extra: markup::Render,
+    > (doc: &'a Option<Documentation>, deprecation: &'a Option<Deprecation>, extras: &'a Vec<Extra>) {
+        @deprecation
+        div[class="mt-2 item-documentation"] { @doc }
+        div[style = "padding-left:1.5rem;"] {
+            @for extra in extras.iter() {
+                @extra
+            }
+        }
+    }
+
     VariantEnchanted<
         Id: markup::Render,
         Definition: markup::Render,
@@ -387,16 +816,38 @@ markup::define! {
         }
     }
 
+    ConstantContent<'a>(inline_id: Option<&'a str>, value: Option<&'a str>, expr: Option<&'a str>) {
+        @if value.is_some() || expr.is_some() {
+            section {
+                h2[class="pb-1 rd-anchor", id=section_id(*inline_id, VALUE_ID)] {
+                    @VALUE
+                    a["aria-label"="anchor", href=format!("#{}", section_id(*inline_id, VALUE_ID))] {
+                        i[class="bi bi-hash"] {}
+                    }
+                }
+                @if let Some(value) = value {
+                    pre[class="rd-const-value"] { code { @value } }
+                }
+                @if let Some(expr) = expr {
+                    details[class="rd-const-expr"] {
+                        summary { "Expression" }
+                        pre { code { @expr } }
+                    }
+                }
+            }
+        }
+    }
+
     StructUnionEnumContent<
         'title,
         Variant: markup::Render,
         Traits: markup::Render
-    > (title: &'title str, variants: Vec<Variant>, traits: Traits) {
+    > (inline_id: Option<&'title str>, title: &'title str, variants: Vec<Variant>, traits: Traits) {
         @if !variants.is_empty() {
             section {
-                h2[class="pb-1 rd-anchor", id=VARIANTS_ID] {
+                h2[class="pb-1 rd-anchor", id=section_id(*inline_id, VARIANTS_ID)] {
                     @title
-                    a["aria-label"="anchor", href=anchor(VARIANTS_ID)] {
+                    a["aria-label"="anchor", href=format!("#{}", section_id(*inline_id, VARIANTS_ID))] {
                         i[class="bi bi-hash"] {}
                     }
                 }
@@ -408,7 +859,58 @@ markup::define! {
         @traits
     }
 
+    CrateMetadataPanel<'a>(metadata: &'a CrateMetadata) {
+        div[class="rd-crate-metadata"] {
+            dl {
+                @if let Some(version) = &metadata.version {
+                    dt { "Version" }
+                    dd { @version }
+                }
+                @if let Some(edition) = &metadata.edition {
+                    dt { "Edition" }
+                    dd { @edition }
+                }
+                @if let Some(license) = &metadata.license {
+                    dt { "License" }
+                    dd { @license }
+                }
+                dt { "Links" }
+                dd {
+                    @if let Some(repository) = &metadata.repository {
+                        a[href=repository] { i[class="bi bi-git"] {} " Repository" }
+                    }
+                    @if let Some(homepage) = &metadata.homepage {
+                        a[href=homepage] { i[class="bi bi-house"] {} " Homepage" }
+                    }
+                    a[href=metadata.documentation_url()] { i[class="bi bi-book"] {} " Documentation" }
+                }
+                @if !metadata.features.is_empty() {
+                    dt { "Features" }
+                    dd {
+                        @for feature in &metadata.features {
+                            span[class="rd-chip"] { @feature }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    CrateAttributes(attributes: Vec<(&'static str, &'static str)>) {
+        @if !attributes.is_empty() {
+            div[class="rd-crate-attributes"] {
+                @for (name, title) in attributes {
+                    span[class="badge rounded-pill rd-chip rd-chip-attribute me-1", title=title] {
+                        "#![" @name "]"
+                    }
+                }
+            }
+        }
+    }
+
     ModulePageContent<
+        Metadata: markup::Render,
+        Features: markup::Render,
         ImportItem: markup::Render,
         ModuleItem: markup::Render,
         UnionItem: markup::Render,
@@ -418,11 +920,20 @@ markup::define! {
         TraitItem: markup::Render,
         TraitAliasItem: markup::Render,
         TypedefItem: markup::Render,
+        OpaqueTypeItem: markup::Render,
+        ForeignTypeItem: markup::Render,
         ConstantItem: markup::Render,
         MacroItem: markup::Render,
         ProcMacroItem: markup::Render,
+        PrimitiveItem: markup::Render,
+        KeywordItem: markup::Render,
+        FailedItem: markup::Render,
     > (
+        attributes: CrateAttributes,
+        metadata: Option<Metadata>,
+        features: Features,
         imports: Vec<ImportItem>,
+        primitives: Vec<PrimitiveItem>,
         modules: Vec<ModuleItem>,
         unions: Vec<UnionItem>,
         structs: Vec<StructItem>,
@@ -431,11 +942,20 @@ markup::define! {
         traits: Vec<TraitItem>,
         trait_alias: Vec<TraitAliasItem>,
         typedefs: Vec<TypedefItem>,
+        opaque_types: Vec<OpaqueTypeItem>,
+        foreign_types: Vec<ForeignTypeItem>,
         constants: Vec<ConstantItem>,
         macros: Vec<MacroItem>,
         proc_macros: Vec<ProcMacroItem>,
+        keywords: Vec<KeywordItem>,
+        failed_items: Vec<FailedItem>,
+        inlined: Option<String>,
     ) {
+        @attributes
+        @metadata
+        @features
         @ModuleSection { name: IMPORTS, id: IMPORTS_ID, items: imports }
+        @ModuleSection { name: PRIMITIVES, id: PRIMITIVES_ID, items: primitives }
         @ModuleSection { name: MODULES, id: MODULES_ID, items: modules }
         @ModuleSection { name: MACROS, id: MACROS_ID, items: macros }
         @ModuleSection { name: PROC_MACROS, id: PROC_MACROS_ID, items: proc_macros }
@@ -446,46 +966,216 @@ markup::define! {
         @ModuleSection { name: TRAITS, id: TRAITS_ID, items: traits }
         @ModuleSection { name: TRAIT_ALIAS, id: TRAIT_ALIAS_ID, items: trait_alias }
         @ModuleSection { name: TYPEDEFS, id: TYPEDEFS_ID, items: typedefs }
+        @ModuleSection { name: OPAQUE_TYPES, id: OPAQUE_TYPES_ID, items: opaque_types }
+        @ModuleSection { name: FOREIGN_TYPES, id: FOREIGN_TYPES_ID, items: foreign_types }
         @ModuleSection { name: CONSTANTS, id: CONSTANTS_ID, items: constants }
+        @ModuleSection { name: KEYWORDS, id: KEYWORDS_ID, items: keywords }
+        @ModuleSection { name: FAILED_ITEMS, id: FAILED_ITEMS_ID, items: failed_items }
+        @if let Some(inlined) = inlined {
+            div[class="rd-inline-items"] {
+                @markup::raw(inlined)
+            }
+        }
+    }
+
+    UnsafePageContent<Item: markup::Render, Module: markup::Render>(
+        functions: Vec<Item>,
+        traits: Vec<Item>,
+        unions: Vec<Item>,
+        modules: Vec<Module>,
+    ) {
+        @ModuleSection { name: UNSAFE_FUNCTIONS, id: UNSAFE_FUNCTIONS_ID, items: functions }
+        @ModuleSection { name: UNSAFE_TRAITS, id: UNSAFE_TRAITS_ID, items: traits }
+        @ModuleSection { name: UNIONS, id: UNIONS_ID, items: unions }
+        @ModuleSection { name: UNSAFE_BY_MODULE, id: UNSAFE_BY_MODULE_ID, items: modules }
+    }
+
+    StatsPageContent<Overview: markup::Render, Kinds: markup::Render>(
+        overview: Vec<Overview>,
+        kinds: Vec<Kinds>,
+    ) {
+        @GeneralSection { name: STATISTICS_OVERVIEW, id: STATISTICS_OVERVIEW_ID, items: overview }
+        @GeneralSection { name: STATISTICS_KINDS, id: STATISTICS_KINDS_ID, items: kinds }
+    }
+
+    StatsOverview<'a>(rows: Vec<(&'static str, String, Option<&'a str>)>) {
+        div[class="rd-crate-metadata"] {
+            dl {
+                @for (name, value, link) in rows {
+                    dt { @name }
+                    dd {
+                        @if let Some(link) = link {
+                            a[href=link] { @value }
+                        } else {
+                            @value
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    FeaturesTable(features: Vec<(String, String, Vec<(String, String, &'static str)>)>) {
+        @if !features.is_empty() {
+            section {
+                h2[id=FEATURES_ID, class="rd-anchor"] {
+                    @FEATURES
+                    a["aria-label"="anchor", href=anchor(FEATURES_ID)] {
+                        i[class="bi bi-hash"] {}
+                    }
+                }
+                table[class="table table-sm rd-features"] {
+                    thead {
+                        tr {
+                            th[scope="col"] { "Feature" }
+                            th[scope="col"] { "Description" }
+                            th[scope="col"] { "Items" }
+                        }
+                    }
+                    tbody {
+                        @for (name, description, items) in features {
+                            tr[id=format!("feature.{}", name), class="rd-anchor"] {
+                                td { code { @name } }
+                                td { @description }
+                                td {
+                                    @for (path, link, kind) in items {
+                                        div { @ItemLink { name: path, link, class: kind, title: None } }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    StatsKinds(rows: Vec<(&'static str, usize, String)>) {
+        table[class="table table-sm rd-stats-kinds"] {
+            thead {
+                tr {
+                    th[scope="col"] { "Kind" }
+                    th[scope="col"] { "Items" }
+                    th[scope="col"] { "Documented" }
+                }
+            }
+            tbody {
+                @for (kind, count, documented) in rows {
+                    tr {
+                        td { @kind }
+                        td { @count }
+                        td { @documented }
+                    }
+                }
+            }
+        }
     }
 
-    TraitPageContent<Code: markup::Render, Trait: markup::Render>(
+    TraitPageContent<'a, Code: markup::Render, Trait: markup::Render, Item: markup::Render>(
+        inline_id: Option<&'a str>,
         associated_types: Vec<Code>,
         associated_consts: Vec<Code>,
         required_methods: Vec<Code>,
         provided_methods: Vec<Code>,
-        implementations_foreign_types: Vec<Trait>,
-        implementors: Vec<Trait>,
+        implementations_foreign_types: Vec<(Cow<'a, str>, Vec<Trait>)>,
+        implementors: Vec<(Cow<'a, str>, Vec<Trait>)>,
         auto_implementors: Vec<Trait>,
+        required_by: Vec<Item>,
     ) {
-        @GeneralSection { name: ASSOCIATED_TYPES, id: ASSOCIATED_TYPES_ID, items: associated_types }
-        @GeneralSection { name: ASSOCIATED_CONSTS, id: ASSOCIATED_CONSTS_ID, items: associated_consts }
-        @GeneralSection { name: REQUIRED_METHODS, id: REQUIRED_METHODS_ID, items: required_methods }
-        @GeneralSection { name: PROVIDED_METHODS, id: PROVIDED_METHODS_ID, items: provided_methods }
-        @GeneralSection { name: IMPLEMENTATION_FOREIGN_TYPES, id: IMPLEMENTATION_FOREIGN_TYPES_ID, items: implementations_foreign_types }
-        @GeneralSection { name: IMPLEMENTORS, id: IMPLEMENTORS_ID, items: implementors }
-        @GeneralSection { name: AUTO_IMPLEMENTORS, id: AUTO_IMPLEMENTORS_ID, items: auto_implementors }
+        @GeneralSection { name: ASSOCIATED_TYPES, id: &section_id(*inline_id, ASSOCIATED_TYPES_ID), items: associated_types }
+        @GeneralSection { name: ASSOCIATED_CONSTS, id: &section_id(*inline_id, ASSOCIATED_CONSTS_ID), items: associated_consts }
+        @GeneralSection { name: REQUIRED_METHODS, id: &section_id(*inline_id, REQUIRED_METHODS_ID), items: required_methods }
+        @GeneralSection { name: PROVIDED_METHODS, id: &section_id(*inline_id, PROVIDED_METHODS_ID), items: provided_methods }
+        @GroupedSection { name: IMPLEMENTATION_FOREIGN_TYPES, id: &section_id(*inline_id, IMPLEMENTATION_FOREIGN_TYPES_ID), groups: implementations_foreign_types }
+        @GroupedSection { name: IMPLEMENTORS, id: &section_id(*inline_id, IMPLEMENTORS_ID), groups: implementors }
+        @GeneralSection { name: AUTO_IMPLEMENTORS, id: &section_id(*inline_id, AUTO_IMPLEMENTORS_ID), items: auto_implementors }
+        @ModuleSection { name: REQUIRED_BY, id: &section_id(*inline_id, REQUIRED_BY_ID), items: required_by }
     }
 
-    TraitsWithItems<Trait: markup::Render>(
+    TraitsWithItems<'a, Trait: markup::Render, Shared: markup::Render>(
+        inline_id: Option<&'a str>,
         implementations: Vec<Trait>,
-        trait_implementations: Vec<Trait>,
+        trait_implementations: Vec<(&'a str, Vec<Trait>)>,
+        derived_trait_implementations: Vec<Trait>,
         auto_trait_implementations: Vec<Trait>,
         blanket_implementations: Vec<Trait>,
+        shared_blanket_implementations: Vec<Shared>,
     ) {
-        @GeneralSection { name: IMPLEMENTATIONS, id: IMPLEMENTATIONS_ID, items: implementations }
-        @GeneralSection { name: TRAIT_IMPLEMENTATIONS, id: TRAIT_IMPLEMENTATIONS_ID, items: trait_implementations }
-        @GeneralSection { name: AUTO_TRAIT_IMPLEMENTATIONS, id: AUTO_TRAIT_IMPLEMENTATIONS_ID, items: auto_trait_implementations }
-        @GeneralSection { name: BLANKET_IMPLEMENTATIONS, id: BLANKET_IMPLEMENTATIONS_ID, items: blanket_implementations }
+        @GeneralSection { name: IMPLEMENTATIONS, id: &section_id(*inline_id, IMPLEMENTATIONS_ID), items: implementations }
+        @GroupedSection { name: TRAIT_IMPLEMENTATIONS, id: &section_id(*inline_id, TRAIT_IMPLEMENTATIONS_ID), groups: trait_implementations }
+        @CollapsedSection { name: DERIVED_TRAIT_IMPLEMENTATIONS, id: &section_id(*inline_id, DERIVED_TRAIT_IMPLEMENTATIONS_ID), items: derived_trait_implementations }
+        @GeneralSection { name: AUTO_TRAIT_IMPLEMENTATIONS, id: &section_id(*inline_id, AUTO_TRAIT_IMPLEMENTATIONS_ID), items: auto_trait_implementations }
+        @if !blanket_implementations.is_empty() || !shared_blanket_implementations.is_empty() {
+            section {
+                h2[id=section_id(*inline_id, BLANKET_IMPLEMENTATIONS_ID), class="rd-anchor"] {
+                    @BLANKET_IMPLEMENTATIONS
+                    a["aria-label"="anchor", href=format!("#{}", section_id(*inline_id, BLANKET_IMPLEMENTATIONS_ID))] {
+                        i[class="bi bi-hash"] {}
+                    }
+                }
+                @for item in blanket_implementations {
+                    @item
+                }
+                @for item in shared_blanket_implementations {
+                    @item
+                }
+            }
+        }
+    }
+
+    SharedImplLink<Code: markup::Render, Id: markup::Render>(code: Code, id: Option<Id>, href: String) {
+        div[id=id, class="mt-2 mb-2 rd-anchor"] {
+            @InlineCode { code }
+            " "
+            a[class="rd-shared-impl", href=href, title="Shared by all the types of the crate"] {
+                i[class="bi bi-box-arrow-up-right"] {}
+            }
+        }
     }
 
-    ItemLink<'a, Item: markup::Render>(name: Item, link: &'a str, class: &'a str) {
-        a[href = link, class = class] {
+    RenderingError<'a>(error: &'a str) {
+        div[class="alert alert-danger", role="alert"] {
+            p { "This item could not be rendered:" }
+            pre { code { @error } }
+        }
+    }
+
+    ImportSummary<'a, Summary: markup::Render>(
+        summary: Option<Summary>,
+        external: Option<(&'a str, Option<String>)>,
+    ) {
+        @if let Some(summary) = summary {
+            @summary
+        } else if let Some((krate, href)) = external {
+            p {
+                "Re-exported from "
+                @if let Some(href) = href {
+                    a[href = href, class = "mod"] { @krate }
+                } else {
+                    span[class = "mod"] { @krate }
+                }
+            }
+        }
+    }
+
+    DependencyLink<'a>(name: &'a str, url: Option<String>) {
+        @if let Some(url) = url {
+            a[href = url, class = "mod"] { @name }
+        } else {
+            span[class = "mod"] { @name }
+        }
+    }
+
+    ItemLink<'a, Item: markup::Render>(name: Item, link: &'a str, class: &'a str, title: Option<Arc<str>>) {
+        a[href = link, class = class, title = title.as_deref()] {
+            @if !class.is_empty() {
+                i[class = "rd-kind", "data-kind" = class, "aria-hidden" = "true"] {}
+            }
             @name
         }
     }
 
-    Header<'a>(krate_name: &'a str, rust: &'a str, krate_path: &'a str) {
+    Header<'a>(krate_name: &'a str, rust: &'a str, krate_path: &'a str, search_path: &'a str, guide_path: Option<&'a str>) {
         header[class="navbar navbar-expand-md navbar-dark rd-navbar"] {
             nav[class="container-xxl flex-wrap flex-md-nowrap", "aria-label"="Main navigation"] {
                 a[class="navbar-brand p-0 me-2", href=krate_path, "aria-label"="Rust"] {
@@ -502,6 +1192,14 @@ markup::define! {
                         li[class="nav-item col-6 col-md-auto"] {
                             a[class="nav-link p-2 active", href=krate_path] { @krate_name }
                         }
+                        @if let Some(guide_path) = guide_path {
+                            li[class="nav-item col-6 col-md-auto"] {
+                                a[class="nav-link p-2", href=guide_path] { @GUIDE }
+                            }
+                        }
+                        li[class="nav-item col-6 col-md-auto"] {
+                            a[class="nav-link p-2", href=search_path] { "Search" }
+                        }
                         /*li[class="nav-item col-6 col-md-auto"] {
                             a[class="nav-link p-2", href="#", title="Not Yet Working"] { "Examples" }
                         }
@@ -525,8 +1223,8 @@ markup::define! {
                                 small[class="d-md-none ms-2"] { "Shortcut" }
                             }
                         }
-                        li[class="nav-item col-6 col-md-auto", title="Unimplemented"] {
-                            a[class="nav-link p-2", href="#options"] {
+                        li[class="nav-item col-6 col-md-auto"] {
+                            a[class="nav-link p-2", href="#options", title="Options", "data-bs-toggle"="modal", "data-bs-target"="#rd-options"] {
                                 i[class="bi bi-wrench"] {}
                                 small[class="d-md-none ms-2"] { "Options" }
                             }
@@ -537,6 +1235,47 @@ markup::define! {
         }
     }
 
+    Options(private_items: bool) {
+        div[class="modal fade", id="rd-options", tabindex="-1", "aria-labelledby"="rd-options-title", "aria-hidden"="true"] {
+            div[class="modal-dialog"] {
+                div[class="modal-content"] {
+                    div[class="modal-header"] {
+                        h5[class="modal-title", id="rd-options-title"] { "Options" }
+                        button[type="button", class="btn-close", "data-bs-dismiss"="modal", "aria-label"="Close"] {}
+                    }
+                    form[class="modal-body", id="rd-options-form"] {
+                        div[class="form-check form-switch mb-2"] {
+                            input[class="form-check-input", type="checkbox", id="rd-option-collapse-impls", name="collapseImpls"];
+                            label[class="form-check-label", for="rd-option-collapse-impls"] { "Collapse implementations by default" }
+                        }
+                        div[class="form-check form-switch mb-2"] {
+                            input[class="form-check-input", type="checkbox", id="rd-option-source-links", name="sourceLinks", checked=true];
+                            label[class="form-check-label", for="rd-option-source-links"] { "Show source links" }
+                        }
+                        div[class="form-check form-switch mb-2"] {
+                            input[class="form-check-input", type="checkbox", id="rd-option-wrap-signatures", name="wrapSignatures"];
+                            label[class="form-check-label", for="rd-option-wrap-signatures"] { "Wrap long signatures" }
+                        }
+                        @if *private_items {
+                            div[class="form-check form-switch mb-2"] {
+                                input[class="form-check-input", type="checkbox", id="rd-option-private-items", name="privateItems", checked=true];
+                                label[class="form-check-label", for="rd-option-private-items"] { "Show private items" }
+                            }
+                        }
+                        div {
+                            label[class="form-label", for="rd-option-theme"] { "Theme" }
+                            select[class="form-select", id="rd-option-theme", name="theme"] {
+                                option[value="auto"] { "System" }
+                                option[value="light"] { "Light" }
+                                option[value="dark"] { "Dark" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     Search<'a>(krate_name: &'a str) {
         nav[class="rd-subnavbar py-2 border-bottom shadow-sm", "aria-label"="Secondary navigation"] {
             div[class="container-xxl d-flex align-items-md-center"] {
@@ -556,6 +1295,63 @@ markup::define! {
         }
     }
 
+    SearchResults<'a>(krate_name: &'a str) {
+        h1[class="item-title"] { @SEARCH_RESULTS " in " @krate_name }
+        p[class="text-muted"] { "Type a path like " code { "io::read" } " in the search box, the results are listed below." }
+        div[class="rd-search-kinds mb-3", id="rd-search-kinds"] {}
+        div[class="rd-search-items", id=SEARCH_RESULTS_ID] {}
+        nav[class="rd-search-pages", id="rd-search-pages", "aria-label"="Pages of the results"] {}
+    }
+
+    GuidePage<'a, Content: markup::Render>(
+        chapters: Vec<(&'a str, &'a str, bool)>,
+        previous: Option<(&'a str, &'a str)>,
+        next: Option<(&'a str, &'a str)>,
+        content: Content,
+    ) {
+        div[class="row"] {
+            nav[class="col-md-3 rd-toc", id="rd-guide-chapters", "aria-label"="Chapters of the guide"] {
+                strong[class="d-block h6 my-2 pb-2 border-bottom"] { @GUIDE }
+                ul[class="list-unstyled"] {
+                    @for (title, link, current) in chapters {
+                        li {
+                            @if *current {
+                                strong { @title }
+                            } else {
+                                a[href=link] { @title }
+                            }
+                        }
+                    }
+                }
+            }
+            div[class="col-md-9 item-documentation"] {
+                @content
+                nav[class="d-flex justify-content-between border-top mt-4 pt-2", "aria-label"="Previous and next chapters"] {
+                    span {
+                        @if let Some((title, link)) = previous {
+                            a[href=link, rel="prev"] { "← " @title }
+                        }
+                    }
+                    span {
+                        @if let Some((title, link)) = next {
+                            a[href=link, rel="next"] { @title " →" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    SourceCrate<'a>(name: &'a str, url: Option<String>) {
+        span[class="rd-source-crate badge bg-secondary ms-2 align-middle", title="Crate defining this item"] {
+            @if let Some(url) = url {
+                a[href=url, class="text-reset"] { @name }
+            } else {
+                @name
+            }
+        }
+    }
+
     Footer(year: u32) {
         footer[class = "container-xxl text-center"] {
             "The rd developpers - (c) " @year
@@ -1,17 +1,31 @@
 //! Collections of utilities functions for the html generation
 
-use anyhow::{anyhow, Context as _, Result};
-use log::{debug, trace, warn};
+use anyhow::{Context as _, Result};
 use rustdoc_types::*;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path as StdPath, PathBuf};
+use tracing::{debug, trace, warn};
 
+use super::constants::{StaticFile, STATIC_FILES};
 use super::id::Id as HtmlId;
 use super::render::{GlobalContext, PageContext};
 use crate::pp;
+use crate::ImplOrder;
 
-pub(crate) fn fetch_impls<'context, 'krate>(
-    global_context: &'context GlobalContext<'krate>,
+/// Name of the crate of an input file: its file name without the `.json` extension nor the one
+/// of its compression, `foo.json.gz` being `foo`
+pub(crate) fn input_crate_name(file: &StdPath) -> Option<&str> {
+    let name = file.file_name()?.to_str()?;
+    let name = [".gz", ".zst"]
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(name);
+    Some(name.strip_suffix(".json").unwrap_or(name))
+}
+
+pub(crate) fn fetch_impls<'krate>(
+    global_context: &GlobalContext<'krate>,
     impls_ids: &[Id],
 ) -> Result<Vec<(&'krate Item, &'krate Impl, String)>> {
     let mut impls = Vec::with_capacity(impls_ids.len());
@@ -31,13 +45,50 @@ pub(crate) fn fetch_impls<'context, 'krate>(
             }
         };
 
+        if global_context.cfg_hidden(&item.attrs) {
+            continue;
+        }
+
         impls.push((item, impl_, name_of(impl_)?))
     }
 
-    impls.sort_by(|(_, _, x_name), (_, _, y_name)| x_name.cmp(y_name));
+    // The impls of the same name in an order independent of the json
+    if global_context.opt.deterministic {
+        impls.sort_by(|(x, ..), (y, ..)| x.id.0.cmp(&y.id.0));
+    }
+    match global_context.opt.impl_order {
+        ImplOrder::Name => impls.sort_by(|(_, _, x_name), (_, _, y_name)| x_name.cmp(y_name)),
+        ImplOrder::Source => impls.sort_by(|(x, _, x_name), (y, _, y_name)| {
+            // Impls without a span (e.g. auto traits) go last
+            let x_span = x.span.as_ref().map(|span| (&span.filename, span.begin));
+            let y_span = y.span.as_ref().map(|span| (&span.filename, span.begin));
+            (x_span.is_none(), x_span, x_name).cmp(&(y_span.is_none(), y_span, y_name))
+        }),
+        ImplOrder::LocalFirst => impls.sort_by(|(x, _, x_name), (y, _, y_name)| {
+            (is_derived(x), x_name).cmp(&(is_derived(y), y_name))
+        }),
+    }
     Ok(impls)
 }
 
+/// Visibility of an item of a module that isn't public (documented with
+/// `--document-private-items`), as carried by the `data-visibility` attributes
+pub(crate) fn private_visibility(item: &Item) -> Option<&'static str> {
+    match item.visibility {
+        Visibility::Public => None,
+        Visibility::Default => Some("private"),
+        Visibility::Crate => Some("crate"),
+        Visibility::Restricted { .. } => Some("restricted"),
+    }
+}
+
+/// Is this impl the expansion of a `#[derive(..)]`
+pub(crate) fn is_derived(item: &Item) -> bool {
+    item.attrs
+        .iter()
+        .any(|attr| attr == "#[automatically_derived]")
+}
+
 pub(crate) fn prefix_item_kind(kind: &ItemKind) -> Option<(&'static str, bool)> {
     Some(match kind {
         ItemKind::Module => ("mod", true),
@@ -58,12 +109,11 @@ pub(crate) fn prefix_item_kind(kind: &ItemKind) -> Option<(&'static str, bool)>
         ItemKind::AssocConst => ("associatedconst", false),
         ItemKind::AssocType => ("associatedtype", false),
         ItemKind::Primitive => ("primitive", true),
-        ItemKind::ForeignType => return None, // TODO: not sure how to handle it
-        ItemKind::ExternCrate
-        | ItemKind::OpaqueTy
-        | ItemKind::ProcAttribute
-        | ItemKind::ProcDerive
-        | ItemKind::Keyword => unreachable!(),
+        ItemKind::ForeignType => ("foreigntype", true),
+        ItemKind::OpaqueTy => ("opaque", true),
+        ItemKind::ProcAttribute | ItemKind::ProcDerive => ("proc.macro", true),
+        ItemKind::Keyword => ("keyword", true),
+        ItemKind::ExternCrate => return None,
     })
 }
 
@@ -87,10 +137,38 @@ pub(crate) fn prefix_item(item: &Item) -> Option<(&'static str, bool)> {
         ItemEnum::ProcMacro(_) => ("proc.macro", true),
         ItemEnum::AssocConst { .. } => ("associatedconst", false),
         ItemEnum::AssocType { .. } => ("associatedtype", false),
-        ItemEnum::ForeignType => return None, // TODO: not sure how to handle this
-        ItemEnum::ExternCrate { .. } | ItemEnum::OpaqueTy(_) | ItemEnum::Primitive(_) => {
-            unreachable!()
-        }
+        ItemEnum::ForeignType => ("foreigntype", true),
+        ItemEnum::OpaqueTy(_) => ("opaque", true),
+        ItemEnum::Primitive(_) => ("primitive", true),
+        ItemEnum::ExternCrate { .. } => unreachable!(),
+    })
+}
+
+/// Prefix of the page of an item as named by rustdoc, when it differs from ours
+pub(crate) fn rustdoc_page_prefix(prefix: &'static str, kind: &ItemKind) -> &'static str {
+    match kind {
+        ItemKind::TraitAlias => "traitalias",
+        ItemKind::ProcAttribute => "attr",
+        ItemKind::ProcDerive => "derive",
+        ItemKind::Macro => "macro",
+        _ => prefix,
+    }
+}
+
+/// Kind of an item, as found in the paths of the crate
+pub(crate) fn item_kind(item: &Item) -> Option<ItemKind> {
+    Some(match &item.inner {
+        ItemEnum::TraitAlias(_) => ItemKind::TraitAlias,
+        ItemEnum::ProcMacro(ProcMacro {
+            kind: MacroKind::Attr,
+            ..
+        }) => ItemKind::ProcAttribute,
+        ItemEnum::ProcMacro(ProcMacro {
+            kind: MacroKind::Derive,
+            ..
+        }) => ItemKind::ProcDerive,
+        ItemEnum::ProcMacro(_) | ItemEnum::Macro(_) => ItemKind::Macro,
+        _ => return None,
     })
 }
 
@@ -107,23 +185,56 @@ pub(crate) fn type_id(type_: &Type) -> Result<&Id, Option<ItemKind>> {
     }
 }
 
-/// Determine if an [`Item`] is auto-trait and also return the crate id
-pub(crate) fn is_auto_trait<'krate>(
-    krate: &'krate Crate,
-    id: &'krate Id,
-) -> Result<Option<(bool, u32)>> {
-    let Some(item) = krate.index.get(id) else {
-        warn!(
-            "unable to find impl (for auto-trait checking) {:?} -- skipping",
-            id
-        );
-        return Ok(None);
-    };
+/// Type behind the references and pointers of a [`Type`], used to group the impls for
+/// `Foo`, `&Foo` and `*const Foo` together
+pub(crate) fn underlying_type(type_: &Type) -> &Type {
+    match type_ {
+        Type::BorrowedRef { type_, .. } | Type::RawPointer { type_, .. } => underlying_type(type_),
+        type_ => type_,
+    }
+}
 
-    Ok(match &item.inner {
-        ItemEnum::Trait(trait_) => Some((trait_.is_auto, item.crate_id)),
-        _ => return Err(anyhow!("is_auto_trait: error not an trait")),
-    })
+/// Whether each trait known to the crate is an auto-trait, along with its crate id
+pub(crate) fn auto_traits(krate: &Crate) -> HashMap<&Id, (bool, u32)> {
+    // External traits are usually only known by their path, so fallback to the well-known
+    // auto-traits of the standard library
+    let external = krate
+        .paths
+        .iter()
+        .filter(|(id, summary)| summary.kind == ItemKind::Trait && !krate.index.contains_key(id))
+        .map(|(id, summary)| {
+            let is_auto = matches!(
+                summary.path.last().map(String::as_str),
+                Some("Send" | "Sync" | "Unpin" | "UnwindSafe" | "RefUnwindSafe" | "Freeze")
+            );
+            (id, (is_auto, summary.crate_id))
+        });
+    let known = krate
+        .index
+        .iter()
+        .filter_map(|(id, item)| match &item.inner {
+            ItemEnum::Trait(trait_) => Some((id, (trait_.is_auto, item.crate_id))),
+            _ => None,
+        });
+
+    external.chain(known).collect()
+}
+
+/// Name of the crate with the given id, as seen from the current crate
+pub(crate) fn crate_name<'krate>(
+    global_context: &GlobalContext<'krate>,
+    crate_id: u32,
+) -> &'krate str {
+    if crate_id == 0 {
+        global_context.krate_name
+    } else {
+        global_context
+            .krate
+            .external_crates
+            .get(&crate_id)
+            .map(|external_crate| external_crate.name.as_str())
+            .unwrap_or("unknown")
+    }
 }
 
 /// "Compute" a pretty-printed name for an [`Impl`]
@@ -159,10 +270,7 @@ pub(crate) fn name_of(impl_: &Impl) -> Result<String> {
 }
 
 /// Compute an somewhat unique HTML-Id for a for a given [`Item`]
-pub(crate) fn id<'krate>(
-    krate: &'krate Crate,
-    item: &'krate Item,
-) -> Option<(Cow<'krate, str>, HtmlId)> {
+pub(crate) fn id(item: &Item) -> Option<(Cow<'_, str>, HtmlId)> {
     if let Some(name) = &item.name {
         let (item_kind_name, is_file) = prefix_item(item)?;
 
@@ -175,31 +283,16 @@ pub(crate) fn id<'krate>(
         ))
     } else if let ItemEnum::Impl(impl_) = &item.inner {
         let name = name_of(impl_).ok()?;
-        let mut id = String::new();
+        let mut id = String::from("impl-");
 
-        let mut should_insert_tiret = false;
-        for token in pp::Tokens::from_item(item, &krate.index).unwrap().iter() {
-            match token {
-                pp::Token::Ponct(_) | pp::Token::Special(pp::SpecialToken::Space) => {
-                    should_insert_tiret = true
-                }
-                pp::Token::Ident(ident, _) => {
-                    if should_insert_tiret {
-                        id.push('-');
-                        should_insert_tiret = false;
-                    }
-                    id.push_str(ident)
-                }
-                pp::Token::Kw(kw) => {
-                    if should_insert_tiret {
-                        id.push('-');
-                        should_insert_tiret = false;
-                    }
-                    id.push_str(kw)
-                }
-                _ => {}
+        if let Some(trait_) = &impl_.trait_ {
+            if impl_.negative {
+                id.push_str("not-");
             }
+            push_stable_path(&mut id, trait_);
+            id.push_str("-for-");
         }
+        push_stable_type(&mut id, &impl_.for_);
 
         Some((Cow::Owned(name), HtmlId::new(id)))
     } else {
@@ -207,6 +300,253 @@ pub(crate) fn id<'krate>(
     }
 }
 
+/// HTML-Id rustdoc gives to an associated item (without the page disambiguation),
+/// used with `--rustdoc-compat-urls`
+pub(crate) fn rustdoc_id(item: &Item) -> Option<HtmlId> {
+    let name = item.name.as_ref()?;
+    let prefix = match &item.inner {
+        ItemEnum::Function(function) if function.has_body => "method",
+        ItemEnum::Function(_) => "tymethod",
+        ItemEnum::AssocConst { .. } => "associatedconstant",
+        _ => prefix_item(item)?.0,
+    };
+    Some(HtmlId::new(format!("{}.{}", prefix, name)))
+}
+
+/// HTML-Id rustdoc gives to an impl (without the page disambiguation), like
+/// `impl-From%3CT%3E-for-Foo`, used with `--rustdoc-compat-urls`
+pub(crate) fn rustdoc_impl_id(impl_: &Impl) -> Option<HtmlId> {
+    // Printed like rustdoc does, the paths by their last segment
+    fn print(type_: &Type) -> Option<String> {
+        let short;
+        let type_ = match type_ {
+            Type::ResolvedPath(path) if path.name.contains("::") => {
+                short = Type::ResolvedPath(Path {
+                    name: path.name.rsplit("::").next()?.to_string(),
+                    ..path.clone()
+                });
+                &short
+            }
+            type_ => type_,
+        };
+        Some(pp::Tokens::from_type(type_).ok()?.to_string())
+    }
+
+    let id = match &impl_.trait_ {
+        Some(trait_) => format!(
+            "impl-{}-for-{}",
+            print(&Type::ResolvedPath(trait_.clone()))?,
+            print(&impl_.for_)?
+        ),
+        None => format!("impl-{}", print(&impl_.for_)?),
+    };
+
+    // Like the `small_url_encode` of rustdoc
+    let mut encoded = String::with_capacity(id.len());
+    for c in id.chars() {
+        match c {
+            '<' => encoded.push_str("%3C"),
+            '>' => encoded.push_str("%3E"),
+            ' ' => encoded.push('-'),
+            '?' => encoded.push_str("%3F"),
+            '\'' => encoded.push_str("%27"),
+            '%' => encoded.push_str("%25"),
+            ',' => encoded.push_str("%2C"),
+            '"' => encoded.push_str("%22"),
+            '#' => encoded.push_str("%23"),
+            '&' => encoded.push_str("%26"),
+            ';' => encoded.push_str("%3B"),
+            c => encoded.push(c),
+        }
+    }
+    Some(HtmlId::new(encoded))
+}
+
+/// Compute the HTML-Id previously used for an [`Impl`] (derived from its whole
+/// signature), so that inbound links to it can still be redirected
+pub(crate) fn legacy_impl_id(krate: &Crate, item: &Item) -> Option<HtmlId> {
+    let mut id = String::new();
+
+    let mut should_insert_tiret = false;
+    for token in pp::Tokens::from_item(item, &krate.index).ok()?.iter() {
+        match token {
+            pp::Token::Ponct(_) | pp::Token::Special(pp::SpecialToken::Space) => {
+                should_insert_tiret = true
+            }
+            pp::Token::Ident(ident, _) => {
+                if should_insert_tiret {
+                    id.push('-');
+                    should_insert_tiret = false;
+                }
+                id.push_str(ident)
+            }
+            pp::Token::Kw(kw) => {
+                if should_insert_tiret {
+                    id.push('-');
+                    should_insert_tiret = false;
+                }
+                id.push_str(kw)
+            }
+            _ => {}
+        }
+    }
+
+    Some(HtmlId::new(id))
+}
+
+/// Push the last segment of a path, ignoring it's generic arguments
+fn push_stable_path(id: &mut String, path: &Path) {
+    id.push_str(path.name.rsplit("::").next().unwrap_or(&path.name));
+}
+
+/// Push a normalized form of a type: paths without generic arguments, lifetimes
+/// and other details ignored, so that it doesn't change when generics are added
+fn push_stable_type(id: &mut String, type_: &Type) {
+    match type_ {
+        Type::ResolvedPath(path) => push_stable_path(id, path),
+        Type::DynTrait(dyn_trait) => {
+            id.push_str("dyn");
+            for poly_trait in &dyn_trait.traits {
+                id.push('-');
+                push_stable_path(id, &poly_trait.trait_);
+            }
+        }
+        Type::Generic(name) | Type::Primitive(name) => id.push_str(name),
+        Type::FunctionPointer(_) => id.push_str("fn"),
+        Type::Tuple(types) => {
+            id.push_str("tuple");
+            for type_ in types {
+                id.push('-');
+                push_stable_type(id, type_);
+            }
+        }
+        Type::Slice(type_) => {
+            id.push_str("slice-");
+            push_stable_type(id, type_);
+        }
+        Type::Array { type_, .. } => {
+            id.push_str("array-");
+            push_stable_type(id, type_);
+        }
+        Type::Pat { type_, .. } => push_stable_type(id, type_),
+        Type::ImplTrait(_) => id.push_str("impl"),
+        Type::Infer => id.push('_'),
+        Type::RawPointer { mutable, type_ } => {
+            id.push_str(if *mutable { "ptr-mut-" } else { "ptr-" });
+            push_stable_type(id, type_);
+        }
+        Type::BorrowedRef { mutable, type_, .. } => {
+            id.push_str(if *mutable { "ref-mut-" } else { "ref-" });
+            push_stable_type(id, type_);
+        }
+        Type::QualifiedPath { name, .. } => id.push_str(name),
+    }
+}
+
+/// Name of a static file in the output, fingerprinted with its content
+pub(crate) fn fingerprinted(name: &str, content: &[u8]) -> String {
+    // FNV-1a, stable between the builds of rd unlike the std hasher
+    let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    match name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}.{:08x}.{}", stem, hash as u32, extension),
+        None => format!("{}.{:08x}", name, hash as u32),
+    }
+}
+
+/// Names of the static files in the output, fingerprinted with their content
+pub(crate) struct StaticFileNames(BTreeMap<&'static str, String>);
+
+impl StaticFileNames {
+    pub(crate) fn new() -> Self {
+        StaticFileNames(
+            STATIC_FILES
+                .iter()
+                .map(|file| (file.name, fingerprinted(file.name, file.content)))
+                .collect(),
+        )
+    }
+
+    /// Fingerprinted name of a static file
+    pub(crate) fn get(&self, file: &StaticFile) -> &str {
+        &self.0[file.name]
+    }
+}
+
+/// Is this path component a page, possibly with an anchor (like `index.html#struct.Foo`)
+fn is_html_file(component: &str) -> bool {
+    component.ends_with(".html") || component.contains(".html#")
+}
+
+/// Text of a span in its source: the lines are 1-based and the columns are 0-based, in
+/// chars, the end being exclusive; the following lines are unindented like the first one,
+/// and `None` when the source doesn't end with the `}` of a body
+pub(crate) fn span_source(source: &str, span: &Span) -> Option<String> {
+    let (first, last) = (span.begin.0.checked_sub(1)?, span.end.0.checked_sub(1)?);
+    let count = (last + 1).checked_sub(first)?;
+    let lines = source.lines().skip(first).take(count).collect::<Vec<_>>();
+    if lines.len() != count {
+        return None;
+    }
+
+    let mut text = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let end = if index + 1 == lines.len() {
+            span.end.1
+        } else {
+            usize::MAX
+        };
+        let line: String = if index == 0 {
+            line.chars().take(end).skip(span.begin.1).collect()
+        } else {
+            let indent = line
+                .chars()
+                .take(span.begin.1)
+                .take_while(|c| c.is_whitespace())
+                .count();
+            text.push('\n');
+            line.chars().take(end).skip(indent).collect()
+        };
+        text.push_str(&line);
+    }
+
+    text.ends_with('}').then_some(text)
+}
+
+/// Page of an item from the `--path-template`, given the path of its module (crate first),
+/// its kind and its name, the empty segments (like an empty `{path}`) being dropped
+pub(crate) fn templated_path<T: AsRef<str>>(
+    template: &str,
+    modules: &[T],
+    kind: &str,
+    name: &str,
+) -> PathBuf {
+    let (krate, modules) = modules
+        .split_first()
+        .map_or(("", &[][..]), |(krate, modules)| (krate.as_ref(), modules));
+    let path = modules
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    template
+        .replace("{crate}", krate)
+        .replace("{path}", &path)
+        .replace("{kind}", kind)
+        .replace("{name}", name)
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Page of an item rendered on the page of its module (`--module-single-page`): the anchor
+/// of the item on the `index.html` of the module, named like its own page would be
+pub(crate) fn inlined_page(filename: &str) -> String {
+    format!("index.html#{}", filename.trim_end_matches(".html"))
+}
+
 /// Create a relative path from a base one and a target
 pub(crate) fn relative(base: &StdPath, url: &StdPath) -> PathBuf {
     let mut relative = PathBuf::new();
@@ -215,9 +555,7 @@ pub(crate) fn relative(base: &StdPath, url: &StdPath) -> PathBuf {
     // maybe try the url crate ?
     let ends_with_html = |c: &std::path::Component| -> bool {
         match c {
-            std::path::Component::Normal(path) => {
-                path.to_str().map(|s| s.ends_with(".html")).unwrap_or(false)
-            }
+            std::path::Component::Normal(path) => path.to_str().is_some_and(is_html_file),
             _ => false,
         }
     };
@@ -270,7 +608,7 @@ pub(crate) fn top_of(base: &StdPath) -> PathBuf {
     for base_path_segment in base.components() {
         // Skip empty last segments
         if let std::path::Component::Normal(s) = base_path_segment {
-            if s.is_empty() || s.to_str().map(|s| s.ends_with(".html")).unwrap_or(false) {
+            if s.is_empty() || s.to_str().is_some_and(is_html_file) {
                 break;
             }
         }
@@ -295,43 +633,59 @@ pub(super) fn href<'context, 'krate>(
     let to = global_context.krate.paths.get(id);
 
     if to.is_none() {
-        // TODO: Here we wrongly supposed that we are in the same "page"
-        if let Some(item) = global_context.krate.index.get(id) {
-            match &item.inner {
-                ItemEnum::Function { .. } => {
-                    return Some((
-                        None,
-                        "".into(),
-                        Some(format!("method.{}", item.name.as_ref().unwrap())),
-                        "method",
-                    ))
+        // Associated items of a trait live on the page of the trait
+        if let Some(trait_id) = global_context.trait_items.get(id) {
+            let item = global_context.krate.index.get(id)?;
+            let foreign = !global_context.krate.index.contains_key(trait_id);
+            let fragment = match &item.inner {
+                // Foreign traits are documented by rustdoc, which tells apart the required
+                // methods from the provided ones
+                ItemEnum::Function(_) if foreign => {
+                    let prefix = match global_context.provided_methods.contains(id) {
+                        true => "method",
+                        false => "tymethod",
+                    };
+                    HtmlId::new(format!("{}.{}", prefix, item.name.as_ref()?))
                 }
-                ItemEnum::AssocType { .. } => {
-                    return Some((
-                        None,
-                        "".into(),
-                        Some(format!("associatedtype.{}", item.name.as_ref().unwrap())),
-                        "associatedtype",
-                    ))
-                }
-                ItemEnum::AssocConst { .. } => {
-                    return Some((
-                        None,
-                        "".into(),
-                        Some(format!("associatedconst.{}", item.name.as_ref().unwrap())),
-                        "associatedconst",
-                    ))
-                }
-                ItemEnum::TypeAlias(..) => {
-                    return Some((
-                        None,
-                        "".into(),
-                        Some(format!("type.{}", item.name.as_ref().unwrap())),
-                        "type",
-                    ))
+                _ if foreign || global_context.opt.rustdoc_compat_urls => rustdoc_id(item)?,
+                _ => self::id(item)?.1,
+            };
+            let (external_crate_url, path, _, _) = href(global_context, page_context, trait_id)?;
+            // The trait rendered on the page of its module prefixes the ids of its items by its
+            // anchor
+            let (path, fragment) = match path.to_str().and_then(|path| path.split_once('#')) {
+                Some((page, anchor)) => {
+                    (page.into(), HtmlId::new(format!("{}.{}", anchor, fragment)))
                 }
+                None => (path, fragment),
+            };
+            let kind = match &item.inner {
+                ItemEnum::Function { .. } => "method",
+                ItemEnum::AssocType { .. } => "associatedtype",
+                ItemEnum::AssocConst { .. } => "associatedconst",
+                _ => return None,
+            };
+            return Some((external_crate_url, path, Some(fragment.to_string()), kind));
+        }
+
+        // TODO: Here we wrongly supposed that we are in the same "page"
+        if let Some(item) = global_context.krate.index.get(id) {
+            let kind = match &item.inner {
+                ItemEnum::Function { .. } => Some("method"),
+                ItemEnum::AssocType { .. } => Some("associatedtype"),
+                ItemEnum::AssocConst { .. } => Some("associatedconst"),
+                ItemEnum::TypeAlias(..) => Some("type"),
                 // _ => warn!("item={:?} not handling this kind of items", item),
-                _ => {}
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                // The id of the item when already rendered on the page, which tells apart the
+                // items of different impls sharing a name
+                let fragment = match page_context.assoc_ids.borrow().get(id) {
+                    Some(fragment) => fragment.clone(),
+                    None => format!("{}.{}", kind, item.name.as_ref().unwrap()),
+                };
+                return Some((None, "".into(), Some(fragment), kind));
             }
         } else {
             debug!(
@@ -353,23 +707,63 @@ pub(super) fn href<'context, 'krate>(
                 0
             })];
 
+        // The pages of the other crates are rendered by rustdoc
+        let is_external = global_context
+            .krate
+            .external_crates
+            .contains_key(&to.crate_id);
         let filename: PathBuf = if matches!(to.kind, ItemKind::Module) {
             "index.html".into()
         } else {
-            format!("{}.{}.html", to_kind, to.path[to.path.len() - 1]).into()
+            let page = if global_context.opt.rustdoc_compat_urls {
+                let prefix = rustdoc_page_prefix(to_kind, &to.kind);
+                format!("{}.{}.html", prefix, to.path[to.path.len() - 1])
+            } else {
+                format!("{}.{}.html", to_kind, to.path[to.path.len() - 1])
+            };
+            match global_context.opt.module_single_page && !is_external {
+                true => inlined_page(&page).into(),
+                false => page.into(),
+            }
         };
 
-        let mut dest = PathBuf::with_capacity(30);
-        dest.extend(parts);
-        dest.push(filename);
+        let dest = match &global_context.opt.path_template {
+            // Like rustdoc, the primitives and keywords at the root of their crate
+            _ if global_context.opt.rustdoc_compat_urls
+                && matches!(to.kind, ItemKind::Primitive | ItemKind::Keyword) =>
+            {
+                PathBuf::from(&to.path[0]).join(filename)
+            }
+            Some(template) if !is_external && to.path.len() > 1 => templated_path(
+                template,
+                &to.path[..to.path.len() - 1],
+                to_kind,
+                &to.path[to.path.len() - 1],
+            ),
+            _ => {
+                let mut dest = PathBuf::with_capacity(30);
+                dest.extend(parts);
+                dest.push(filename);
+                dest
+            }
+        };
 
         //debug!(?dest, ?current_filepath, ?relative);
 
         let (external_crate_url, path) =
             if let Some(external_crate) = global_context.krate.external_crates.get(&to.crate_id) {
-                if let Some(html_root_url) = &external_crate.html_root_url {
+                let html_root_url = external_crate
+                    .html_root_url
+                    .as_ref()
+                    .or_else(|| global_context.external_urls.get(&to.crate_id));
+                if let Some(html_root_url) = html_root_url {
                     (Some(html_root_url), dest)
                 } else {
+                    *global_context
+                        .unresolved
+                        .borrow_mut()
+                        .entry(to.crate_id)
+                        .or_default() += 1;
                     return None;
                 }
             } else {
@@ -384,6 +778,186 @@ pub(super) fn href<'context, 'krate>(
     }
 }
 
+/// Inlining requested on a re-export: `Some(true)` for `#[doc(inline)]`
+/// and `Some(false)` for `#[doc(no_inline)]`
+pub(crate) fn doc_inlining<T: AsRef<str>>(attrs: &[T]) -> Option<bool> {
+    attrs
+        .iter()
+        .filter_map(|attr| {
+            attr.as_ref()
+                .strip_prefix("#[doc(")
+                .and_then(|rest| rest.strip_suffix(")]"))
+        })
+        .flat_map(|args| args.split(','))
+        .find_map(|arg| match arg.trim() {
+            "inline" => Some(true),
+            "no_inline" => Some(false),
+            _ => None,
+        })
+}
+
+/// Split the arguments of a `#[doc(..)]` attribute on the commas outside of the parentheses
+/// and strings
+fn doc_args(attr: &str) -> Vec<&str> {
+    let Some(args) = attr
+        .strip_prefix("#[doc(")
+        .and_then(|rest| rest.strip_suffix(")]"))
+    else {
+        return Vec::new();
+    };
+
+    let (mut depth, mut in_string, mut start) = (0, false, 0);
+    let mut split = Vec::new();
+    for (index, c) in args.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                split.push(args[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(args[start..].trim());
+    split
+}
+
+/// Value of a `key = "value"` argument
+fn doc_arg_value<'a>(arg: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = arg.split_once('=')?;
+    (name.trim() == key).then(|| value.trim().trim_matches('"'))
+}
+
+/// Keyword or primitive documented by a module with `#[doc(keyword = "..")]` or
+/// `#[doc(primitive = "..")]` (`#[rustc_doc_primitive = ".."]`), as `(kind, name)`
+pub(crate) fn special_module(item: &Item) -> Option<(&'static str, &str)> {
+    if !matches!(item.inner, ItemEnum::Module(_)) {
+        return None;
+    }
+
+    item.attrs.iter().find_map(|attr| {
+        if let Some(primitive) = attr
+            .strip_prefix("#[rustc_doc_primitive")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|rest| doc_arg_value(rest, ""))
+        {
+            return Some(("primitive", primitive));
+        }
+        doc_args(attr).into_iter().find_map(|arg| {
+            doc_arg_value(arg, "keyword")
+                .map(|keyword| ("keyword", keyword))
+                .or_else(|| {
+                    doc_arg_value(arg, "primitive").map(|primitive| ("primitive", primitive))
+                })
+        })
+    })
+}
+
+/// Aliases of an item, from `#[doc(alias = "..")]` and `#[doc(alias("..", ..))]`
+pub(crate) fn doc_aliases<T: AsRef<str>>(attrs: &[T]) -> Vec<&str> {
+    let mut aliases = Vec::new();
+    for arg in attrs.iter().flat_map(|attr| doc_args(attr.as_ref())) {
+        if let Some(alias) = doc_arg_value(arg, "alias") {
+            aliases.push(alias);
+        } else if let Some(list) = arg
+            .strip_prefix("alias")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            aliases.extend(list.split(',').map(|alias| alias.trim().trim_matches('"')));
+        }
+    }
+    aliases
+}
+
+/// Crate-level attributes shown as badges on the crate root page, with their explanation
+const CRATE_ATTRIBUTES: [(&str, &str); 4] = [
+    ("no_std", "Doesn't depend on the standard library"),
+    ("no_core", "Doesn't depend on the core library"),
+    (
+        "forbid(unsafe_code)",
+        "Contains no unsafe code, and can't allow it",
+    ),
+    (
+        "deny(unsafe_code)",
+        "Contains no unsafe code, unless explicitly allowed",
+    ),
+];
+
+/// Crate-level attributes of interest (`#![no_std]`, `#![forbid(unsafe_code)]`, ...) of the
+/// root module, as their name and explanation
+pub(crate) fn crate_attributes<T: AsRef<str>>(attrs: &[T]) -> Vec<(&'static str, &'static str)> {
+    let args: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| {
+            let attr = attr.as_ref();
+            attr.strip_prefix("#![")
+                .or_else(|| attr.strip_prefix("#["))
+                .and_then(|rest| rest.strip_suffix(']'))
+        })
+        .map(|arg| arg.split_whitespace().collect())
+        .collect();
+
+    CRATE_ATTRIBUTES
+        .iter()
+        .filter(|(attr, _)| args.iter().any(|arg| arg == attr))
+        .copied()
+        .collect()
+}
+
+/// Seconds since the Unix epoch the pages are dated of: `SOURCE_DATE_EPOCH` when set, else
+/// the Unix epoch itself when deterministic and the current time otherwise
+pub(crate) fn build_timestamp(deterministic: bool) -> Result<u64> {
+    if let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") {
+        return epoch
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid SOURCE_DATE_EPOCH {:?}", epoch));
+    }
+    if deterministic {
+        return Ok(0);
+    }
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .context("the current time is before the Unix epoch")?
+        .as_secs())
+}
+
+/// Year (UTC) of a timestamp in seconds since the Unix epoch
+pub(crate) fn year_of(timestamp: u64) -> u32 {
+    // Days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let days = timestamp / 86400 + 719468;
+    let (era, day_of_era) = (days / 146097, days % 146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    // The years start in March, so January and February belong to the next one
+    let year = era * 400 + year_of_era + u64::from(month_index >= 10);
+    year as u32
+}
+
+/// Version since which the item is available, from `#[stable(since = "..")]`
+/// or `#[doc(since = "..")]`
+pub(crate) fn since_version<T: AsRef<str>>(attrs: &[T]) -> Option<&str> {
+    attrs
+        .iter()
+        .filter_map(|attr| {
+            let attr = attr.as_ref();
+            attr.strip_prefix("#[stable(")
+                .or_else(|| attr.strip_prefix("#[doc("))
+                .and_then(|rest| rest.strip_suffix(")]"))
+        })
+        .flat_map(|args| args.split(','))
+        .find_map(|arg| {
+            let (key, value) = arg.split_once('=')?;
+            (key.trim() == "since").then(|| value.trim().trim_matches('"'))
+        })
+}
+
 pub(crate) struct Portability<'a> {
     original: &'a str,
     inner: &'a str,
@@ -421,3 +995,149 @@ impl<'a> Portability<'a> {
         ("The portability is definied by: ", self.original)
     }
 }
+
+pub(crate) struct MustUse<'a> {
+    reason: Option<Cow<'a, str>>,
+}
+
+impl<'a> MustUse<'a> {
+    pub(crate) fn from_attrs<T: AsRef<str>>(attrs: &'a [T]) -> Option<Self> {
+        let must_use = attrs.iter().map(AsRef::as_ref).find(|attr| {
+            attr.strip_prefix("#[must_use")
+                .map(|rest| rest.starts_with(']') || rest.trim_start().starts_with('='))
+                .unwrap_or(false)
+        })?;
+
+        // `#[must_use = "reason"]`: take everything between the first and last quote
+        let reason = match (must_use.find('"'), must_use.rfind('"')) {
+            (Some(start), Some(end)) if start < end => Some(unescape(&must_use[start + 1..end])),
+            _ => None,
+        };
+
+        Some(Self { reason })
+    }
+
+    pub(crate) fn render_long(self, item: &Item) -> (&'static str, Option<Cow<'a, str>>) {
+        (
+            match &item.inner {
+                ItemEnum::Function(_) => "This function's result must be used",
+                ItemEnum::Trait(_) => "Values of types implementing this trait must be used",
+                _ => "Values of this type must be used",
+            },
+            self.reason,
+        )
+    }
+}
+
+/// Content of a string literal, with its escapes (`\"`, `\\`, `\n`, ...) resolved
+fn unescape(literal: &str) -> Cow<'_, str> {
+    if !literal.contains('\\') {
+        return Cow::Borrowed(literal);
+    }
+
+    let mut content = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => content.push('\n'),
+                Some('t') => content.push('\t'),
+                Some('r') => content.push('\r'),
+                Some('0') => content.push('\0'),
+                Some(c) => content.push(c),
+                None => {}
+            },
+            c => content.push(c),
+        }
+    }
+    Cow::Owned(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn must_use_reasons() {
+        for (attr, reason) in [
+            ("#[must_use]", None),
+            ("#[must_use = \"plain\"]", Some("plain")),
+            (
+                "#[must_use = \"a \\\"quoted\\\" reason\"]",
+                Some("a \"quoted\" reason"),
+            ),
+            ("#[must_use = \"back\\\\slash\"]", Some("back\\slash")),
+        ] {
+            let attrs = [attr];
+            let must_use = MustUse::from_attrs(&attrs).unwrap();
+            assert_eq!(must_use.reason.as_deref(), reason, "{}", attr);
+        }
+    }
+
+    #[test]
+    fn input_crate_names() {
+        for (file, name) in [
+            ("doc/foo.json", Some("foo")),
+            ("foo.json.gz", Some("foo")),
+            ("foo.json.zst", Some("foo")),
+            ("foo", Some("foo")),
+            ("foo.gz", Some("foo")),
+            ("/", None),
+        ] {
+            assert_eq!(input_crate_name(StdPath::new(file)), name, "{}", file);
+        }
+    }
+
+    fn span(begin: (usize, usize), end: (usize, usize)) -> Span {
+        Span {
+            filename: "src/lib.rs".into(),
+            begin,
+            end,
+        }
+    }
+
+    const SOURCE: &str = "\
+pub trait Greet {
+    fn greet(&self) -> String {
+        format!(
+            \"héllo {}\",
+            self.name()
+        )
+    }
+
+    fn bye(&self) { println!(\"→ bye\") }
+}";
+
+    #[test]
+    fn span_source_multi_line() {
+        assert_eq!(
+            span_source(SOURCE, &span((2, 4), (7, 5))).as_deref(),
+            Some(
+                "fn greet(&self) -> String {\n    format!(\n        \"héllo {}\",\n        \
+                 self.name()\n    )\n}"
+            )
+        );
+    }
+
+    #[test]
+    fn span_source_non_ascii() {
+        // The columns are in chars, not bytes
+        assert_eq!(
+            span_source(SOURCE, &span((9, 4), (9, 42))).as_deref(),
+            Some("fn bye(&self) { println!(\"→ bye\") }")
+        );
+    }
+
+    #[test]
+    fn span_source_last_line() {
+        assert_eq!(
+            span_source(SOURCE, &span((1, 0), (10, 1))).as_deref(),
+            Some(SOURCE)
+        );
+        // Past the end of the source, or not ending with a body
+        assert_eq!(span_source(SOURCE, &span((9, 4), (11, 1))), None);
+        assert_eq!(span_source(SOURCE, &span((2, 4), (2, 30))), None);
+        assert_eq!(span_source(SOURCE, &span((3, 0), (2, 0))), None);
+        assert_eq!(span_source(SOURCE, &span((0, 0), (1, 0))), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! Structural validation of the written HTML, in debug builds
+
+use std::io::{self, Write};
+
+use tracing::warn;
+
+/// Elements without content nor end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is not parsed as HTML
+const RAW_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Writer checking that the tags of the HTML written through it are balanced, reporting
+/// the stray and unclosed ones of the page of `path` as warnings
+pub(super) struct Validate<W: Write> {
+    inner: W,
+    enabled: bool,
+    /// Rust path of the item of the page, for the reports
+    path: String,
+    /// Tag being written, from its `<`
+    tag: Vec<u8>,
+    quote: Option<u8>,
+    /// Raw element we are in
+    raw: Option<&'static str>,
+    /// Elements opened and not yet closed, with the line of their start tag
+    open: Vec<(String, usize)>,
+    line: usize,
+    /// Errors reported so far
+    errors: Vec<String>,
+}
+
+impl<W: Write> Validate<W> {
+    pub(super) fn new(inner: W, path: &str, enabled: bool) -> Self {
+        Validate {
+            inner,
+            enabled,
+            path: path.to_string(),
+            tag: Vec::new(),
+            quote: None,
+            raw: None,
+            open: Vec::new(),
+            line: 1,
+            errors: Vec::new(),
+        }
+    }
+
+    fn end_tag(&mut self) {
+        let tag = std::mem::take(&mut self.tag);
+        // Comments and doctype
+        if tag.starts_with(b"<!") || tag.starts_with(b"<?") {
+            return;
+        }
+
+        let (closing, rest) = match tag[1..].strip_prefix(b"/") {
+            Some(rest) => (true, rest),
+            None => (false, &tag[1..]),
+        };
+        let name = rest
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'-')
+            .map(|b| b.to_ascii_lowercase() as char)
+            .collect::<String>();
+
+        if let Some(raw) = self.raw {
+            if closing && name == raw {
+                self.raw = None;
+                self.open.pop();
+            }
+        } else if name.is_empty() {
+            self.report(format_args!(
+                "malformed tag {:?}",
+                String::from_utf8_lossy(&tag)
+            ));
+        } else if closing {
+            match self.open.iter().rposition(|(open, _)| *open == name) {
+                Some(index) => {
+                    for (unclosed, line) in self.open.split_off(index).into_iter().skip(1).rev() {
+                        self.report(format_args!(
+                            "<{}> of line {} closed by </{}>",
+                            unclosed, line, name
+                        ));
+                    }
+                }
+                None => self.report(format_args!("stray </{}>", name)),
+            }
+        } else if !VOID_ELEMENTS.contains(&name.as_str()) && !tag.ends_with(b"/>") {
+            self.raw = RAW_ELEMENTS.iter().copied().find(|raw| *raw == name);
+            self.open.push((name, self.line));
+        }
+    }
+
+    /// Report the elements still opened, at the end of the page
+    fn close(&mut self) {
+        for (unclosed, line) in std::mem::take(&mut self.open) {
+            self.report(format_args!(
+                "<{}> of line {} is never closed",
+                unclosed, line
+            ));
+        }
+    }
+
+    fn report(&mut self, error: std::fmt::Arguments<'_>) {
+        let error = format!("line {}: {}", self.line, error);
+        warn!("invalid html on the page of {}, {}", self.path, error);
+        self.errors.push(error);
+    }
+}
+
+/// Check the tags of some HTML written outside of a page file (like the items inlined on the
+/// page of their module or the fragments), in debug builds
+pub(super) fn validate_html(html: &str, path: &str) {
+    // Writing to a sink can't fail
+    let _ = Validate::new(io::sink(), path, cfg!(debug_assertions)).write_all(html.as_bytes());
+}
+
+impl<W: Write> Write for Validate<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if !self.enabled {
+            return Ok(written);
+        }
+
+        for &b in &buf[..written] {
+            if b == b'\n' {
+                self.line += 1;
+            }
+            // In a raw element, a `<` restarts the tag: the previous one was some text
+            if b == b'<' && (self.tag.is_empty() || self.raw.is_some()) {
+                self.tag.clear();
+                self.tag.push(b);
+            } else if !self.tag.is_empty() {
+                self.tag.push(b);
+                if self.tag.starts_with(b"<!--") {
+                    if self.tag.len() >= 7 && self.tag.ends_with(b"-->") {
+                        self.tag.clear();
+                    }
+                    continue;
+                }
+                match (self.quote, b) {
+                    (Some(quote), _) if quote == b => self.quote = None,
+                    (None, b'"' | b'\'') if self.raw.is_none() => self.quote = Some(b),
+                    (None, b'>') => self.end_tag(),
+                    _ => {}
+                }
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Validate<W> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Errors of the HTML written in these chunks
+    fn errors(chunks: &[&str]) -> Vec<String> {
+        let mut validate = Validate::new(io::sink(), "demo", true);
+        for chunk in chunks {
+            validate.write_all(chunk.as_bytes()).unwrap();
+        }
+        validate.close();
+        std::mem::take(&mut validate.errors)
+    }
+
+    #[test]
+    fn balanced() {
+        let html = "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><link rel=stylesheet>\
+                    </head><body><DIV class=a>text<br><img src=x /><p>a</p></div></body></html>";
+        assert!(errors(&[html]).is_empty());
+        // Split anywhere across the writes
+        let chunks = html
+            .split_inclusive(['<', '"', '=', ' '])
+            .collect::<Vec<_>>();
+        assert!(errors(&chunks).is_empty());
+    }
+
+    #[test]
+    fn quoted_attributes() {
+        assert!(errors(&["<a title=\"a > b </a>\" data-x='<p>'>x</a>"]).is_empty());
+        assert!(errors(&["<a title=\"a ", "> </i>", "\">x</a>"]).is_empty());
+    }
+
+    #[test]
+    fn comments() {
+        assert!(errors(&["<p><!-- <div> </p> --></p>"]).is_empty());
+        assert!(errors(&["<p><!-", "- </p> -", "-></p><!---->"]).is_empty());
+        assert!(errors(&["<?xml version=\"1.0\"?><p></p>"]).is_empty());
+    }
+
+    #[test]
+    fn raw_elements() {
+        assert!(errors(&["<script>if (a < b && c > d) { x = \"</div>\" }</script>"]).is_empty());
+        assert!(errors(&["<style>a > b { }</style><title>a <b></title>"]).is_empty());
+        assert!(errors(&["<script>a <", "/script>"]).is_empty());
+        assert!(errors(&["<script>a <</script>"]).is_empty());
+        assert_eq!(
+            errors(&["<script>a"]),
+            ["line 1: <script> of line 1 is never closed"]
+        );
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert_eq!(
+            errors(&["<p>a</div>"]),
+            [
+                "line 1: stray </div>",
+                "line 1: <p> of line 1 is never closed"
+            ]
+        );
+        assert_eq!(
+            errors(&["<div>\n<span>a\n</div>"]),
+            ["line 3: <span> of line 2 closed by </div>"]
+        );
+        assert_eq!(
+            errors(&["<p>a < b</p>"]),
+            [
+                "line 1: malformed tag \"< b</p>\"",
+                "line 1: <p> of line 1 is never closed"
+            ]
+        );
+    }
+}
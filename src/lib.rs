@@ -0,0 +1,4 @@
+//! Library part of rd, the plain-text signatures of the items of a rustdoc json
+//! output are available from the [`pp`] module.
+
+pub mod pp;
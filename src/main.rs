@@ -1,81 +1,934 @@
 use anyhow::{Context as _, Result};
-use clap::Parser;
-use log::{info, LevelFilter};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use rustdoc_types::*;
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, IsTerminal as _, Read, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tracing::level_filters::LevelFilter;
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
 
+use rd::pp;
+
+mod config;
+mod extract;
+#[cfg(test)]
+mod golden;
 mod html;
-mod pp;
+mod merge;
+mod metadata;
+mod output;
+mod serve;
+mod summary;
+mod watch;
+
+/// Input file standing for the standard input
+pub(crate) const STDIN: &str = "-";
+/// Magic numbers of the compressed inputs
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
 
 /// Experimental frontend for the rustdoc json output format
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(
+    after_help = "Exit codes: 2 for the invalid arguments, 3 when an input file is not \
+                        found, 4 when an input can't be deserialized, 5 when a crate fails to \
+                        render and 1 for the other errors"
+)]
+struct Cli {
+    #[command(flatten)]
+    opt: Option<Opt>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render the documentation and serve it over http
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        #[command(flatten)]
+        opt: Opt,
+    },
+    /// Only refresh the search index of already rendered documentation, without rendering
+    /// the pages again
+    IndexOnly {
+        #[command(flatten)]
+        opt: Opt,
+    },
+    /// Print a plain-text outline of the public API
+    Summary {
+        /// Rustdoc json input file to summarize, `-` for the standard input
+        #[arg(name = "FILE")]
+        file: PathBuf,
+
+        /// What to print for each item
+        #[arg(long, value_enum, default_value_t = SummaryFormat::Outline)]
+        format: SummaryFormat,
+    },
+    /// Print the path, kind, signature and documentation of each item, for other tools
+    Extract {
+        /// Rustdoc json input file to extract, `-` for the standard input
+        #[arg(name = "FILE")]
+        file: PathBuf,
+
+        /// Format of the output
+        #[arg(long, value_enum, default_value_t = ExtractFormat::Jsonl)]
+        format: ExtractFormat,
+    },
+}
+
+#[derive(Args, Debug)]
 pub(crate) struct Opt {
     // The number of occurrences of the `v/verbose` flag
     /// Verbose mode (-v, -vv, -vvv, etc.)
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
 
+    /// Quiet mode, only logging the warnings and the errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of the logs
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
+
+    /// Per-module log filter directives (e.g. `rd::html::markdown=debug,rd::watch=off`),
+    /// applied on top of the verbosity; `RUST_LOG` is used when not given
+    #[arg(long, value_name = "DIRECTIVES")]
+    log_filter: Option<String>,
+
     /// Open the generated documentation if successful
     #[arg(long)]
     open: bool,
 
+    /// Also write per-item JSON fragments (signature, docs and toc) under `fragments/`
+    #[arg(long)]
+    fragments: bool,
+
+    /// Keep the headings of the docs at their level, instead of moving them one level down
+    /// below the title of the page (the `h6` staying `h6`)
+    #[arg(long)]
+    no_heading_shift: bool,
+
+    /// Render ```mermaid code blocks as diagrams, with mermaid bundled in the output from the
+    /// `mermaid.min.js` of `--vendored-assets` (`bundled`, the default) or loaded from its CDN
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "bundled"
+    )]
+    mermaid: Option<MermaidSource>,
+
+    /// Keep the raw HTML of the docs as is, instead of escaping the tags and dropping the
+    /// attributes that aren't on the allowlist (only for trusted crates)
+    #[arg(long)]
+    allow_raw_html: bool,
+
+    /// Link the inline code spans whose text is one of the intra-doc links of the item, like
+    /// `` `Foo` `` when ``[`Foo`]`` is linked elsewhere in its docs
+    #[arg(long)]
+    link_code_spans: bool,
+
+    /// Features selected (comma separated): the items whose `#[cfg(feature = "..")]` don't
+    /// match are annotated, or hidden with `--cfg-mode filter`
+    #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+    features: Vec<String>,
+
+    /// Select all the features
+    #[arg(long, conflicts_with = "features")]
+    all_features: bool,
+
+    /// Cfg selected, like `unix` or `target_os="linux"` (can be repeated); the other
+    /// cfgs are then considered disabled
+    #[arg(long, value_name = "SPEC")]
+    cfg: Vec<String>,
+
+    /// What to do with the items not matching `--features`, `--all-features` and `--cfg`
+    #[arg(long, value_enum, default_value_t = CfgMode::Annotate)]
+    cfg_mode: CfgMode,
+
+    /// Also index the documentation prose, searchable from an "In docs" tab
+    #[arg(long)]
+    full_text_search: bool,
+
+    /// Formats of the search index: `js` (used by the search of the pages), `json` and
+    /// `binary` (for the external tools)
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "js")]
+    search_index_format: Vec<IndexFormat>,
+
+    /// Only write the search index (set by the `index-only` command)
+    #[arg(skip)]
+    index_only: bool,
+
+    /// Watch the input files and re-render the documentation when they change
+    #[arg(long)]
+    watch: bool,
+
+    /// Start a livereload server on this port that reloads the pages after each re-render
+    #[arg(long, value_name = "PORT", requires = "watch")]
+    livereload: Option<u16>,
+
+    /// Show the crate metadata (version, links, license, features, ...) on the crate page,
+    /// read from the output of `cargo metadata --format-version 1` or from a `Cargo.toml`
+    #[arg(long, value_name = "PATH")]
+    cargo_metadata: Option<PathBuf>,
+
+    /// Descriptions of the cargo features (a TOML table of `feature = "description"`) for
+    /// the table of the features on the crate page, taking precedence over the `Features`
+    /// section of the crate docs
+    #[arg(long, value_name = "FILE")]
+    features_file: Option<PathBuf>,
+
+    /// Link the items of the dependencies without `html_root_url` to docs.rs, at the version
+    /// found with `--cargo-metadata` (or else the latest one)
+    #[arg(long)]
+    docs_rs_fallback: bool,
+
+    /// Render the blanket impls once, on a page shared by all the types of the crate,
+    /// and only link to them from the type pages
+    #[arg(long)]
+    shared_blanket_impls: bool,
+
+    /// Simplify the bounds of the signatures: elide the implicit `Sized` of the type
+    /// parameters and the duplicated bounds, and fold the where clauses on the same type
+    #[arg(long)]
+    simplify_bounds: bool,
+
+    /// Where to print the bounds of the type parameters in the signatures
+    #[arg(long, value_enum, default_value_t = Bounds::AsWritten)]
+    bounds_placement: Bounds,
+
+    /// Don't add `<wbr>` break opportunities (after `::`, `<` and `,`) in the signatures
+    #[arg(long)]
+    no_wbr: bool,
+
+    /// Name the pages and the anchors of the associated items like rustdoc does
+    /// (`method.foo`, `tymethod.foo`, `derive.Foo.html`, `traitalias.Foo.html`, ...), so that
+    /// the output can replace the one of rustdoc without breaking the links to it
+    #[arg(long)]
+    rustdoc_compat_urls: bool,
+
+    /// Render the items of a module on the page of the module (at the `#struct.Foo` anchors)
+    /// instead of on pages of their own
+    #[arg(long)]
+    module_single_page: bool,
+
+    /// Show the default bodies of the provided trait methods, read from the sources of the
+    /// crate under this directory (the one rustdoc was run from, the current one by default)
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "."
+    )]
+    include_source: Option<PathBuf>,
+
+    /// Path of the pages of the items and modules in the output, like
+    /// `{crate}/{path}/{kind}/{name}.html`, from `{crate}`, `{path}` (the modules, separated by
+    /// `/`, telling apart the items of the same name), `{kind}` and `{name}`; the page of the
+    /// crate stays `<crate>/index.html`
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = parse_path_template,
+        conflicts_with_all = ["module_single_page", "rustdoc_compat_urls"]
+    )]
+    path_template: Option<String>,
+
+    /// Run a compiled-in item processor adding a section to the page of each item, like
+    /// `source-location` (can be repeated)
+    #[arg(long = "processor", value_name = "NAME", value_parser = parse_processor)]
+    processors: Vec<String>,
+
+    /// Url where the documentation is published, like `https://docs.example.com`, to write an
+    /// OpenSearch descriptor of each crate so that the browsers can search in it (landing on
+    /// its `search.html` results page) and a `sitemap.xml` of its pages
+    #[arg(long, value_name = "URL", value_parser = parse_base_url)]
+    base_url: Option<String>,
+
+    /// Start the pages with a YAML front-matter (title, kind and path of the page), for the
+    /// static site generators
+    #[arg(long)]
+    front_matter: bool,
+
+    /// Directory with vendored copies of the third-party assets (`bootstrap-blackbox.min.css`,
+    /// `bootstrap-icons.css` and its `fonts/`, `bootstrap.min.js`, `darkmode.min.js` and
+    /// `mermaid.min.js` with `--mermaid`), copied in the output and used instead of the CDN
+    #[arg(long, value_name = "DIR")]
+    vendored_assets: Option<PathBuf>,
+
+    /// Minify the generated HTML pages (whitespace between blocks and runs of whitespace)
+    #[arg(long)]
+    minify: bool,
+
+    /// Render this README (markdown) above the documentation of the crate, its links
+    /// like `[Foo]` or `[crate::module::Bar]` pointing to the item pages
+    #[arg(long, value_name = "PATH")]
+    readme: Option<PathBuf>,
+
+    /// Append a markdown file to the documentation of a module, like `my_crate::guide=guide.md`
+    /// (can be repeated), its links like `[Foo]` or `[crate::module::Bar]` pointing to the
+    /// item pages
+    #[arg(long = "include-doc", value_name = "MODULE=FILE", value_parser = parse_include_doc)]
+    include_docs: Vec<(String, PathBuf)>,
+
+    /// Render only the subtree of this module, like `my_crate::module`, and its parent modules
+    /// (listing only it), leaving the pages and indexes of the whole crate as they are
+    #[arg(long, value_name = "MODULE", conflicts_with_all = ["clean", "prune"])]
+    only: Option<String>,
+
+    /// Directory of markdown chapters rendered as a guide in the `guide/` directory of each
+    /// crate (`index.md` or `README.md` being its introduction), their links like `[Foo]`
+    /// pointing to the item pages and like `other.md` to the other chapters
+    #[arg(long, value_name = "DIR")]
+    guide: Option<PathBuf>,
+
+    /// Group the derived trait impls (`#[derive(..)]`) in their own collapsed section
+    #[arg(long)]
+    group_derived: bool,
+
+    /// Order of the impls on the type pages
+    #[arg(long, value_enum, default_value_t = ImplOrder::Name)]
+    impl_order: ImplOrder,
+
+    /// Sections whose impls and items start expanded, the others start collapsed
+    /// [default: methods,trait-items]
+    #[arg(long, value_enum, value_delimiter = ',')]
+    open_sections: Vec<Section>,
+
+    /// Start collapsed the sections with more than this number of impls or items, even
+    /// the ones of `--open-sections`
+    #[arg(long, value_name = "N")]
+    collapse_threshold: Option<usize>,
+
+    /// Configuration file giving the default of `--open-sections` and `--collapse-threshold`
+    /// (`rd.toml` of the current directory when it exists)
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Classes of the tokens of the signatures (`[syntax-classes]` of the configuration)
+    #[arg(skip)]
+    syntax_classes: config::SyntaxClasses,
+
+    /// Keep rendering when an item fails, emitting a placeholder page for it,
+    /// and only exit with an error at the end
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Make the output byte-identical between runs: items sorted with a total order and the
+    /// pages dated of `SOURCE_DATE_EPOCH` (or else of the Unix epoch) instead of the current time
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Print rendering statistics (items per kind, bytes written, largest pages and
+    /// pretty-printed tokens) of each crate
+    #[arg(long, hide = true)]
+    stats: bool,
+
     /// Output directory of html files
     #[arg(short, long)]
     output: PathBuf,
 
-    /// Rustdoc json input file to process
-    #[arg(name = "FILE", required = true)]
+    /// Remove the files of previous renders from the output directory, instead of
+    /// only replacing the rendered crates and static files
+    #[arg(long)]
+    clean: bool,
+
+    /// Delete the files of the output directory that were not produced by this render,
+    /// like the crates removed or the static files of older versions, keeping the hidden
+    /// ones (like `.git`)
+    #[arg(long)]
+    prune: bool,
+
+    /// Write a `manifest.json` in the output directory listing every file with its SHA-256
+    /// and size, for the deployments only uploading the files that changed
+    #[arg(long)]
+    manifest: bool,
+
+    /// Rustdoc json input file to process, `-` for the standard input; it may be compressed
+    /// with gzip or zstd (`.json.gz`, `.json.zst`)
+    #[arg(name = "FILE", required_unless_present = "merge")]
     files: Vec<PathBuf>,
+
+    /// Rustdoc json of the crate built for a target, described by its cfg (like `unix=unix.json`
+    /// or `target_os="windows"=windows.json`); the ones given are merged into a single crate
+    /// whose items not available on all the targets are marked with the cfg of their targets
+    #[arg(long, value_name = "CFG=FILE")]
+    merge: Vec<String>,
 }
 
-fn main() -> Result<()> {
-    let opt = Opt::parse();
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogFormat {
+    /// Human readable logs
+    Pretty,
+    /// One json object per event, with the spans (item paths) it happened in
+    Json,
+}
 
-    env_logger::builder()
-        .filter_level(match opt.verbose {
-            0 => LevelFilter::Info,
-            1 => LevelFilter::Debug,
-            _ => LevelFilter::Trace,
-        })
-        .try_init()
-        .context("setting env logger failed")?;
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SummaryFormat {
+    /// The declarations, indented module by module
+    Outline,
+    /// One line per item: its path, a tabulation and its signature
+    Signatures,
+}
 
-    info!("creating the output directory: {:?}", &opt.output);
-    let _ = std::fs::create_dir(&opt.output);
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExtractFormat {
+    /// One json object per line: `{"path", "kind", "signature", "docs"}`
+    Jsonl,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CfgMode {
+    /// Render them with a notice
+    Annotate,
+    /// Don't render them
+    Filter,
+}
 
-    let outputs = opt
-        .files
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MermaidSource {
+    /// A fingerprinted copy of the vendored `mermaid.min.js`, next to the static files
+    Bundled,
+    /// The CDN of mermaid
+    Cdn,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub(crate) enum IndexFormat {
+    /// `search-index.js`, loaded by the search of the pages
+    Js,
+    /// `search-index.json`
+    Json,
+    /// `search-index.bin`
+    Binary,
+}
+
+/// Where to print the bounds, the [`pp::BoundsPlacement`] of the command line
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Bounds {
+    /// Like in the source code
+    AsWritten,
+    /// In the declaration of the parameters, like `<T: Clone>`
+    Inline,
+    /// In the where clauses, like `where T: Clone`
+    Where,
+}
+
+impl From<Bounds> for pp::BoundsPlacement {
+    fn from(bounds: Bounds) -> Self {
+        match bounds {
+            Bounds::AsWritten => pp::BoundsPlacement::AsWritten,
+            Bounds::Inline => pp::BoundsPlacement::Inline,
+            Bounds::Where => pp::BoundsPlacement::Where,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ImplOrder {
+    /// Sorted by name
+    Name,
+    /// In the order of the source code
+    Source,
+    /// Hand-written impls first, derived ones (`#[derive(..)]`) last
+    LocalFirst,
+}
+
+#[derive(ValueEnum, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Section {
+    /// Inherent methods of the type pages
+    Methods,
+    /// Required and provided items of the trait pages
+    TraitItems,
+    /// Trait implementations of the type pages
+    TraitImplementations,
+    /// Derived trait implementations of the type pages (`--group-derived`)
+    DerivedImplementations,
+    /// Auto trait implementations of the type pages
+    AutoTraitImplementations,
+    /// Blanket implementations of the type pages
+    BlanketImplementations,
+    /// Implementors of the trait pages
+    Implementors,
+}
+
+/// Failures of rd distinguished by their exit code, attached as the context of the errors
+#[derive(Debug)]
+pub(crate) enum Failure {
+    /// An input file doesn't exist or can't be opened
+    InputNotFound(PathBuf),
+    /// An input isn't a valid rustdoc json, with the location of the error
+    Deserialize(String),
+    /// A crate failed to render
+    Render(String),
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::InputNotFound(_) => 3,
+            Failure::Deserialize(_) => 4,
+            Failure::Render(_) => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::InputNotFound(file) => write!(f, "unable to open {}", file.display()),
+            Failure::Deserialize(message) | Failure::Render(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            report(&err);
+            ExitCode::from(err.downcast_ref::<Failure>().map_or(1, Failure::exit_code))
+        }
+    }
+}
+
+/// Print an error and its causes on the standard error, in color on a terminal
+fn report(err: &anyhow::Error) {
+    let mut stderr = std::io::stderr().lock();
+    let color = stderr.is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let (red, bold, reset) = if color {
+        ("\x1b[1;31m", "\x1b[1m", "\x1b[0m")
+    } else {
+        ("", "", "")
+    };
+
+    let _ = writeln!(stderr, "{}error{}: {}", red, reset, err);
+    for cause in err.chain().skip(1) {
+        let _ = writeln!(stderr, "  {}caused by{}: {}", bold, reset, cause);
+    }
+    if let std::backtrace::BacktraceStatus::Captured = err.backtrace().status() {
+        let _ = writeln!(stderr, "\n{}", err.backtrace());
+    }
+}
+
+/// Run the command given on the command line
+fn run() -> Result<()> {
+    let cli = Cli::parse();
+    let (mut opt, port) = match cli.command {
+        Some(Command::Serve { port, opt }) => (opt, Some(port)),
+        Some(Command::IndexOnly { mut opt }) => {
+            opt.index_only = true;
+            init_logging(&opt)?;
+            return index_all(&opt);
+        }
+        Some(Command::Summary { file, format }) => {
+            let krate = read_crate(&file)?;
+            let mut stdout = std::io::stdout().lock();
+            return match format {
+                SummaryFormat::Outline => summary::write(&krate, &mut stdout),
+                SummaryFormat::Signatures => summary::write_signatures(&krate, &mut stdout),
+            };
+        }
+        Some(Command::Extract { file, format }) => {
+            let krate = read_crate(&file)?;
+            let mut stdout = std::io::stdout().lock();
+            return match format {
+                ExtractFormat::Jsonl => extract::write_jsonl(&krate, &mut stdout),
+            };
+        }
+        None => (cli.opt.context("missing arguments")?, None),
+    };
+
+    init_logging(&opt)?;
+    config::apply(&mut opt)?;
+
+    if opt.watch && opt.files.iter().any(|file| file == Path::new(STDIN)) {
+        anyhow::bail!("the standard input can't be watched");
+    }
+
+    // Render into a staging directory, only published once everything went well
+    info!("creating the staging directory of {:?}", &opt.output);
+    let staging = output::Staging::create(&opt.output)?;
+    let output = std::mem::replace(&mut opt.output, staging.path().to_path_buf());
+    let rendered = render_all(&opt);
+    opt.output = output;
+
+    let (outputs, global_index, failed) = match rendered {
+        Ok(rendered) => rendered,
+        Err(err) => {
+            staging.discard()?;
+            return Err(err);
+        }
+    };
+    let outputs = outputs
         .iter()
-        .map(|file| {
-            info!("opening input file: {:?}", &file);
-            let reader = File::open(&file).context("The file provided doesn't exists")?;
-            let bufreader = BufReader::new(reader);
+        .map(|output| staging.published(output))
+        .collect::<Vec<_>>();
+    let global_index = staging.published(&global_index);
+    match opt.only {
+        Some(_) => staging.merge()?,
+        None => staging.publish(opt.clean, opt.prune)?,
+    }
+    if opt.manifest {
+        let files = output::write_manifest(&opt.output)?;
+        info!(
+            "wrote the manifest of {} file(s) in {:?}",
+            files, &opt.output
+        );
+    }
 
-            info!("starting deserialize of the file");
-            let krate: Crate = serde_json::from_reader(bufreader)
-                .context("Unable to deseriliaze the content of the file")?;
+    let index = match outputs[..] {
+        [ref module_index] => module_index,
+        _ => &global_index,
+    };
 
-            let krate_item = krate
-                .index
-                .get(&krate.root)
-                .context("Unable to find the crate item")?;
+    if let Some(port) = port {
+        let server = serve::Server::bind(&opt.output, port)?;
+        if opt.open {
+            open::that(server.url_of(index)?)?;
+        }
 
-            html::render::render(&opt, &krate, krate_item)
-        })
-        .collect::<Result<Vec<_>>>()?;
+        if opt.watch {
+            std::thread::spawn(move || server.run());
+            watch::watch(&opt)?;
+        } else {
+            server.run();
+        }
+    } else {
+        if opt.open {
+            open::that(index)?;
+        }
 
-    let global_index = html::render::render_global(&opt, &outputs)
+        if opt.watch {
+            watch::watch(&opt)?;
+        }
+    }
+
+    if failed > 0 {
+        return Err(Failure::Render(format!(
+            "{} of {} files failed to render",
+            failed,
+            opt.files.len()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Render all the input files and the global files, returning the index of each
+/// crate, the global index and the number of files that failed to render
+fn render_all(opt: &Opt) -> Result<(Vec<PathBuf>, PathBuf, usize)> {
+    let mut outputs: Vec<PathBuf> = Vec::with_capacity(opt.files.len());
+    let mut inputs: Vec<&PathBuf> = Vec::with_capacity(opt.files.len());
+    let mut failed = 0;
+    for file in &opt.files {
+        match render_file(opt, file) {
+            Ok(output) => {
+                // Each crate has its own directory, two inputs of the same crate would overwrite
+                // each other
+                if let Some(index) = outputs.iter().position(|other| *other == output) {
+                    anyhow::bail!(
+                        "{:?} and {:?} both render into the {:?} directory",
+                        inputs[index],
+                        file,
+                        output
+                            .parent()
+                            .and_then(Path::file_name)
+                            .unwrap_or_default()
+                    );
+                }
+                outputs.push(output);
+                inputs.push(file);
+            }
+            Err(err) if opt.keep_going => {
+                error!("{:#}", err);
+                failed += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if !opt.merge.is_empty() {
+        match render_merged(opt) {
+            Ok(output) => {
+                if let Some(index) = outputs.iter().position(|other| *other == output) {
+                    anyhow::bail!(
+                        "the merged crate and {:?} both render into the same directory",
+                        inputs[index]
+                    );
+                }
+                outputs.push(output);
+            }
+            Err(err) if opt.keep_going => {
+                error!("{:#}", err);
+                failed += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    let global_index = html::render::render_global(opt, &outputs)
         .context("Unable to write the global context (js, css, imgs, ...)")?;
+    Ok((outputs, global_index, failed))
+}
 
-    if opt.open {
-        open::that(match outputs[..] {
-            [ref module_index] => module_index,
-            _ => &global_index,
-        })?;
+/// Refresh the search index of all the input files, in place
+fn index_all(opt: &Opt) -> Result<()> {
+    let mut failed = 0;
+    for file in &opt.files {
+        match render_file(opt, file) {
+            Ok(_) => {}
+            Err(err) if opt.keep_going => {
+                error!("{:#}", err);
+                failed += 1;
+            }
+            Err(err) => return Err(err),
+        }
     }
 
+    if failed > 0 {
+        return Err(Failure::Render(format!(
+            "{} of {} files failed to index",
+            failed,
+            opt.files.len()
+        ))
+        .into());
+    }
     Ok(())
 }
+
+/// Check that a `--processor` names one of the compiled-in processors
+fn parse_processor(name: &str) -> Result<String, String> {
+    let processors = &html::plugins::PROCESSORS;
+    if processors.iter().any(|processor| processor.name() == name) {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "unknown processor, expected one of {}",
+            processors
+                .iter()
+                .map(|processor| processor.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+}
+
+/// Split a `--include-doc` into the path of the module and the markdown file
+fn parse_include_doc(include: &str) -> Result<(String, PathBuf), String> {
+    match include.split_once('=') {
+        Some((module, file)) if !module.is_empty() && !file.is_empty() => {
+            Ok((module.to_string(), PathBuf::from(file)))
+        }
+        _ => Err("expected MODULE=FILE".to_string()),
+    }
+}
+
+/// Check that a `--base-url` is an absolute http(s) url, without its trailing slashes
+fn parse_base_url(url: &str) -> Result<String, String> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err("expected an absolute http(s) url".to_string());
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// Check that a `--path-template` only uses the known placeholders and names html pages
+fn parse_path_template(template: &str) -> Result<String, String> {
+    const PLACEHOLDERS: [&str; 4] = ["{crate}", "{path}", "{kind}", "{name}"];
+
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end + 1)
+            .ok_or("unclosed `{` in the template")?;
+        if !PLACEHOLDERS.contains(&&rest[start..end]) {
+            return Err(format!(
+                "unknown placeholder `{}`, expected one of {}",
+                &rest[start..end],
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+        rest = &rest[end..];
+    }
+
+    // Without the modules, the items of the same name in different modules would clash
+    for placeholder in ["{path}", "{name}"] {
+        if !template.contains(placeholder) {
+            return Err(format!("the template must contain `{}`", placeholder));
+        }
+    }
+    // The pages must stay in the output directory
+    if template.starts_with(['/', '\\']) || Path::new(template).is_absolute() {
+        return Err("the template must be relative to the output directory".to_string());
+    }
+    if template.split(['/', '\\']).any(|segment| segment == "..") {
+        return Err("the template can't contain `..` segments".to_string());
+    }
+    if !template.ends_with(".html") {
+        return Err("the pages must end with `.html`".to_string());
+    }
+    Ok(template.to_string())
+}
+
+/// Install the global tracing subscriber according to the options
+fn init_logging(opt: &Opt) -> Result<()> {
+    let level = match opt.verbose {
+        _ if opt.quiet => LevelFilter::WARN,
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let directives = match &opt.log_filter {
+        Some(directives) => directives.clone(),
+        None => std::env::var(EnvFilter::DEFAULT_ENV).unwrap_or_default(),
+    };
+    let filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .parse(directives)
+        .context("invalid log filter directives")?;
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match opt.log_format {
+        LogFormat::Pretty => builder.try_init(),
+        LogFormat::Json => builder.json().with_current_span(false).try_init(),
+    }
+    .map_err(|err| anyhow::anyhow!(err))
+    .context("setting the logger failed")
+}
+
+/// Deserialize and render a single rustdoc json file
+pub(crate) fn render_file(opt: &Opt, file: &Path) -> Result<PathBuf> {
+    let mut krate = read_crate(file)?;
+    pp::rewrite_bounds(&mut krate, opt.simplify_bounds, opt.bounds_placement.into());
+    let krate_item = krate.index.get(&krate.root).with_context(|| {
+        Failure::Deserialize(format!("{}: unable to find the crate item", file.display()))
+    })?;
+
+    html::render::render(opt, &krate, krate_item)
+        .with_context(|| Failure::Render(format!("failed to render {}", file.display())))
+}
+
+/// Merge and render the rustdoc json files of the targets given with `--merge`
+fn render_merged(opt: &Opt) -> Result<PathBuf> {
+    let targets = opt
+        .merge
+        .iter()
+        .map(|target| {
+            let (cfg, file) = target
+                .rsplit_once('=')
+                .with_context(|| format!("invalid --merge {:?}: expected CFG=FILE", target))?;
+            Ok((cfg.to_string(), read_crate(Path::new(file))?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    info!("merging the json of {} targets", targets.len());
+    let mut krate = merge::merge(targets)?;
+    pp::rewrite_bounds(&mut krate, opt.simplify_bounds, opt.bounds_placement.into());
+    let krate_item = krate
+        .index
+        .get(&krate.root)
+        .context("Unable to find the crate item")?;
+
+    html::render::render(opt, &krate, krate_item)
+        .with_context(|| Failure::Render("failed to render the merged crate".to_string()))
+}
+
+/// Read and deserialize a rustdoc json file
+fn read_crate(file: &Path) -> Result<Crate> {
+    let content = read_input(file)?;
+
+    info!("starting deserialize of the file");
+    let mut deserializer = serde_json::Deserializer::from_slice(&content);
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+        let path = err.path().to_string();
+        let err = err.into_inner();
+        let mut message = format!(
+            "{}:{}:{}: unable to deserialize the content of the file",
+            file.display(),
+            err.line(),
+            err.column()
+        );
+        if err.is_data() && path != "." {
+            message.push_str(&format!(" at `{}`", path));
+        }
+
+        // The usual cause being a json generated by a different version of rustdoc
+        match format_version(&content) {
+            Some(found) if found != FORMAT_VERSION => message.push_str(&format!(
+                " (the file is in the format version {} but rd expects the version {}, \
+                 use a rustdoc and a rd that agree on it)",
+                found, FORMAT_VERSION
+            )),
+            _ => {}
+        }
+        anyhow::Error::new(err).context(Failure::Deserialize(message))
+    })
+}
+
+/// Format version of a rustdoc json file, if it has one
+fn format_version(content: &[u8]) -> Option<u32> {
+    #[derive(serde::Deserialize)]
+    struct Versioned {
+        format_version: u32,
+    }
+
+    serde_json::from_slice::<Versioned>(content)
+        .ok()
+        .map(|versioned| versioned.format_version)
+}
+
+/// Read the content of an input file, or of the standard input for `-`, decompressed when
+/// it's compressed with gzip or zstd
+fn read_input(file: &Path) -> Result<Vec<u8>> {
+    let mut reader: Box<dyn BufRead> = if file == Path::new(STDIN) {
+        info!("reading the standard input");
+        Box::new(std::io::stdin().lock())
+    } else {
+        info!("opening input file: {:?}", &file);
+        Box::new(BufReader::new(
+            File::open(file).with_context(|| Failure::InputNotFound(file.to_path_buf()))?,
+        ))
+    };
+
+    // Recognized by their magic number rather than by their extension, so that they are
+    // also decompressed from the standard input
+    let magic = reader.fill_buf().context("unable to read the input")?;
+    let mut reader: Box<dyn Read> = if magic.starts_with(&GZIP_MAGIC) {
+        info!("decompressing the gzip input");
+        Box::new(flate2::bufread::MultiGzDecoder::new(reader))
+    } else if magic.starts_with(&ZSTD_MAGIC) {
+        info!("decompressing the zstd input");
+        Box::new(
+            ruzstd::decoding::StreamingDecoder::new(reader)
+                .map_err(|err| anyhow::anyhow!("{}", err))
+                .context("invalid zstd input")?,
+        )
+    } else {
+        reader
+    };
+
+    let mut content = Vec::new();
+    reader
+        .read_to_end(&mut content)
+        .with_context(|| format!("unable to read {:?}", file))?;
+    Ok(content)
+}
@@ -0,0 +1,538 @@
+//! Merge of the rustdoc json of a crate built for several targets (`--merge`)
+//!
+//! The items are matched by their path, and their children (fields, variants, impls,
+//! associated items, ...) by their name or signature, the ones missing from some targets being
+//! tagged with the `#[cfg(..)]` of the targets that have them. The content of an item present
+//! in several targets (its signature, docs, ...) is taken from the first of them.
+
+use anyhow::{Context as _, Result};
+use rustdoc_types::{
+    Crate, Enum, Id, Impl, Item, ItemEnum, ItemKind, ItemSummary, Module, StructKind, Trait, Union,
+    VariantKind,
+};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::pp;
+
+/// Merge the crates, each one built for the target described by its cfg (like `unix` or
+/// `target_os = "windows"`)
+pub(crate) fn merge(targets: Vec<(String, Crate)>) -> Result<Crate> {
+    let mut targets = targets.into_iter();
+    let (base_cfg, mut base) = targets.next().context("no crate to merge")?;
+
+    let mut cfgs = vec![base_cfg];
+    // Targets having each local item, by id in the base crate
+    let mut presence: HashMap<Id, Vec<usize>> = base
+        .index
+        .values()
+        .filter(|item| item.crate_id == 0)
+        .map(|item| (item.id.clone(), vec![0]))
+        .collect();
+
+    for (cfg, other) in targets {
+        if other
+            .index
+            .get(&other.root)
+            .and_then(|root| root.name.as_ref())
+            != base
+                .index
+                .get(&base.root)
+                .and_then(|root| root.name.as_ref())
+        {
+            anyhow::bail!("unable to merge the json of different crates");
+        }
+
+        for id in Merger::new(&mut base, &other, cfgs.len()).run()? {
+            if base.index.get(&id).is_some_and(|item| item.crate_id == 0) {
+                presence.entry(id).or_default().push(cfgs.len());
+            }
+        }
+        cfgs.push(cfg);
+    }
+
+    // Tag the items that aren't available on all the targets, unless their parent already is
+    // with the same cfg
+    let parents: HashMap<&Id, &Id> = base
+        .index
+        .values()
+        .flat_map(|item| {
+            let lists = match &item.inner {
+                // The impls of a trait are the children of their type
+                ItemEnum::Trait(trait_) => vec![&trait_.items],
+                inner => children(inner),
+            };
+            let tuple_fields = tuple_fields(&item.inner).iter().flatten();
+            lists
+                .into_iter()
+                .flatten()
+                .chain(tuple_fields)
+                .map(move |child| (child, &item.id))
+        })
+        .collect();
+    let mut tags = Vec::new();
+    for (id, targets) in &presence {
+        if targets.len() == cfgs.len()
+            || parents.get(id).and_then(|parent| presence.get(*parent)) == Some(targets)
+        {
+            continue;
+        }
+        let cfg = match &targets[..] {
+            [target] => cfgs[*target].clone(),
+            targets => format!(
+                "any({})",
+                targets
+                    .iter()
+                    .map(|target| cfgs[*target].as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        tags.push((id.clone(), cfg));
+    }
+    for (id, cfg) in tags {
+        if let Some(item) = base.index.get_mut(&id) {
+            add_cfg(item, &cfg);
+        }
+    }
+
+    Ok(base)
+}
+
+/// Add a cfg to an item, combined with its own `#[cfg(..)]` if any
+fn add_cfg(item: &mut Item, cfg: &str) {
+    let own = item
+        .attrs
+        .iter()
+        .position(|attr| attr.starts_with("#[cfg("));
+    match own {
+        Some(index) => {
+            let own = &item.attrs[index]["#[cfg(".len()..item.attrs[index].len() - ")]".len()];
+            item.attrs[index] = format!("#[cfg(all({}, {}))]", cfg, own);
+        }
+        None => item.attrs.insert(0, format!("#[cfg({})]", cfg)),
+    }
+}
+
+/// Lists of the children of an item (module items, fields, variants, impls, associated
+/// items), the tuple fields being apart in [`tuple_fields`]
+fn children(inner: &ItemEnum) -> Vec<&Vec<Id>> {
+    match inner {
+        ItemEnum::Module(Module { items, .. }) | ItemEnum::Impl(Impl { items, .. }) => {
+            vec![items]
+        }
+        ItemEnum::Struct(struct_) => match &struct_.kind {
+            StructKind::Plain { fields, .. } => vec![fields, &struct_.impls],
+            _ => vec![&struct_.impls],
+        },
+        ItemEnum::Union(Union { fields, impls, .. }) => vec![fields, impls],
+        ItemEnum::Enum(Enum {
+            variants, impls, ..
+        }) => vec![variants, impls],
+        ItemEnum::Variant(variant) => match &variant.kind {
+            VariantKind::Struct { fields, .. } => vec![fields],
+            _ => vec![],
+        },
+        ItemEnum::Trait(Trait {
+            items,
+            implementations,
+            ..
+        }) => vec![items, implementations],
+        _ => vec![],
+    }
+}
+
+/// Mutable version of [`children`], with the lists in the same order
+fn children_mut(inner: &mut ItemEnum) -> Vec<&mut Vec<Id>> {
+    match inner {
+        ItemEnum::Module(Module { items, .. }) | ItemEnum::Impl(Impl { items, .. }) => {
+            vec![items]
+        }
+        ItemEnum::Struct(struct_) => match &mut struct_.kind {
+            StructKind::Plain { fields, .. } => vec![fields, &mut struct_.impls],
+            _ => vec![&mut struct_.impls],
+        },
+        ItemEnum::Union(Union { fields, impls, .. }) => vec![fields, impls],
+        ItemEnum::Enum(Enum {
+            variants, impls, ..
+        }) => vec![variants, impls],
+        ItemEnum::Variant(variant) => match &mut variant.kind {
+            VariantKind::Struct { fields, .. } => vec![fields],
+            _ => vec![],
+        },
+        ItemEnum::Trait(Trait {
+            items,
+            implementations,
+            ..
+        }) => vec![items, implementations],
+        _ => vec![],
+    }
+}
+
+/// Fields of a tuple struct or variant, matched by position
+fn tuple_fields(inner: &ItemEnum) -> &[Option<Id>] {
+    match inner {
+        ItemEnum::Struct(struct_) => match &struct_.kind {
+            StructKind::Tuple(fields) => fields,
+            _ => &[],
+        },
+        ItemEnum::Variant(variant) => match &variant.kind {
+            VariantKind::Tuple(fields) => fields,
+            _ => &[],
+        },
+        _ => &[],
+    }
+}
+
+/// Key matching a child of an item across the targets: its signature for an impl, its kind
+/// and name otherwise
+fn child_key(krate: &Crate, id: &Id) -> Option<String> {
+    let item = krate.index.get(id)?;
+    match &item.inner {
+        ItemEnum::Impl(_) => Some(format!(
+            "impl {}",
+            pp::Tokens::from_item(item, &krate.index).ok()?
+        )),
+        ItemEnum::Import(import) => Some(format!("import {} as {}", import.source, import.name)),
+        _ => Some(format!(
+            "{} {}",
+            crate::extract::kind(item, false),
+            item.name.as_deref()?
+        )),
+    }
+}
+
+/// Merge of the items of one crate into the base one
+struct Merger<'a> {
+    base: &'a mut Crate,
+    other: &'a Crate,
+    /// Number of the merged crate, suffixed to the ids of its imported items
+    number: usize,
+    /// Ids of the merged crate to their id in the base one
+    ids: HashMap<Id, Id>,
+    /// Crate ids of the merged crate to their id in the base one
+    crate_ids: HashMap<u32, u32>,
+}
+
+impl<'a> Merger<'a> {
+    fn new(base: &'a mut Crate, other: &'a Crate, number: usize) -> Self {
+        let mut crate_ids = HashMap::from([(0, 0)]);
+        let mut next_crate_id = base.external_crates.keys().max().map_or(1, |max| max + 1);
+        // In a fixed order, the new crates being numbered as they come
+        let mut external_crates = other.external_crates.iter().collect::<Vec<_>>();
+        external_crates.sort_by_key(|(crate_id, _)| **crate_id);
+        for (crate_id, external_crate) in external_crates {
+            let base_crate_id = match base
+                .external_crates
+                .iter()
+                .find(|(_, base_crate)| base_crate.name == external_crate.name)
+            {
+                Some((base_crate_id, _)) => *base_crate_id,
+                None => {
+                    base.external_crates
+                        .insert(next_crate_id, external_crate.clone());
+                    next_crate_id += 1;
+                    next_crate_id - 1
+                }
+            };
+            crate_ids.insert(*crate_id, base_crate_id);
+        }
+
+        Merger {
+            ids: HashMap::from([(other.root.clone(), base.root.clone())]),
+            base,
+            other,
+            number,
+            crate_ids,
+        }
+    }
+
+    /// Merge the items, returning the ids (in the base crate) of the ones of the merged crate
+    fn run(mut self) -> Result<HashSet<Id>> {
+        // Items of the merged crate matching (by path) the ones of the base crate
+        let base_paths: HashMap<(u32, &Vec<String>, &ItemKind), &Id> = self
+            .base
+            .paths
+            .iter()
+            .map(|(id, summary)| ((summary.crate_id, &summary.path, &summary.kind), id))
+            .collect();
+        let mut new = Vec::new();
+        let mut pairs = vec![(self.other.root.clone(), self.base.root.clone())];
+        for (id, summary) in &self.other.paths {
+            let crate_id = self.crate_id(summary.crate_id);
+            match base_paths.get(&(crate_id, &summary.path, &summary.kind)) {
+                Some(base_id) => {
+                    self.ids.insert(id.clone(), (*base_id).clone());
+                    if crate_id == 0 && *id != self.other.root {
+                        pairs.push((id.clone(), (*base_id).clone()));
+                    }
+                }
+                None if summary.crate_id == 0 => new.push((id, summary)),
+                None => {}
+            }
+        }
+        let base_modules: HashMap<Vec<String>, Id> = base_paths
+            .into_iter()
+            .filter(|((crate_id, _, kind), _)| *crate_id == 0 && **kind == ItemKind::Module)
+            .map(|((_, path, _), id)| (path.clone(), id.clone()))
+            .collect();
+
+        // Walk the items of both crates from the matched ones, for the items without a path
+        // (impls, associated items, fields, ...)
+        while let Some((id, base_id)) = pairs.pop() {
+            self.merge_children(&id, &base_id, &mut pairs)?;
+        }
+
+        // Import the items only in the merged crate, in the module of the base crate they are in
+        new.sort_by(|(_, x), (_, y)| x.path.cmp(&y.path));
+        for (id, summary) in new {
+            let new_id = self.import(id)?;
+
+            let parent = &summary.path[..summary.path.len().saturating_sub(1)];
+            if let Some(module) = base_modules
+                .get(parent)
+                .and_then(|module| self.base.index.get_mut(module))
+            {
+                if let ItemEnum::Module(module) = &mut module.inner {
+                    if !module.items.contains(&new_id) {
+                        module.items.push(new_id);
+                    }
+                }
+            }
+        }
+        Ok(self.ids.into_values().collect())
+    }
+
+    /// Match the children of two matched items, importing the ones only in the merged crate
+    /// into the base item
+    fn merge_children(&mut self, id: &Id, base_id: &Id, pairs: &mut Vec<(Id, Id)>) -> Result<()> {
+        let other = self.other;
+        let (Some(item), Some(base_item)) = (other.index.get(id), self.base.index.get(base_id))
+        else {
+            return Ok(());
+        };
+        let fields = tuple_fields(&item.inner)
+            .iter()
+            .zip(tuple_fields(&base_item.inner));
+        for (field, base_field) in fields {
+            if let (Some(field), Some(base_field)) = (field, base_field) {
+                self.ids.insert(field.clone(), base_field.clone());
+            }
+        }
+        let base_lists: Vec<(HashMap<String, Id>, Vec<Id>)> = children(&base_item.inner)
+            .into_iter()
+            .map(|list| {
+                let keys = list
+                    .iter()
+                    .filter_map(|id| Some((child_key(self.base, id)?, id.clone())))
+                    .collect();
+                (keys, list.clone())
+            })
+            .collect();
+
+        for (index, list) in children(&item.inner).into_iter().enumerate() {
+            let Some((base_keys, base_list)) = base_lists.get(index) else {
+                continue;
+            };
+            for child in list {
+                let base_child = match self.ids.get(child) {
+                    Some(base_child) => base_child.clone(),
+                    None => match child_key(other, child).and_then(|key| base_keys.get(&key)) {
+                        Some(base_child) => {
+                            self.ids.insert(child.clone(), base_child.clone());
+                            pairs.push((child.clone(), base_child.clone()));
+                            continue;
+                        }
+                        None => self.import(child)?,
+                    },
+                };
+                if !base_list.contains(&base_child) {
+                    if let Some(base_item) = self.base.index.get_mut(base_id) {
+                        children_mut(&mut base_item.inner)[index].push(base_child);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn crate_id(&self, crate_id: u32) -> u32 {
+        self.crate_ids.get(&crate_id).copied().unwrap_or(crate_id)
+    }
+
+    /// Import an item (and the ones it refers to) of the merged crate, returning its new id
+    fn import(&mut self, id: &Id) -> Result<Id> {
+        if let Some(new_id) = self.ids.get(id) {
+            return Ok(new_id.clone());
+        }
+        let new_id = Id(format!("{}@{}", id.0, self.number));
+        self.ids.insert(id.clone(), new_id.clone());
+
+        if let Some(item) = self.other.index.get(id) {
+            let mut value = serde_json::to_value(item)
+                .with_context(|| format!("unable to serialize the item {:?}", id))?;
+            self.remap(&mut value)?;
+
+            let mut item: Item = serde_json::from_value(value)
+                .with_context(|| format!("unable to deserialize the item {:?}", id))?;
+            item.id = new_id.clone();
+            item.crate_id = self.crate_id(item.crate_id);
+            self.base.index.insert(new_id.clone(), item);
+        }
+        if let Some(summary) = self.other.paths.get(id) {
+            self.base.paths.insert(
+                new_id.clone(),
+                ItemSummary {
+                    crate_id: self.crate_id(summary.crate_id),
+                    ..summary.clone()
+                },
+            );
+        }
+        Ok(new_id)
+    }
+
+    /// Replace the ids referenced in a serialized item by their id in the base crate
+    fn remap(&mut self, value: &mut Value) -> Result<()> {
+        match value {
+            Value::String(s) => {
+                let id = Id(std::mem::take(s));
+                *s = if self.other.index.contains_key(&id) || self.other.paths.contains_key(&id) {
+                    self.import(&id)?.0
+                } else {
+                    id.0
+                };
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.remap(value)?;
+                }
+            }
+            Value::Object(map) => {
+                for value in map.values_mut() {
+                    self.remap(value)?;
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn demo() -> Value {
+        serde_json::from_str(include_str!("../tests/fixtures/demo.json")).expect("invalid demo")
+    }
+
+    fn krate(value: Value) -> Crate {
+        serde_json::from_value(value).expect("invalid crate")
+    }
+
+    fn id(id: &str) -> Id {
+        Id(id.to_string())
+    }
+
+    fn cfgs<'a>(krate: &'a Crate, id: &Id) -> Vec<&'a str> {
+        krate.index[id]
+            .attrs
+            .iter()
+            .filter(|attr| attr.starts_with("#[cfg("))
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn merge_same_targets() {
+        let merged = merge(vec![
+            ("unix".to_string(), krate(demo())),
+            ("windows".to_string(), krate(demo())),
+        ])
+        .unwrap();
+
+        let demo = krate(demo());
+        assert_eq!(merged.index.len(), demo.index.len());
+        for (id, item) in &demo.index {
+            assert_eq!(merged.index[id].attrs, item.attrs, "{:?}", id);
+        }
+    }
+
+    #[test]
+    fn merge_target_specific_items() {
+        let mut windows = demo();
+        let index = &mut windows["index"];
+
+        // `impl Pair { fn size() }` and `impl Tr for Kind` only on windows
+        let mut size = index["0:11"].clone();
+        size["id"] = json!("0:901");
+        size["name"] = json!("size");
+        let mut inherent = index["0:10"].clone();
+        inherent["id"] = json!("0:900");
+        inherent["inner"]["impl"]["items"] = json!(["0:901"]);
+        inherent["inner"]["impl"]["for"] =
+            json!({ "resolved_path": { "name": "Pair", "id": "0:23", "args": null } });
+        let mut tr = index["0:37"].clone();
+        tr["id"] = json!("0:902");
+        tr["attrs"] = json!([]);
+        tr["inner"]["impl"]["for"] =
+            json!({ "resolved_path": { "name": "Kind", "id": "0:5", "args": null } });
+        index["0:901"] = size;
+        index["0:900"] = inherent;
+        index["0:902"] = tr;
+        index["0:23"]["inner"]["struct"]["impls"] = json!(["0:34", "0:900"]);
+        index["0:5"]["inner"]["enum"]["impls"] = json!(["0:902"]);
+        index["0:6"]["inner"]["trait"]["implementations"] =
+            json!(["0:12", "0:36", "0:37", "0:49", "0:902"]);
+
+        // `Foo::raw` only on unix
+        index["0:10"]["inner"]["impl"]["items"] = json!(["0:11", "0:46"]);
+        index.as_object_mut().unwrap().remove("0:47");
+
+        let merged = merge(vec![
+            ("unix".to_string(), krate(demo())),
+            ("windows".to_string(), krate(windows)),
+        ])
+        .unwrap();
+
+        let impls = |id: &Id| match &merged.index[id].inner {
+            ItemEnum::Struct(struct_) => struct_.impls.clone(),
+            ItemEnum::Enum(enum_) => enum_.impls.clone(),
+            _ => unreachable!(),
+        };
+        let inherent = impls(&id("0:23"))
+            .into_iter()
+            .find(|impl_| impl_.0.ends_with("@1"))
+            .expect("the impl only on windows isn't merged");
+        assert_eq!(cfgs(&merged, &inherent), ["#[cfg(windows)]"]);
+        let ItemEnum::Impl(Impl { items, .. }) = &merged.index[&inherent].inner else {
+            unreachable!()
+        };
+        assert_eq!(merged.index[&items[0]].name.as_deref(), Some("size"));
+        assert!(cfgs(&merged, &items[0]).is_empty());
+
+        let [tr] = &impls(&id("0:5"))[..] else {
+            panic!("the trait impl only on windows isn't merged");
+        };
+        assert_eq!(cfgs(&merged, tr), ["#[cfg(windows)]"]);
+        let ItemEnum::Trait(Trait {
+            implementations, ..
+        }) = &merged.index[&id("0:6")].inner
+        else {
+            unreachable!()
+        };
+        assert!(implementations.contains(tr));
+
+        assert_eq!(cfgs(&merged, &id("0:47")), ["#[cfg(unix)]"]);
+        let unix = krate(demo());
+        for matched in ["0:1", "0:10", "0:11", "0:12", "0:13", "0:24", "0:37"] {
+            assert_eq!(
+                cfgs(&merged, &id(matched)),
+                cfgs(&unix, &id(matched)),
+                "{}",
+                matched
+            );
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Crate metadata (version, links, license, features) read from the output of
+//! `cargo metadata` or directly from a `Cargo.toml`
+
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::warn;
+
+/// Metadata of a package, shown on the crate root page
+#[derive(Debug, Default)]
+pub(crate) struct CrateMetadata {
+    pub(crate) name: String,
+    pub(crate) version: Option<String>,
+    pub(crate) edition: Option<String>,
+    pub(crate) license: Option<String>,
+    pub(crate) repository: Option<String>,
+    pub(crate) homepage: Option<String>,
+    pub(crate) documentation: Option<String>,
+    /// Enabled features
+    pub(crate) features: Vec<String>,
+}
+
+impl CrateMetadata {
+    /// Url of the documentation: the `documentation` field or else docs.rs
+    pub(crate) fn documentation_url(&self) -> String {
+        match (&self.documentation, &self.version) {
+            (Some(documentation), _) => documentation.clone(),
+            (None, Some(version)) => format!("https://docs.rs/{}/{}", self.name, version),
+            (None, None) => format!("https://docs.rs/{}", self.name),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    resolve: Option<Resolve>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+    edition: String,
+    license: Option<String>,
+    repository: Option<String>,
+    homepage: Option<String>,
+    documentation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Resolve {
+    nodes: Vec<Node>,
+}
+
+#[derive(Deserialize)]
+struct Node {
+    id: String,
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: toml::Table,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+}
+
+/// Load the metadata of the packages described by a `Cargo.toml` or by the
+/// (json) output of `cargo metadata`
+pub(crate) fn load(path: &Path) -> Result<Vec<CrateMetadata>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read the cargo metadata {:?}", path))?;
+
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        from_manifest(&content).with_context(|| format!("invalid manifest {:?}", path))
+    } else {
+        from_cargo_metadata(&content).with_context(|| format!("invalid cargo metadata {:?}", path))
+    }
+}
+
+/// Keep only the http(s) links, the others (like `javascript:`) not being written in the pages
+fn web_url(url: Option<String>) -> Option<String> {
+    let url = url?;
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+    if matches!(scheme.as_deref(), Some("http" | "https")) {
+        Some(url)
+    } else {
+        warn!(
+            "ignoring the link {:?} of the metadata, not an http(s) url",
+            url
+        );
+        None
+    }
+}
+
+fn from_cargo_metadata(content: &str) -> Result<Vec<CrateMetadata>> {
+    let metadata: Metadata = serde_json::from_str(content)?;
+    let mut features: BTreeMap<String, Vec<String>> = metadata
+        .resolve
+        .map(|resolve| {
+            resolve
+                .nodes
+                .into_iter()
+                .map(|node| (node.id, node.features))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(metadata
+        .packages
+        .into_iter()
+        .map(|package| CrateMetadata {
+            features: features.remove(&package.id).unwrap_or_default(),
+            name: package.name,
+            version: Some(package.version),
+            edition: Some(package.edition),
+            license: package.license,
+            repository: web_url(package.repository),
+            homepage: web_url(package.homepage),
+            documentation: web_url(package.documentation),
+        })
+        .collect())
+}
+
+fn from_manifest(content: &str) -> Result<Vec<CrateMetadata>> {
+    let manifest: Manifest = toml::from_str(content)?;
+    // Inherited fields (`version.workspace = true`) are not strings and are skipped
+    let field = |name: &str| {
+        manifest
+            .package
+            .get(name)
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+
+    Ok(vec![CrateMetadata {
+        name: field("name").context("missing package name")?,
+        version: field("version"),
+        edition: field("edition"),
+        license: field("license"),
+        repository: web_url(field("repository")),
+        homepage: web_url(field("homepage")),
+        documentation: web_url(field("documentation")),
+        // Without a resolve, consider the default features as the enabled ones
+        features: manifest
+            .features
+            .get("default")
+            .into_iter()
+            .flatten()
+            .filter(|feature| !feature.starts_with("dep:") && !feature.contains('/'))
+            .cloned()
+            .collect(),
+    }])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_links() {
+        let metadata = from_manifest(
+            r#"
+            [package]
+            name = "foo"
+            repository = "https://github.com/foo/foo"
+            homepage = " JavaScript:alert(1)"
+            documentation = "data:text/html,<script>alert(1)</script>"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            metadata[0].repository.as_deref(),
+            Some("https://github.com/foo/foo")
+        );
+        assert_eq!(metadata[0].homepage, None);
+        assert_eq!(metadata[0].documentation, None);
+    }
+}
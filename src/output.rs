@@ -0,0 +1,438 @@
+//! Staging of the output directory, so that a failed render never leaves a broken site
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Temporary sibling directory the pages are rendered into, before being published
+pub(crate) struct Staging {
+    output: PathBuf,
+    staging: PathBuf,
+}
+
+impl Staging {
+    /// Create the staging directory of `output`, removing the leftovers of a crashed render
+    pub(crate) fn create(output: &Path) -> Result<Self> {
+        let staging = sibling(output, "staging")?;
+        if staging.exists() {
+            debug!("removing the leftover staging directory {:?}", &staging);
+            fs::remove_dir_all(&staging)
+                .with_context(|| format!("unable to remove the leftover {:?}", staging))?;
+        }
+        fs::create_dir(&staging)
+            .with_context(|| format!("unable to create the staging directory {:?}", staging))?;
+
+        Ok(Staging {
+            output: output.to_path_buf(),
+            staging,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.staging
+    }
+
+    /// Path in the output directory of a path in the staging one
+    pub(crate) fn published(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.staging) {
+            Ok(rest) => self.output.join(rest),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+
+    /// Move the rendered files into the output directory: the whole directory is
+    /// swapped with `clean`, otherwise only its entries (crates, static files) are,
+    /// keeping the other files of the output directory, unless they are stale and `prune`d
+    /// (the hidden ones, like `.git`, are always kept)
+    pub(crate) fn publish(self, clean: bool, prune: bool) -> Result<()> {
+        let old = sibling(&self.output, "old")?;
+        if old.exists() {
+            fs::remove_dir_all(&old)
+                .with_context(|| format!("unable to remove the leftover {:?}", old))?;
+        }
+
+        if clean || !self.output.exists() {
+            info!("publishing {:?} to {:?}", &self.staging, &self.output);
+            replace(&self.staging, &self.output, &old)?;
+        } else {
+            info!(
+                "publishing the entries of {:?} to {:?}",
+                &self.staging, &self.output
+            );
+            fs::create_dir(&old).with_context(|| format!("unable to create {:?}", old))?;
+            let mut published = HashSet::new();
+            for entry in fs::read_dir(&self.staging)
+                .with_context(|| format!("unable to read the directory {:?}", self.staging))?
+            {
+                let name = entry?.file_name();
+                replace(
+                    &self.staging.join(&name),
+                    &self.output.join(&name),
+                    &old.join(&name),
+                )?;
+                published.insert(name);
+            }
+            if prune {
+                // The crates are replaced as a whole, so only the entries not rendered
+                // anymore (removed crates, static files of older versions) are stale
+                let mut pruned = 0;
+                for entry in fs::read_dir(&self.output)
+                    .with_context(|| format!("unable to read the directory {:?}", self.output))?
+                {
+                    let name = entry?.file_name();
+                    if !published.contains(&name) && !is_hidden(Path::new(&name)) {
+                        info!("pruning the stale {:?}", self.output.join(&name));
+                        rename(&self.output.join(&name), &old.join(&name))?;
+                        pruned += 1;
+                    }
+                }
+                info!("pruned {} stale entries from {:?}", pruned, &self.output);
+            }
+            fs::remove_dir(&self.staging)
+                .with_context(|| format!("unable to remove {:?}", self.staging))?;
+        }
+
+        if old.exists() {
+            fs::remove_dir_all(&old).with_context(|| format!("unable to remove {:?}", old))?;
+        }
+        Ok(())
+    }
+
+    /// Move the rendered files into the output directory one by one, keeping all the
+    /// others, like the pages of the rest of the crate with `--only`, and the full version
+    /// of the [partial pages](add_partial_pages)
+    pub(crate) fn merge(self) -> Result<()> {
+        info!("merging {:?} into {:?}", &self.staging, &self.output);
+        let partial_pages = self.staging.join(PARTIAL_PAGES);
+        let partial_pages: HashSet<PathBuf> = match fs::read_to_string(&partial_pages) {
+            Ok(content) => {
+                fs::remove_file(&partial_pages)
+                    .with_context(|| format!("unable to remove {:?}", partial_pages))?;
+                content.lines().map(PathBuf::from).collect()
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("unable to read {:?}", partial_pages))
+            }
+        };
+
+        walk(&self.staging, &mut |path| {
+            let relative = path.strip_prefix(&self.staging)?;
+            let dest = self.output.join(relative);
+            if partial_pages.contains(relative) && dest.exists() {
+                debug!("keeping the full page {:?}", dest);
+                return Ok(());
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("unable to create {:?}", parent))?;
+            }
+            rename(path, &dest)
+        })?;
+        self.discard()
+    }
+
+    /// Remove the staging directory, leaving the output directory untouched
+    pub(crate) fn discard(self) -> Result<()> {
+        fs::remove_dir_all(&self.staging)
+            .with_context(|| format!("unable to remove the staging directory {:?}", self.staging))
+    }
+}
+
+/// List of the partial pages of a staging directory, one path (relative to it) per line
+const PARTIAL_PAGES: &str = ".partial-pages";
+
+/// Record pages of `staging` (relative to it) only rendered partially, like the parent
+/// modules with `--only`, that don't replace their full version when merging
+pub(crate) fn add_partial_pages(staging: &Path, pages: &[PathBuf]) -> Result<()> {
+    let path = staging.join(PARTIAL_PAGES);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("unable to open {:?}", path))?;
+    for page in pages {
+        let page = page.to_str().context("non utf-8 page path")?;
+        writeln!(file, "{}", page).with_context(|| format!("unable to write {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Manifest of the output directory (`--manifest`), listing every file with its hash
+pub(crate) const MANIFEST_JSON: &str = "manifest.json";
+
+/// A file of the manifest
+#[derive(Serialize)]
+struct ManifestEntry {
+    /// Hex-encoded SHA-256 of the content, usable as an ETag
+    sha256: String,
+    size: u64,
+}
+
+/// Write the manifest of every file of `output` (relative to it, with `/` separators),
+/// so that the deployments only upload the files whose hash changed
+pub(crate) fn write_manifest(output: &Path) -> Result<usize> {
+    let mut files = BTreeMap::new();
+    walk(output, &mut |path| {
+        let relative = path.strip_prefix(output)?;
+        if relative == Path::new(MANIFEST_JSON) {
+            return Ok(());
+        }
+
+        let content = fs::read(path).with_context(|| format!("unable to read {:?}", path))?;
+        let sha256 = Sha256::digest(&content)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let name = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.insert(
+            name,
+            ManifestEntry {
+                sha256,
+                size: content.len() as u64,
+            },
+        );
+        Ok(())
+    })?;
+
+    let path = output.join(MANIFEST_JSON);
+    let content = serde_json::to_vec_pretty(&files).context("unable to serialize the manifest")?;
+    fs::write(&path, content).with_context(|| format!("unable to write {:?}", path))?;
+    Ok(files.len())
+}
+
+/// Call `f` on every file under `dir`, recursively, skipping the hidden ones (like `.git`)
+fn walk(dir: &Path, f: &mut dyn FnMut(&Path) -> Result<()>) -> Result<()> {
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("unable to read the directory {:?}", dir))?
+    {
+        let path = entry?.path();
+        if is_hidden(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, f)?;
+        } else {
+            f(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the file name starts with a dot, like `.git` or `.nojekyll`
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Hidden sibling directory of `output`, like `.doc.rd-staging` for `doc`
+fn sibling(output: &Path, suffix: &str) -> Result<PathBuf> {
+    let output = std::path::absolute(output)
+        .with_context(|| format!("unable to make {:?} absolute", output))?;
+    let name = output
+        .file_name()
+        .with_context(|| format!("the output directory {:?} has no name", output))?;
+    Ok(output.with_file_name(format!(".{}.rd-{}", name.to_string_lossy(), suffix)))
+}
+
+/// Move `new` to `dest`, and the previous `dest` (if any) to `old`
+///
+/// On Linux they are exchanged atomically so that `dest` is never missing; elsewhere (or
+/// when the file system can't) `dest` is moved away before `new` takes its place, leaving
+/// a short window where the servers answer a 404 for it
+fn replace(new: &Path, dest: &Path, old: &Path) -> Result<()> {
+    if !dest.exists() {
+        return rename(new, dest);
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    match exchange(new, dest) {
+        Ok(()) => return rename(new, old),
+        Err(err) if matches!(err.raw_os_error(), Some(libc::EINVAL | libc::ENOSYS)) => {
+            debug!("unable to exchange {:?} and {:?}: {}", new, dest, err);
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("unable to exchange {:?} and {:?}", new, dest))
+        }
+    }
+
+    rename(dest, old)?;
+    rename(new, dest)
+}
+
+/// Atomically exchange two paths, with `renameat2(RENAME_EXCHANGE)`
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn exchange(a: &Path, b: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let a = CString::new(a.as_os_str().as_bytes())?;
+    let b = CString::new(b.as_os_str().as_bytes())?;
+    // SAFETY: both paths are NUL-terminated strings living until the end of the call
+    let result = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            a.as_ptr(),
+            libc::AT_FDCWD,
+            b.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+    match result {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    fs::rename(from, to).with_context(|| format!("unable to move {:?} to {:?}", from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty temporary directory, unique to the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rd-output-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn merge_partial_pages() {
+        let output = temp_dir("merge").join("doc");
+        write(&output.join("demo/index.html"), "full");
+        write(&output.join("demo/fn.add.html"), "add");
+
+        let staging = Staging::create(&output).unwrap();
+        write(&staging.path().join("demo/index.html"), "partial");
+        write(&staging.path().join("demo/inner/index.html"), "partial");
+        write(&staging.path().join("demo/inner/fn.run.html"), "run");
+        add_partial_pages(
+            staging.path(),
+            &[
+                PathBuf::from("demo/index.html"),
+                PathBuf::from("demo/inner/index.html"),
+            ],
+        )
+        .unwrap();
+        let staging_path = staging.path().to_path_buf();
+        staging.merge().unwrap();
+
+        let read = |path: &str| fs::read_to_string(output.join(path)).unwrap();
+        assert_eq!(read("demo/index.html"), "full");
+        assert_eq!(read("demo/fn.add.html"), "add");
+        // Without a full version, the partial page is better than none
+        assert_eq!(read("demo/inner/index.html"), "partial");
+        assert_eq!(read("demo/inner/fn.run.html"), "run");
+        assert!(!output.join(PARTIAL_PAGES).exists());
+        assert!(!staging_path.exists());
+    }
+
+    #[test]
+    fn publish_prune() {
+        let output = temp_dir("prune").join("doc");
+        write(&output.join(".git/HEAD"), "ref");
+        write(&output.join("demo/fn.removed.html"), "removed");
+        write(&output.join("gone/index.html"), "gone");
+        write(&output.join("search.old.js"), "old");
+
+        let staging = Staging::create(&output).unwrap();
+        write(&staging.path().join("demo/index.html"), "demo");
+        write(&staging.path().join("search.new.js"), "new");
+        staging.publish(false, true).unwrap();
+
+        assert!(output.join(".git/HEAD").exists());
+        assert!(output.join("demo/index.html").exists());
+        assert!(output.join("search.new.js").exists());
+        assert!(!output.join("demo/fn.removed.html").exists());
+        assert!(!output.join("gone").exists());
+        assert!(!output.join("search.old.js").exists());
+    }
+
+    #[test]
+    fn publish_without_prune() {
+        let output = temp_dir("no-prune").join("doc");
+        write(&output.join("gone/index.html"), "gone");
+
+        let staging = Staging::create(&output).unwrap();
+        write(&staging.path().join("demo/index.html"), "demo");
+        staging.publish(false, false).unwrap();
+
+        assert!(output.join("gone/index.html").exists());
+        assert!(output.join("demo/index.html").exists());
+    }
+
+    #[test]
+    fn manifest_skips_hidden_files() {
+        let output = temp_dir("manifest");
+        write(&output.join(".git/HEAD"), "ref");
+        write(&output.join(".nojekyll"), "");
+        write(&output.join("demo/index.html"), "demo");
+
+        assert_eq!(write_manifest(&output).unwrap(), 1);
+        let manifest = fs::read_to_string(output.join(MANIFEST_JSON)).unwrap();
+        assert!(manifest.contains("\"demo/index.html\""));
+        assert!(!manifest.contains(".git"));
+    }
+
+    #[test]
+    fn publish_clean() {
+        let output = temp_dir("clean").join("doc");
+        write(&output.join("demo/fn.removed.html"), "removed");
+        write(&output.join("search.old.js"), "old");
+
+        let staging = Staging::create(&output).unwrap();
+        let staging_path = staging.path().to_path_buf();
+        write(&staging.path().join("demo/index.html"), "demo");
+        staging.publish(true, false).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(output.join("demo/index.html")).unwrap(),
+            "demo"
+        );
+        assert!(!output.join("demo/fn.removed.html").exists());
+        assert!(!output.join("search.old.js").exists());
+        assert!(!staging_path.exists());
+        assert!(!sibling(&output, "old").unwrap().exists());
+    }
+
+    #[test]
+    fn replace_keeps_the_previous_version() {
+        let dir = temp_dir("replace");
+        write(&dir.join("new/index.html"), "new");
+        write(&dir.join("dest/index.html"), "previous");
+
+        replace(&dir.join("new"), &dir.join("dest"), &dir.join("old")).unwrap();
+        assert!(!dir.join("new").exists());
+        let read = |path: &str| fs::read_to_string(dir.join(path)).unwrap();
+        assert_eq!(read("dest/index.html"), "new");
+        assert_eq!(read("old/index.html"), "previous");
+
+        write(&dir.join("file.new"), "new");
+        replace(
+            &dir.join("file.new"),
+            &dir.join("file"),
+            &dir.join("file.old"),
+        )
+        .unwrap();
+        assert_eq!(read("file"), "new");
+        assert!(!dir.join("file.old").exists());
+    }
+}
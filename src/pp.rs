@@ -1,4 +1,18 @@
 //! Pretty-printer for rustdoc-json output
+//!
+//! [`Tokens::from_item`] and [`Tokens::from_type`] turn an item or a type into a
+//! stream of [`Token`]s, the [`Display`] implementation of [`Tokens`] gives back
+//! the plain-text signature:
+//!
+//! ```no_run
+//! # fn signatures(krate: &rustdoc_types::Crate) {
+//! for item in krate.index.values() {
+//!     if let Ok(tokens) = rd::pp::Tokens::from_item(item, &krate.index) {
+//!         println!("{}", tokens);
+//!     }
+//! }
+//! # }
+//! ```
 
 use rustdoc_types::*;
 use std::{
@@ -7,6 +21,14 @@ use std::{
     ops::Deref,
 };
 
+/// Constant expressions longer than this are elided (as `_`) from the definitions
+pub const MAX_INLINE_EXPR_LEN: usize = 64;
+
+/// Whether a constant expression is too long to be printed inline
+pub fn is_long_expr(expr: &str) -> bool {
+    expr.len() > MAX_INLINE_EXPR_LEN || expr.contains('\n')
+}
+
 const ALLOWED_ATTRIBUTES: [&str; 6] = [
     "must_use",
     "export_name",
@@ -16,22 +38,46 @@ const ALLOWED_ATTRIBUTES: [&str; 6] = [
     "non_exhaustive",
 ];
 
-#[derive(Debug, PartialEq, Clone)]
+/// Where the bounds of the generic parameters are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundsPlacement {
+    /// Like in the source code
+    AsWritten,
+    /// In the declaration of the parameters, like `<T: Clone>`
+    Inline,
+    /// In the where clauses, like `where T: Clone`
+    Where,
+}
+
+/// A piece of a pretty-printed signature
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Token<'token> {
+    /// A name, with the item it refers to when known (`Vec`, `new`, `T`, ...)
     Ident(&'token str, Option<&'token Id>),
+    /// A keyword (`pub`, `fn`, `where`, ...)
     Kw(&'static str),
+    /// A punctuation (`<`, `::`, `->`, ...)
     Ponct(&'static str),
+    /// A whitespace or a placeholder, see [`SpecialToken`]
     Special(SpecialToken),
+    /// An attribute, as written (`#[must_use]`, ...)
     Attr(&'token str),
+    /// A primitive type (`u8`, `str`, ...)
     Primitive(&'token str),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// A token without a textual representation of its own
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SpecialToken {
+    /// A line break
     NewLine,
+    /// A single space
     Space,
+    /// One level of indentation (four spaces)
     Tabulation,
+    /// Some (or `all`) of the fields are private and not shown
     Hidden { all: bool },
+    /// Something elided, printed as `...`
     Ignored,
 }
 
@@ -101,9 +147,7 @@ impl<'pusher, 'token> Pusher<Token<'token>> for NewLineTabulationPusher<'pusher,
     #[inline]
     fn try_extend_from_slice(&mut self, t: &[Token<'token>]) -> Result<(), PusherError> {
         for t in t {
-            // NOTE: This is ugly but we cannot yse use IntoSlice because that would
-            // make the Pusher trait not "safe" and so it will not be possible to use with dyn
-            self.try_push(t.clone())?;
+            self.try_push(*t)?;
         }
         Ok(())
     }
@@ -131,6 +175,8 @@ impl<'token, const N: usize> IntoSlice<N> for [Token<'token>; N] {
     }
 }
 
+/// The pretty-printed signature of an item or a type, `Display` gives its plain-text form
+#[derive(Default)]
 pub struct Tokens<'tcx>(Vec<Token<'tcx>>);
 
 impl Display for Tokens<'_> {
@@ -164,6 +210,7 @@ impl<'tcx> Deref for Tokens<'tcx> {
     }
 }
 
+/// Why an item couldn't be pretty-printed
 #[derive(Debug, PartialEq)]
 pub enum FromItemErrorKind {
     InvalidItem,
@@ -187,7 +234,40 @@ impl From<PusherError> for FromItemErrorKind {
     }
 }
 
+impl<'tcx> Tokens<'tcx> {
+    /// Make the identifiers referring to `from` refer to `to` instead
+    pub fn retarget(&mut self, from: &Id, to: &'tcx Id) {
+        for token in &mut self.0 {
+            if let Token::Ident(_, id @ Some(_)) = token {
+                if *id == Some(from) {
+                    *id = Some(to);
+                }
+            }
+        }
+    }
+
+    /// Take back the (emptied) buffer of the tokens, to give it to [`Tokens::from_item_in`]
+    pub fn into_buffer(self) -> Vec<Token<'static>> {
+        let mut buffer = std::mem::ManuallyDrop::new(self.0);
+        buffer.clear();
+        let (pointer, capacity) = (buffer.as_mut_ptr(), buffer.capacity());
+        // SAFETY: the allocation is taken over from the (not dropped) emptied buffer, with
+        // the layout of the tokens being the same whatever their lifetime, and no token
+        // borrowing anything is left in it
+        unsafe { Vec::from_raw_parts(pointer.cast::<Token<'static>>(), 0, capacity) }
+    }
+}
+
+/// Empty a reused buffer and make room for at least `capacity` tokens
+fn reuse<'token>(buffer: Vec<Token<'static>>, capacity: usize) -> Vec<Token<'token>> {
+    let mut buffer: Vec<Token<'token>> = buffer;
+    buffer.clear();
+    buffer.reserve(capacity);
+    buffer
+}
+
 impl Tokens<'_> {
+    /// Get the [`Token`]s of a type
     pub fn from_type(type_: &Type) -> Result<Tokens<'_>, FromItemErrorKind> {
         Ok({
             let mut tokens = Vec::new();
@@ -198,10 +278,20 @@ impl Tokens<'_> {
         })
     }
 
-    /// Get a [`Token`] from a item
+    /// Get the [`Token`]s of the declaration of an item (modules and extern crates excluded)
     pub fn from_item<'item>(
         item: &'item Item,
         index: &'item HashMap<Id, Item>,
+    ) -> Result<Tokens<'item>, FromItemErrorKind> {
+        Self::from_item_in(item, index, Vec::new())
+    }
+
+    /// Like [`Tokens::from_item`], but reusing the buffer of previous tokens (see
+    /// [`Tokens::into_buffer`]) instead of allocating a new one
+    pub fn from_item_in<'item>(
+        item: &'item Item,
+        index: &'item HashMap<Id, Item>,
+        buffer: Vec<Token<'static>>,
     ) -> Result<Tokens<'item>, FromItemErrorKind> {
         Ok(Tokens(match &item.inner {
             ItemEnum::Module(_) => {
@@ -211,7 +301,7 @@ impl Tokens<'_> {
                 return Err(FromItemErrorKind::InvalidItem);
             }
             ItemEnum::Import(import) => {
-                let mut tokens = Vec::with_capacity(12);
+                let mut tokens = reuse(buffer, 12);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -246,7 +336,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::Union(union_) => {
-                let mut tokens = Vec::with_capacity(32);
+                let mut tokens = reuse(buffer, 32);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -330,7 +420,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::Struct(struct_) => {
-                let mut tokens = Vec::with_capacity(32);
+                let mut tokens = reuse(buffer, 32);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -423,7 +513,7 @@ impl Tokens<'_> {
                             .iter()
                             .map(|id| {
                                 id.as_ref()
-                                    .map(|id| match index.get(&id) {
+                                    .map(|id| match index.get(id) {
                                         Some(item) => match &item.inner {
                                             ItemEnum::StructField(struct_field) => {
                                                 Ok((item, struct_field))
@@ -467,14 +557,14 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::StructField(struct_field) => {
-                let mut tokens = Vec::with_capacity(8);
+                let mut tokens = reuse(buffer, 8);
 
                 with_struct_field(&mut tokens, item, struct_field)?;
 
                 tokens
             }
             ItemEnum::Enum(enum_) => {
-                let mut tokens = Vec::with_capacity(16);
+                let mut tokens = reuse(buffer, 16);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -553,21 +643,21 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::Variant(variant) => {
-                let mut tokens = Vec::with_capacity(8);
+                let mut tokens = reuse(buffer, 8);
 
                 with_enum_variant(&mut tokens, index, item, variant)?;
 
                 tokens
             }
             ItemEnum::Function(function) => {
-                let mut tokens = Vec::with_capacity(16);
+                let mut tokens = reuse(buffer, 16);
 
                 with_function(&mut tokens, item, function, false)?;
 
                 tokens
             }
             ItemEnum::Trait(trait_) => {
-                let mut tokens = Vec::with_capacity(16);
+                let mut tokens = reuse(buffer, 16);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -671,7 +761,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::TraitAlias(trait_alias) => {
-                let mut tokens = Vec::with_capacity(16);
+                let mut tokens = reuse(buffer, 16);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -730,7 +820,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::Impl(impl_) => {
-                let mut tokens = Vec::with_capacity(32);
+                let mut tokens = reuse(buffer, 32);
 
                 with_attrs(&mut tokens, &item.attrs)?;
 
@@ -787,7 +877,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::TypeAlias(typealias) => {
-                let mut tokens = Vec::with_capacity(12);
+                let mut tokens = reuse(buffer, 12);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -833,9 +923,71 @@ impl Tokens<'_> {
 
                 tokens
             }
-            ItemEnum::OpaqueTy(_) => todo!("ItemEnum::OpaqueTy"),
+            ItemEnum::OpaqueTy(opaque_ty) => {
+                let mut tokens = reuse(buffer, 12);
+
+                with_attrs(&mut tokens, &item.attrs)?;
+                with_visibility(&mut tokens, &item.visibility)?;
+                tokens.extend_from_slice(&[
+                    Token::Kw("type"),
+                    Token::Special(SpecialToken::Space),
+                    Token::Ident(item.name.as_ref().unwrap(), Some(&item.id)),
+                ]);
+
+                with(
+                    &mut tokens,
+                    &opaque_ty.generics.params,
+                    Some([Token::Ponct("<")]),
+                    Some(Token::Ponct(">")),
+                    Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
+                    with_generic_param_def,
+                )?;
+
+                tokens.extend_from_slice(&[
+                    Token::Special(SpecialToken::Space),
+                    Token::Ponct("="),
+                    Token::Special(SpecialToken::Space),
+                    Token::Kw("impl"),
+                    Token::Special(SpecialToken::Space),
+                ]);
+
+                with(
+                    &mut tokens,
+                    &opaque_ty.bounds,
+                    Option::<Token>::None,
+                    Option::<Token>::None,
+                    Some([
+                        Token::Special(SpecialToken::Space),
+                        Token::Ponct("+"),
+                        Token::Special(SpecialToken::Space),
+                    ]),
+                    with_generic_bound,
+                )?;
+
+                with(
+                    &mut tokens,
+                    &opaque_ty.generics.where_predicates,
+                    Some([
+                        Token::Special(SpecialToken::NewLine),
+                        Token::Kw("where"),
+                        Token::Special(SpecialToken::NewLine),
+                        Token::Special(SpecialToken::Tabulation),
+                    ]),
+                    Some([Token::Ponct(",")]),
+                    Some([
+                        Token::Ponct(","),
+                        Token::Special(SpecialToken::NewLine),
+                        Token::Special(SpecialToken::Space),
+                    ]),
+                    with_where_predicate,
+                )?;
+
+                tokens.try_push(Token::Ponct(";"))?;
+
+                tokens
+            }
             ItemEnum::Constant { type_, const_ } => {
-                let mut tokens = Vec::with_capacity(16);
+                let mut tokens = reuse(buffer, 16);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -845,17 +997,24 @@ impl Tokens<'_> {
                 tokens.try_push(Token::Ident(item.name.as_ref().unwrap(), Some(&item.id)))?;
                 tokens.try_push(Token::Ponct(":"))?;
                 tokens.try_push(Token::Special(SpecialToken::Space))?;
-                with_type(&mut tokens, &type_)?;
+                with_type(&mut tokens, type_)?;
                 tokens.try_push(Token::Special(SpecialToken::Space))?;
                 tokens.try_push(Token::Ponct("="))?;
                 tokens.try_push(Token::Special(SpecialToken::Space))?;
-                tokens.try_push(Token::Ident(&const_.expr, None))?;
+                tokens.try_push(Token::Ident(
+                    if is_long_expr(&const_.expr) {
+                        "_"
+                    } else {
+                        &const_.expr
+                    },
+                    None,
+                ))?;
                 tokens.try_push(Token::Ponct(";"))?;
 
                 tokens
             }
             ItemEnum::Static(static_) => {
-                let mut tokens = Vec::with_capacity(16);
+                let mut tokens = reuse(buffer, 16);
 
                 with_attrs(&mut tokens, &item.attrs)?;
                 with_visibility(&mut tokens, &item.visibility)?;
@@ -876,9 +1035,31 @@ impl Tokens<'_> {
 
                 tokens
             }
-            ItemEnum::ForeignType => todo!("ItemEnum::ForeignType"),
+            ItemEnum::ForeignType => {
+                let mut tokens = reuse(buffer, 12);
+
+                with_attrs(&mut tokens, &item.attrs)?;
+                tokens.extend_from_slice(&[
+                    Token::Kw("extern"),
+                    Token::Special(SpecialToken::Space),
+                    Token::Ponct("{"),
+                    Token::Special(SpecialToken::NewLine),
+                    Token::Special(SpecialToken::Tabulation),
+                ]);
+                with_visibility(&mut tokens, &item.visibility)?;
+                tokens.extend_from_slice(&[
+                    Token::Kw("type"),
+                    Token::Special(SpecialToken::Space),
+                    Token::Ident(item.name.as_ref().unwrap(), Some(&item.id)),
+                    Token::Ponct(";"),
+                    Token::Special(SpecialToken::NewLine),
+                    Token::Ponct("}"),
+                ]);
+
+                tokens
+            }
             ItemEnum::Macro(macro_) => {
-                let mut tokens = Vec::with_capacity(12);
+                let mut tokens = reuse(buffer, 12);
 
                 with_attrs(&mut tokens, &item.attrs)?;
 
@@ -892,7 +1073,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::ProcMacro(proc_macro) => {
-                let mut tokens = Vec::with_capacity(12);
+                let mut tokens = reuse(buffer, 12);
 
                 match proc_macro.kind {
                     MacroKind::Bang => {
@@ -924,7 +1105,7 @@ impl Tokens<'_> {
                 tokens
             }
             ItemEnum::AssocConst { type_, default } => {
-                let mut tokens = Vec::with_capacity(12);
+                let mut tokens = reuse(buffer, 12);
 
                 with_assoc_const(&mut tokens, item, type_, default, true)?;
 
@@ -935,13 +1116,15 @@ impl Tokens<'_> {
                 default,
                 generics,
             } => {
-                let mut tokens = Vec::with_capacity(12);
+                let mut tokens = reuse(buffer, 12);
 
                 with_assoc_type(&mut tokens, item, bounds, default, generics, true)?;
 
                 tokens
             }
-            ItemEnum::Primitive(_) => todo!("ItemEnum::Primitive"),
+            ItemEnum::Primitive(_) => {
+                return Err(FromItemErrorKind::InvalidItem);
+            }
         }))
     }
 }
@@ -1169,7 +1352,7 @@ fn with_function<'tokens>(
     }
 
     if function.decl.c_variadic {
-        if function.decl.inputs.len() >= 1 {
+        if !function.decl.inputs.is_empty() {
             tokens
                 .try_extend_from_slice(&[Token::Ponct(","), Token::Special(SpecialToken::Space)])?;
         }
@@ -1265,7 +1448,7 @@ fn with_enum_variant<'tokens>(
                 .iter()
                 .map(|id| {
                     id.as_ref()
-                        .map(|id| match index.get(&id) {
+                        .map(|id| match index.get(id) {
                             Some(item) => match &item.inner {
                                 ItemEnum::StructField(struct_field) => Ok(struct_field),
                                 _ => Err(FromItemErrorKind::UnexpectedItemType(
@@ -1409,21 +1592,9 @@ fn with_where_predicate<'tokens>(
             bounds,
             generic_params,
         } => {
+            with_binder(tokens, generic_params)?;
             with_type(tokens, type_)?;
 
-            with(
-                tokens,
-                generic_params,
-                Some([
-                    Token::Special(SpecialToken::Space),
-                    Token::Kw("for"),
-                    Token::Ponct("<"),
-                ]),
-                Some([Token::Ponct(">"), Token::Special(SpecialToken::Space)]),
-                Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
-                with_generic_param_def,
-            )?;
-
             tokens.try_push(Token::Ponct(":"))?;
             tokens.try_push(Token::Special(SpecialToken::Space))?;
 
@@ -1445,6 +1616,7 @@ fn with_where_predicate<'tokens>(
             tokens.try_push(Token::Ponct(":"))?;
             tokens.try_push(Token::Special(SpecialToken::Space))?;
 
+            #[allow(clippy::ptr_arg)]
             fn with_outlive<'tokens>(
                 tokens: &mut dyn Pusher<Token<'tokens>>,
                 outlive: &'tokens String,
@@ -1501,34 +1673,8 @@ fn with_generic_bound<'tokens>(
                 tokens.try_push(Token::Kw(modifier_str))?;
             }
 
-            let pivot = generic_params
-                .iter()
-                .enumerate()
-                .find(|(_, param)| !matches!(param.kind, GenericParamDefKind::Lifetime { .. }))
-                .map(|(index, _)| index)
-                .unwrap_or(generic_params.len());
-
-            with(
-                tokens,
-                &generic_params[..pivot],
-                Some([Token::Ponct("for"), Token::Ponct("<")]),
-                Some([Token::Ponct(">"), Token::Special(SpecialToken::Space)]),
-                Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
-                with_generic_param_def,
-            )?;
+            with_binder(tokens, generic_params)?;
             with_path(tokens, trait_)?;
-            with(
-                tokens,
-                &generic_params[pivot..],
-                Option::<Token>::None,
-                Option::<Token>::None,
-                Some([
-                    Token::Special(SpecialToken::Space),
-                    Token::Ponct("+"),
-                    Token::Special(SpecialToken::Space),
-                ]),
-                with_generic_param_def,
-            )?;
         }
         GenericBound::Outlives(n) => {
             tokens.try_push(Token::Ident(n, None))?;
@@ -1537,6 +1683,22 @@ fn with_generic_bound<'tokens>(
     Ok(())
 }
 
+/// The higher-ranked binder (`for<'a, 'b> `) of a bound, a where predicate or a function
+/// pointer, if it has parameters
+fn with_binder<'tokens>(
+    tokens: &mut dyn Pusher<Token<'tokens>>,
+    generic_params: &'tokens [GenericParamDef],
+) -> Result<(), FromItemErrorKind> {
+    with(
+        tokens,
+        generic_params,
+        Some([Token::Kw("for"), Token::Ponct("<")]),
+        Some([Token::Ponct(">"), Token::Special(SpecialToken::Space)]),
+        Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
+        with_generic_param_def,
+    )
+}
+
 fn without_impl(items: &[GenericParamDef]) -> &[GenericParamDef] {
     let until = items
         .iter()
@@ -1740,23 +1902,16 @@ fn with_generic_args<'tcx>(
     Ok(())
 }
 
-fn with_poly_trait<'tcx, 'tokens>(
+fn with_poly_trait<'tcx>(
     tokens: &mut dyn Pusher<Token<'tcx>>,
     poly_trait: &'tcx PolyTrait,
 ) -> Result<(), FromItemErrorKind> {
-    with(
-        tokens,
-        &poly_trait.generic_params,
-        Some([Token::Kw("for"), Token::Ponct("<")]),
-        Some(Token::Ponct(">")),
-        Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
-        with_generic_param_def,
-    )?;
+    with_binder(tokens, &poly_trait.generic_params)?;
     with_path(tokens, &poly_trait.trait_)?;
     Ok(())
 }
 
-fn with_path<'tcx, 'tokens>(
+fn with_path<'tcx>(
     tokens: &mut dyn Pusher<Token<'tcx>>,
     path: &'tcx Path,
 ) -> Result<(), FromItemErrorKind> {
@@ -1771,7 +1926,7 @@ fn with_path<'tcx, 'tokens>(
     // ))?;
     tokens.try_push(Token::Ident(&path.name, Some(&path.id)))?;
     if let Some(generic_args) = &path.args {
-        with_generic_args(tokens, &generic_args)?;
+        with_generic_args(tokens, generic_args)?;
     }
     Ok(())
 }
@@ -1811,17 +1966,10 @@ fn with_type<'tcx>(
         }
         // `extern "ABI" fn`
         Type::FunctionPointer(fn_ptr) => {
+            with_binder(tokens, &fn_ptr.generic_params)?;
             with_header(tokens, &fn_ptr.header)?;
 
             tokens.try_push(Token::Kw("fn"))?;
-            with(
-                tokens,
-                &fn_ptr.generic_params,
-                Some([Token::Ponct("<")]),
-                Some(Token::Ponct(">")),
-                Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
-                with_generic_param_def,
-            )?;
 
             tokens.try_push(Token::Ponct("("))?;
             with(
@@ -1851,7 +1999,7 @@ fn with_type<'tcx>(
                 Some([]),
                 Some([]),
                 Some([Token::Ponct(","), Token::Special(SpecialToken::Space)]),
-                &with_type,
+                with_type,
             )?;
             tokens.try_push(Token::Ponct(")"))?;
         }
@@ -1913,6 +2061,7 @@ fn with_type<'tcx>(
             if let Some(lifetime) = &dyn_trait.lifetime {
                 tokens.try_push(Token::Special(SpecialToken::Space))?;
                 tokens.try_push(Token::Ponct("+"))?;
+                tokens.try_push(Token::Special(SpecialToken::Space))?;
                 tokens.try_push(Token::Ident(lifetime, None))?;
             }
         }
@@ -1950,13 +2099,13 @@ fn with_type<'tcx>(
                 tokens.try_push(Token::Ponct(">"))?;
                 tokens.try_push(Token::Ponct("::"))?;
                 tokens.try_push(Token::Ident(name, None))?;
-                with_generic_args(tokens, &qargs)?;
+                with_generic_args(tokens, qargs)?;
             }
             _ => {
                 with_type(tokens, self_type)?;
                 tokens.try_push(Token::Ponct("::"))?;
                 tokens.try_push(Token::Ident(name, None))?;
-                with_generic_args(tokens, &qargs)?;
+                with_generic_args(tokens, qargs)?;
             }
         },
         Type::Pat { .. } => todo!("Type::Pat is unstable"),
@@ -2010,3 +2159,470 @@ where
 
     Ok(())
 }
+
+/// Rewrite the bounds of the generics of all the items before printing them: elide the
+/// implicit `Sized` and the duplicated bounds (with `simplify`) and put the bounds of the
+/// type parameters inline or in the where clauses (with `placement`)
+pub fn rewrite_bounds(krate: &mut Crate, simplify: bool, placement: BoundsPlacement) {
+    if !simplify && placement == BoundsPlacement::AsWritten {
+        return;
+    }
+
+    let sized = krate
+        .paths
+        .iter()
+        .find(|(_, summary)| summary.path == ["core", "marker", "Sized"])
+        .map(|(id, _)| id.clone());
+    let is_sized = |bound: &GenericBound| match bound {
+        GenericBound::TraitBound {
+            trait_, modifier, ..
+        } => {
+            *modifier == TraitBoundModifier::None
+                && match &sized {
+                    Some(sized) => trait_.id == *sized,
+                    None => trait_.name == "Sized",
+                }
+        }
+        GenericBound::Outlives(_) => false,
+    };
+
+    for item in krate.index.values_mut() {
+        let generics = match &mut item.inner {
+            ItemEnum::Function(function) => &mut function.generics,
+            ItemEnum::Struct(struct_) => &mut struct_.generics,
+            ItemEnum::Enum(enum_) => &mut enum_.generics,
+            ItemEnum::Union(union_) => &mut union_.generics,
+            ItemEnum::Trait(trait_) => &mut trait_.generics,
+            ItemEnum::TraitAlias(trait_alias) => &mut trait_alias.generics,
+            ItemEnum::TypeAlias(type_alias) => &mut type_alias.generics,
+            ItemEnum::Impl(impl_) => &mut impl_.generics,
+            ItemEnum::OpaqueTy(opaque_ty) => &mut opaque_ty.generics,
+            ItemEnum::AssocType { generics, .. } => generics,
+            _ => continue,
+        };
+        rewrite_generics_bounds(generics, simplify, placement, &is_sized);
+    }
+}
+
+fn rewrite_generics_bounds(
+    generics: &mut Generics,
+    simplify: bool,
+    placement: BoundsPlacement,
+    is_sized: &dyn Fn(&GenericBound) -> bool,
+) {
+    // Type parameters written in the source (not the ones of the `impl Trait` arguments)
+    fn param_bounds<'a>(
+        params: &'a mut [GenericParamDef],
+        name: &str,
+    ) -> Option<&'a mut Vec<GenericBound>> {
+        params.iter_mut().find_map(|param| match &mut param.kind {
+            GenericParamDefKind::Type {
+                bounds,
+                synthetic: false,
+                ..
+            } if param.name == name => Some(bounds),
+            _ => None,
+        })
+    }
+    fn generic_name(type_: &Type, generic_params: &[GenericParamDef]) -> Option<String> {
+        match type_ {
+            Type::Generic(name) if generic_params.is_empty() => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    match placement {
+        BoundsPlacement::AsWritten => {}
+        BoundsPlacement::Inline => {
+            let params = &mut generics.params;
+            generics.where_predicates.retain_mut(|predicate| {
+                if let WherePredicate::BoundPredicate {
+                    type_,
+                    bounds,
+                    generic_params,
+                } = predicate
+                {
+                    if let Some(param_bounds) = generic_name(type_, generic_params)
+                        .and_then(|name| param_bounds(params, &name))
+                    {
+                        param_bounds.append(bounds);
+                        return false;
+                    }
+                }
+                true
+            });
+        }
+        BoundsPlacement::Where => {
+            let moved = generics
+                .params
+                .iter_mut()
+                .filter_map(|param| match &mut param.kind {
+                    GenericParamDefKind::Type {
+                        bounds,
+                        synthetic: false,
+                        ..
+                    } if !bounds.is_empty() => Some(WherePredicate::BoundPredicate {
+                        type_: Type::Generic(param.name.clone()),
+                        bounds: std::mem::take(bounds),
+                        generic_params: Vec::new(),
+                    }),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            generics.where_predicates.splice(0..0, moved);
+        }
+    }
+
+    if simplify {
+        // `Sized` is implicit for the type parameters (but not for `Self`)
+        for param in &mut generics.params {
+            if let GenericParamDefKind::Type { bounds, .. } = &mut param.kind {
+                bounds.retain(|bound| !is_sized(bound));
+                dedup_bounds(bounds);
+            }
+        }
+
+        let mut predicates: Vec<WherePredicate> = Vec::new();
+        for mut predicate in std::mem::take(&mut generics.where_predicates) {
+            if let WherePredicate::BoundPredicate {
+                type_,
+                bounds,
+                generic_params,
+            } = &mut predicate
+            {
+                if let Some(param_bounds) = generic_name(type_, generic_params)
+                    .and_then(|name| param_bounds(&mut generics.params, &name))
+                {
+                    bounds.retain(|bound| !is_sized(bound) && !param_bounds.contains(bound));
+                    if bounds.is_empty() {
+                        continue;
+                    }
+                }
+
+                // Fold the predicates on the same type
+                if let Some(WherePredicate::BoundPredicate {
+                    bounds: previous_bounds,
+                    ..
+                }) = predicates.iter_mut().find(|previous| {
+                    matches!(previous, WherePredicate::BoundPredicate {
+                        type_: previous_type,
+                        generic_params: previous_generic_params,
+                        ..
+                    } if previous_type == type_ && previous_generic_params == generic_params)
+                }) {
+                    previous_bounds.append(bounds);
+                    dedup_bounds(previous_bounds);
+                    continue;
+                }
+                dedup_bounds(bounds);
+            }
+            predicates.push(predicate);
+        }
+        generics.where_predicates = predicates;
+    }
+}
+
+/// Remove the duplicated bounds, keeping the first of them
+fn dedup_bounds(bounds: &mut Vec<GenericBound>) {
+    let mut index = 0;
+    while index < bounds.len() {
+        if bounds[..index].contains(&bounds[index]) {
+            bounds.remove(index);
+        } else {
+            index += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn type_(json: serde_json::Value) -> Type {
+        serde_json::from_value(json).expect("invalid type")
+    }
+
+    fn lifetimes(names: &[&str]) -> serde_json::Value {
+        names
+            .iter()
+            .map(|name| json!({ "name": name, "kind": { "lifetime": { "outlives": [] } } }))
+            .collect()
+    }
+
+    fn ref_u8(lifetime: &str) -> serde_json::Value {
+        json!({ "borrowed_ref": { "lifetime": lifetime, "mutable": false, "type": { "primitive": "u8" } } })
+    }
+
+    fn fn_trait(inputs: &[&str]) -> serde_json::Value {
+        json!({
+            "name": "Fn",
+            "id": "1:1",
+            "args": { "parenthesized": {
+                "inputs": inputs.iter().map(|lifetime| ref_u8(lifetime)).collect::<Vec<_>>(),
+                "output": null,
+            } },
+        })
+    }
+
+    #[test]
+    fn hrtb_dyn_trait() {
+        let type_ = type_(json!({ "dyn_trait": {
+            "traits": [
+                { "trait": fn_trait(&["'a"]), "generic_params": lifetimes(&["'a"]) },
+                { "trait": { "name": "Send", "id": "1:2", "args": null }, "generic_params": [] },
+            ],
+            "lifetime": "'static",
+        } }));
+
+        assert_eq!(
+            Tokens::from_type(&type_).unwrap().to_string(),
+            "dyn for<'a> Fn(&'a u8) + Send + 'static"
+        );
+    }
+
+    #[test]
+    fn hrtb_function_pointer() {
+        let type_ = type_(json!({ "function_pointer": {
+            "decl": {
+                "inputs": [["_", ref_u8("'a")], ["_", ref_u8("'b")]],
+                "output": ref_u8("'a"),
+                "c_variadic": false,
+            },
+            "generic_params": lifetimes(&["'a", "'b"]),
+            "header": { "const": false, "unsafe": true, "async": false, "abi": { "C": { "unwind": false } } },
+        } }));
+
+        assert_eq!(
+            Tokens::from_type(&type_).unwrap().to_string(),
+            "for<'a, 'b> unsafe extern \"C\" fn(&'a u8, &'b u8) -> &'a u8"
+        );
+    }
+
+    #[test]
+    fn hrtb_impl_trait() {
+        let type_ = type_(json!({ "impl_trait": [
+            { "trait_bound": {
+                "trait": fn_trait(&["'a", "'b"]),
+                "generic_params": lifetimes(&["'a", "'b"]),
+                "modifier": "none",
+            } },
+        ] }));
+
+        assert_eq!(
+            Tokens::from_type(&type_).unwrap().to_string(),
+            "impl for<'a, 'b> Fn(&'a u8, &'b u8)"
+        );
+    }
+
+    #[test]
+    fn hrtb_where_clauses() {
+        let item: Item = serde_json::from_value(json!({
+            "id": "0:1",
+            "crate_id": 0,
+            "name": "apply",
+            "span": null,
+            "visibility": "public",
+            "docs": null,
+            "links": {},
+            "attrs": [],
+            "deprecation": null,
+            "inner": { "function": {
+                "decl": { "inputs": [["f", { "generic": "F" }]], "output": null, "c_variadic": false },
+                "generics": {
+                    "params": [{ "name": "F", "kind": { "type": {
+                        "bounds": [],
+                        "default": null,
+                        "synthetic": false,
+                    } } }],
+                    "where_predicates": [
+                        { "bound_predicate": {
+                            "type": { "generic": "F" },
+                            "bounds": [{ "trait_bound": {
+                                "trait": fn_trait(&["'a", "'b"]),
+                                "generic_params": [],
+                                "modifier": "none",
+                            } }],
+                            "generic_params": lifetimes(&["'a", "'b"]),
+                        } },
+                        { "bound_predicate": {
+                            "type": { "generic": "F" },
+                            "bounds": [{ "trait_bound": {
+                                "trait": fn_trait(&["'c"]),
+                                "generic_params": lifetimes(&["'c"]),
+                                "modifier": "none",
+                            } }],
+                            "generic_params": [],
+                        } },
+                    ],
+                },
+                "header": { "const": false, "unsafe": false, "async": false, "abi": "Rust" },
+                "has_body": true,
+            } },
+        }))
+        .expect("invalid item");
+
+        let signature = Tokens::from_item(&item, &HashMap::new())
+            .unwrap()
+            .to_string();
+        assert!(
+            signature.contains("for<'a, 'b> F: Fn(&'a u8, &'b u8),"),
+            "{}",
+            signature
+        );
+        assert!(
+            signature.contains("F: for<'c> Fn(&'c u8),"),
+            "{}",
+            signature
+        );
+    }
+
+    #[test]
+    fn reused_buffer() {
+        let item: Item = serde_json::from_value(json!({
+            "id": "0:1",
+            "crate_id": 0,
+            "name": "run",
+            "span": null,
+            "visibility": "public",
+            "docs": null,
+            "links": {},
+            "attrs": [],
+            "deprecation": null,
+            "inner": { "function": {
+                "decl": { "inputs": [["a", { "primitive": "u8" }]], "output": null, "c_variadic": false },
+                "generics": { "params": [], "where_predicates": [] },
+                "header": { "const": false, "unsafe": false, "async": false, "abi": "Rust" },
+                "has_body": true,
+            } },
+        }))
+        .expect("invalid item");
+        let index = HashMap::new();
+
+        let buffer = Tokens::from_item(&item, &index).unwrap().into_buffer();
+        let (capacity, allocation) = (buffer.capacity(), buffer.as_ptr());
+        assert!(buffer.is_empty() && capacity > 0);
+
+        let tokens = Tokens::from_item_in(&item, &index, buffer).unwrap();
+        assert_eq!(tokens.as_ptr().cast::<()>(), allocation.cast::<()>());
+        assert_eq!(tokens.to_string(), "pub fn run(a: u8) { ... }");
+    }
+
+    fn bound(name: &str, id: &str) -> serde_json::Value {
+        json!({ "trait_bound": {
+            "trait": { "name": name, "id": id, "args": null },
+            "generic_params": [],
+            "modifier": "none",
+        } })
+    }
+
+    /// Signature of `fn run<T: params>(t: T) where predicates` with its bounds rewritten
+    fn rewritten(
+        params: serde_json::Value,
+        predicates: serde_json::Value,
+        simplify: bool,
+        placement: BoundsPlacement,
+    ) -> String {
+        let item = json!({
+            "id": "0:1",
+            "crate_id": 0,
+            "name": "run",
+            "span": null,
+            "visibility": "public",
+            "docs": null,
+            "links": {},
+            "attrs": [],
+            "deprecation": null,
+            "inner": { "function": {
+                "decl": { "inputs": [["t", { "generic": "T" }]], "output": null, "c_variadic": false },
+                "generics": {
+                    "params": [{ "name": "T", "kind": { "type": {
+                        "bounds": params,
+                        "default": null,
+                        "synthetic": false,
+                    } } }],
+                    "where_predicates": predicates,
+                },
+                "header": { "const": false, "unsafe": false, "async": false, "abi": "Rust" },
+                "has_body": true,
+            } },
+        });
+        let mut krate: Crate = serde_json::from_value(json!({
+            "root": "0:1",
+            "crate_version": null,
+            "includes_private": false,
+            "index": { "0:1": item },
+            "paths": { "1:1": { "crate_id": 1, "path": ["core", "marker", "Sized"], "kind": "trait" } },
+            "external_crates": {},
+            "format_version": 30,
+        }))
+        .expect("invalid crate");
+
+        rewrite_bounds(&mut krate, simplify, placement);
+        Tokens::from_item(&krate.index[&Id("0:1".to_string())], &HashMap::new())
+            .unwrap()
+            .to_string()
+    }
+
+    fn predicate(type_: &str, bounds: &[serde_json::Value]) -> serde_json::Value {
+        json!({ "bound_predicate": {
+            "type": { "generic": type_ },
+            "bounds": bounds,
+            "generic_params": [],
+        } })
+    }
+
+    #[test]
+    fn rewrite_bounds_as_written() {
+        let params = json!([bound("Sized", "1:1"), bound("Clone", "1:2")]);
+        let predicates = json!([predicate("T", &[bound("Clone", "1:2")])]);
+        assert_eq!(
+            rewritten(params, predicates, false, BoundsPlacement::AsWritten),
+            "pub fn run<T: Sized + Clone>(t: T)\nwhere\n    T: Clone,\n{ ... }"
+        );
+    }
+
+    #[test]
+    fn rewrite_bounds_simplified() {
+        let params = json!([
+            bound("Sized", "1:1"),
+            bound("Clone", "1:2"),
+            bound("Clone", "1:2")
+        ]);
+        let predicates = json!([
+            predicate("T", &[bound("Clone", "1:2"), bound("Debug", "1:3")]),
+            predicate("T", &[bound("Debug", "1:3"), bound("Send", "1:4")]),
+            predicate("Self", &[bound("Sized", "1:1")]),
+        ]);
+        assert_eq!(
+            rewritten(params, predicates, true, BoundsPlacement::AsWritten),
+            "pub fn run<T: Clone>(t: T)\nwhere\n    T: Debug + Send,\n    Self: Sized,\n{ ... }"
+        );
+    }
+
+    #[test]
+    fn rewrite_bounds_inline() {
+        let params = json!([bound("Clone", "1:2")]);
+        let predicates = json!([
+            predicate("T", &[bound("Debug", "1:3")]),
+            predicate("U", &[bound("Send", "1:4")]),
+        ]);
+        assert_eq!(
+            rewritten(params, predicates, false, BoundsPlacement::Inline),
+            "pub fn run<T: Clone + Debug>(t: T)\nwhere\n    U: Send,\n{ ... }"
+        );
+    }
+
+    #[test]
+    fn rewrite_bounds_where() {
+        let params = json!([bound("Sized", "1:1"), bound("Clone", "1:2")]);
+        let predicates = json!([predicate(
+            "T",
+            &[bound("Clone", "1:2"), bound("Debug", "1:3")]
+        )]);
+        assert_eq!(
+            rewritten(params, predicates, true, BoundsPlacement::Where),
+            "pub fn run<T>(t: T)\nwhere\n    T: Clone + Debug,\n{ ... }"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Minimal http server used to preview the generated documentation
+
+use anyhow::{Context as _, Result};
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use tiny_http::{Header, Request, Response, StatusCode};
+use tracing::{debug, info, warn};
+
+pub(crate) struct Server {
+    root: PathBuf,
+    port: u16,
+    server: tiny_http::Server,
+}
+
+impl Server {
+    /// Bind a server on localhost serving the files under `root`
+    pub(crate) fn bind(root: &Path, port: u16) -> Result<Self> {
+        let server = tiny_http::Server::http(("127.0.0.1", port))
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("unable to bind the http server on port {}", port))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            port,
+            server,
+        })
+    }
+
+    /// Url under which the given file (inside the root) is served
+    pub(crate) fn url_of(&self, path: &Path) -> Result<String> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .context("the path isn't under the served directory")?;
+
+        let mut url = format!("http://localhost:{}/", self.port);
+        for (i, component) in relative.components().enumerate() {
+            if i != 0 {
+                url.push('/');
+            }
+            url.push_str(&component.as_os_str().to_string_lossy());
+        }
+        Ok(url)
+    }
+
+    /// Serve the requests, forever
+    pub(crate) fn run(self) {
+        info!("serving {:?} on http://localhost:{}/", self.root, self.port);
+        for request in self.server.incoming_requests() {
+            if let Err(e) = respond(&self.root, request) {
+                warn!("unable to respond to a request: {}", e);
+            }
+        }
+    }
+}
+
+fn respond(root: &Path, request: Request) -> std::io::Result<()> {
+    debug!("{} {}", request.method(), request.url());
+
+    let path = match resolve(root, request.url()) {
+        Some(path) => path,
+        None => return request.respond(Response::empty(StatusCode(404))),
+    };
+
+    match File::open(&path) {
+        Ok(file) => {
+            let content_type = Header::from_bytes("Content-Type", mime_of(&path))
+                .expect("the content type should be a valid header");
+            request.respond(Response::from_file(file).with_header(content_type))
+        }
+        Err(_) => request.respond(Response::empty(StatusCode(404))),
+    }
+}
+
+/// Map the url of a request to a file under the root, refusing to escape it
+fn resolve(root: &Path, url: &str) -> Option<PathBuf> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    // Decoded before looking at its components, `%2e%2e` being `..`
+    let url = percent_decode(url)?;
+
+    let mut path = root.to_path_buf();
+    for component in Path::new(url.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if path.is_dir() {
+        path.push("index.html");
+    }
+    Some(path)
+}
+
+/// Decode the `%XX` escapes of the path of an url, `None` when they are invalid or don't
+/// give some utf-8
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes)
+        .ok()
+        .filter(|path| !path.contains('\0'))
+}
+
+fn mime_of(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") | Some("mjs") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("woff2") => "font/woff2",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_decoded_paths() {
+        let root = Path::new("/nonexistent-root");
+        assert_eq!(
+            resolve(root, "/krate/struct.Foo.html?search=x#method.bar"),
+            Some(root.join("krate/struct.Foo.html"))
+        );
+        assert_eq!(
+            resolve(root, "/krate/My%20Guide/caf%C3%A9.html"),
+            Some(root.join("krate/My Guide/café.html"))
+        );
+    }
+
+    #[test]
+    fn resolve_rejects_escapes() {
+        let root = Path::new("/nonexistent-root");
+        for url in [
+            "/../secret",
+            "/krate/%2e%2e/%2E%2E/secret",
+            "/krate%2F..%2F..%2Fsecret",
+            "/krate/%zz",
+            "/krate/%2",
+            "/krate/%ff",
+            "/krate/%00.html",
+        ] {
+            assert_eq!(resolve(root, url), None, "{}", url);
+        }
+    }
+}
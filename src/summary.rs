@@ -0,0 +1,177 @@
+//! Plain-text outline of the public API (`rd summary`), suitable for code review
+//! or to be stored as a golden file
+
+use anyhow::{Context as _, Result};
+use rustdoc_types::*;
+use std::io::Write;
+
+use crate::pp;
+
+/// Write the outline of the public API of the crate, module by module
+pub(crate) fn write(krate: &Crate, writer: &mut impl Write) -> Result<()> {
+    let root = get(krate, &krate.root)?;
+    let name = root.name.as_deref().context("expect a crate name")?;
+
+    write_module(krate, writer, name, root)
+}
+
+/// Write one line per item, its path and its signature on a single line separated by a
+/// tabulation, to be consumed by other tools
+pub(crate) fn write_signatures(krate: &Crate, writer: &mut impl Write) -> Result<()> {
+    let root = get(krate, &krate.root)?;
+    let name = root.name.as_deref().context("expect a crate name")?;
+
+    write_module_signatures(krate, writer, name, root)
+}
+
+fn write_module_signatures(
+    krate: &Crate,
+    writer: &mut impl Write,
+    path: &str,
+    item: &Item,
+) -> Result<()> {
+    let module = match &item.inner {
+        ItemEnum::Module(module) => module,
+        _ => unreachable!("write_module_signatures only takes modules"),
+    };
+
+    let mut items = module
+        .items
+        .iter()
+        .map(|id| get(krate, id))
+        .collect::<Result<Vec<_>>>()?;
+    items.sort_by(|x, y| x.name.cmp(&y.name));
+
+    for item in &items {
+        let name = match (&item.name, &item.inner) {
+            (Some(name), _) | (None, ItemEnum::Import(Import { name, .. })) => {
+                format!("{}::{}", path, name)
+            }
+            (None, _) => path.to_string(),
+        };
+
+        if is_module(item) {
+            write_module_signatures(krate, writer, &name, item)?;
+            continue;
+        }
+        write_signature(krate, writer, &name, item)?;
+
+        let impls = match &item.inner {
+            ItemEnum::Struct(Struct { impls, .. })
+            | ItemEnum::Enum(Enum { impls, .. })
+            | ItemEnum::Union(Union { impls, .. }) => impls.as_slice(),
+            _ => &[],
+        };
+        for id in impls {
+            if let ItemEnum::Impl(Impl {
+                trait_: None,
+                items,
+                ..
+            }) = &get(krate, id)?.inner
+            {
+                for id in items {
+                    let impl_item = get(krate, id)?;
+                    let item_name = impl_item.name.as_deref().unwrap_or_default();
+                    write_signature(
+                        krate,
+                        writer,
+                        &format!("{}::{}", name, item_name),
+                        impl_item,
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the path and the pretty-printed item, with its whitespaces collapsed
+fn write_signature(krate: &Crate, writer: &mut impl Write, path: &str, item: &Item) -> Result<()> {
+    let tokens = pp::Tokens::from_item(item, &krate.index)
+        .with_context(|| format!("unable to pretty-print the item {:?}", item.id))?;
+    let signature = tokens.to_string();
+
+    writeln!(
+        writer,
+        "{}\t{}",
+        path,
+        signature.split_whitespace().collect::<Vec<_>>().join(" ")
+    )?;
+    Ok(())
+}
+
+fn get<'krate>(krate: &'krate Crate, id: &Id) -> Result<&'krate Item> {
+    krate
+        .index
+        .get(id)
+        .with_context(|| format!("unable to find the item {:?}", id))
+}
+
+fn write_module(krate: &Crate, writer: &mut impl Write, path: &str, item: &Item) -> Result<()> {
+    let module = match &item.inner {
+        ItemEnum::Module(module) => module,
+        _ => unreachable!("write_module only takes modules"),
+    };
+
+    let mut items = module
+        .items
+        .iter()
+        .map(|id| get(krate, id))
+        .collect::<Result<Vec<_>>>()?;
+    items.sort_by(|x, y| x.name.cmp(&y.name));
+
+    writeln!(writer, "mod {}", path)?;
+    for item in items.iter().filter(|item| !is_module(item)) {
+        write_item(krate, writer, item, 1)?;
+
+        let impls = match &item.inner {
+            ItemEnum::Struct(Struct { impls, .. })
+            | ItemEnum::Enum(Enum { impls, .. })
+            | ItemEnum::Union(Union { impls, .. }) => impls.as_slice(),
+            _ => &[],
+        };
+        for id in impls {
+            let impl_item = get(krate, id)?;
+            if let ItemEnum::Impl(impl_) = &impl_item.inner {
+                if impl_.synthetic || impl_.blanket_impl.is_some() {
+                    continue;
+                }
+
+                write_item(krate, writer, impl_item, 2)?;
+                if impl_.trait_.is_none() {
+                    for id in &impl_.items {
+                        write_item(krate, writer, get(krate, id)?, 3)?;
+                    }
+                }
+            }
+        }
+    }
+    writeln!(writer)?;
+
+    for item in items.iter().filter(|item| is_module(item)) {
+        let name = item.name.as_deref().context("expect a module name")?;
+        write_module(krate, writer, &format!("{}::{}", path, name), item)?;
+    }
+
+    Ok(())
+}
+
+fn is_module(item: &Item) -> bool {
+    matches!(item.inner, ItemEnum::Module(_))
+}
+
+/// Write the pretty-printed item, each of its lines indented by `depth` levels
+fn write_item(krate: &Crate, writer: &mut impl Write, item: &Item, depth: usize) -> Result<()> {
+    let tokens = pp::Tokens::from_item(item, &krate.index)
+        .with_context(|| format!("unable to pretty-print the item {:?}", item.id))?;
+
+    for line in tokens.to_string().lines().map(str::trim_end) {
+        if line.is_empty() {
+            writeln!(writer)?;
+        } else {
+            writeln!(writer, "{:indent$}{}", "", line, indent = depth * 4)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! Watch mode: re-render the documentation when the input files change
+
+use anyhow::{Context as _, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use tungstenite::{Message, WebSocket};
+
+use crate::{html, output, render_file, Opt};
+
+/// Time to wait for other events before re-rendering (editors tend to write in multiple steps)
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Minimal websocket server notifying the connected pages that they should reload
+struct LiveReload {
+    clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>>,
+}
+
+impl LiveReload {
+    fn start(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("unable to bind the livereload server on port {}", port))?;
+        info!("livereload server listening on ws://localhost:{}", port);
+
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&clients);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream.map_err(anyhow::Error::from).and_then(|stream| {
+                    tungstenite::accept(stream).map_err(|e| anyhow::anyhow!("{}", e))
+                }) {
+                    Ok(websocket) => {
+                        debug!("new livereload client");
+                        accepted.lock().unwrap().push(websocket);
+                    }
+                    Err(e) => warn!("unable to accept a livereload client: {}", e),
+                }
+            }
+        });
+
+        Ok(Self { clients })
+    }
+
+    fn reload(&self) {
+        let mut clients = self.clients.lock().unwrap();
+        debug!("sending reload to {} livereload client(s)", clients.len());
+        clients.retain_mut(|client| client.send(Message::Text("reload".into())).is_ok());
+    }
+}
+
+/// Watch the input files and re-render the ones that changed, until interrupted
+pub(crate) fn watch(opt: &Opt) -> Result<()> {
+    let livereload = opt.livereload.map(LiveReload::start).transpose()?;
+
+    // Watch the parent directories rather than the files themselves, so that
+    // files replaced (and not modified in place) are still picked up
+    let mut files = HashMap::new();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("unable to create the watcher")?;
+    for file in &opt.files {
+        let canonical = file
+            .canonicalize()
+            .with_context(|| format!("unable to canonicalize {:?}", file))?;
+        let parent = canonical
+            .parent()
+            .context("input file without a parent directory")?;
+        watcher
+            .watch(parent, RecursiveMode::NonRecursive)
+            .with_context(|| format!("unable to watch {:?}", parent))?;
+        files.insert(canonical, file);
+    }
+
+    info!("watching {} file(s) for changes", files.len());
+    while let Ok(event) = rx.recv() {
+        let mut changed: Vec<PathBuf> = Vec::new();
+        let mut event = Some(event);
+        while let Some(res) = event.take() {
+            match res {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    changed.extend(event.paths)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("watch error: {}", e),
+            }
+            event = rx.recv_timeout(DEBOUNCE).ok();
+        }
+
+        let mut outputs = Vec::new();
+        for (canonical, file) in &files {
+            if changed.iter().any(|path| path == canonical) {
+                info!("{:?} changed, re-rendering", file);
+                match render_file(opt, file) {
+                    Ok(output) => outputs.push(output),
+                    Err(e) => error!("{:#}", e),
+                }
+            }
+        }
+
+        if !outputs.is_empty() {
+            html::render::render_global(opt, &outputs)
+                .context("Unable to write the global context (js, css, imgs, ...)")?;
+            if opt.manifest {
+                output::write_manifest(&opt.output)?;
+            }
+            if let Some(livereload) = &livereload {
+                livereload.reload();
+            }
+        }
+    }
+
+    Ok(())
+}
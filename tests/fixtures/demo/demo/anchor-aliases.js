@@ -0,0 +1 @@
+const RD_ANCHOR_ALIASES = {"demo/inner/struct.Foo.html":{"fn.run":"impl-Tr-for-Foo.fn.run","method.run":"impl-Tr-for-Foo.fn.run"},"demo/struct.Foo.html":{"associatedconst.ID":"impl-Tr-for-Foo.associatedconst.ID","associatedconstant.ID":"impl-Tr-for-Foo.associatedconst.ID","associatedtype.Item":"impl-Iterator-for-Foo.associatedtype.Item","associatedtype.Out":"impl-Tr-for-Foo.associatedtype.Out","fn.next":"impl-Iterator-for-Foo.fn.next","fn.run":"impl-Tr-for-Foo.fn.run","fn.size_hint":"impl-Iterator-for-Foo.fn.size_hint","method.new":"fn.new","method.next":"impl-Iterator-for-Foo.fn.next","method.raw":"fn.raw","method.run":"impl-Tr-for-Foo.fn.run","method.size_hint":"impl-Iterator-for-Foo.fn.size_hint","method.with":"fn.with"},"demo/trait.Tr.html":{"associatedconstant.ID":"associatedconst.ID","method.run":"impl-Tr-for-Foo.fn.run","method.walk":"fn.walk","tymethod.run":"fn.run"}};
@@ -0,0 +1,7 @@
+
+
+const INDEX = JSON.parse('[{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"}],"filepath":"demo/index.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Alias","lower_case_name":"alias","kind":"type"}],"filepath":"demo/type.Alias.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Ext","lower_case_name":"ext","kind":"foreigntype"}],"filepath":"demo/foreigntype.Ext.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Foo","lower_case_name":"foo","kind":"struct"}],"filepath":"demo/struct.Foo.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Iter","lower_case_name":"iter","kind":"opaque"}],"filepath":"demo/opaque.Iter.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Kind","lower_case_name":"kind","kind":"enum"}],"filepath":"demo/enum.Kind.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"MAX","lower_case_name":"max","kind":"constant"}],"filepath":"demo/constant.MAX.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Pair","lower_case_name":"pair","kind":"struct"}],"filepath":"demo/struct.Pair.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"Tr","lower_case_name":"tr","kind":"trait"}],"filepath":"demo/trait.Tr.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"add","lower_case_name":"add","kind":"fn"}],"filepath":"demo/fn.add.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"apply","lower_case_name":"apply","kind":"fn"}],"filepath":"demo/fn.apply.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"inner","lower_case_name":"inner","kind":"mod"}],"filepath":"demo/inner/index.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"inner","lower_case_name":"inner","kind":"mod"},{"name":"Foo","lower_case_name":"foo","kind":"struct"}],"filepath":"demo/inner/struct.Foo.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"inner","lower_case_name":"inner","kind":"mod"},{"name":"danger","lower_case_name":"danger","kind":"fn"}],"filepath":"demo/inner/fn.danger.html"},{"components":[{"name":"demo","lower_case_name":"demo","kind":"mod"},{"name":"pick","lower_case_name":"pick","kind":"fn"}],"filepath":"demo/fn.pick.html"}]');
+
+const INDEX_PREVIEWS = [{"summary":"The demo crate.","signature":null,"since":null},{"summary":null,"signature":"pub type Alias = u32;","since":null},{"summary":"An extern type.","signature":"extern {\n    pub type Ext;\n}","since":null},{"summary":"A foo struct. It is used for things.","signature":"#[must_use]\npub struct Foo {\n    pub a: u32,\n}","since":null},{"summary":"An opaque iterator.","signature":"pub type Iter = impl Iterator;","since":null},{"summary":"Kinds of things.","signature":"pub enum Kind {\n    A,\n    B(u32),\n}","since":null},{"summary":"The max.","signature":"pub const MAX: u32 = _;","since":null},{"summary":"A pair.","signature":"pub struct Pair(pub u32, pub u32);","since":null},{"summary":"A trait.","signature":"pub trait Tr {\n    fn run(&Self);\n\n    async fn walk(&Self) { ... }\n\n    const ID: u32 = 0;\n\n    type Out;\n}","since":null},{"summary":"Adds two numbers together. Returns the sum.","signature":"#[must_use = \"the sum is returned, not stored\"]\npub const fn add(a: u32, b: u32) -> u32 { ... }","since":"1.2.0"},{"summary":"Applies the trait.","signature":"pub fn apply
+<T: Tr>(t: T) { ... }","since":null},{"summary":"Inner module.","signature":null,"since":null},{"summary":"An other foo, named like the one of the crate root.","signature":"pub struct Foo;","since":null},{"summary":"Dangerous.","signature":"pub unsafe fn danger() { ... }","since":null},{"summary":"Picks a thing.","signature":"pub fn pick
+<T: Tr + Sized + Tr>(t: T) -> T\nwhere\n    T: Send,\n    T: Sized + Tr + Send,\n{ ... }","since":null}];